@@ -0,0 +1,155 @@
+use std::{
+    fmt::{self, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    error::Error,
+    message::{CommitType, ConventionalMessage},
+};
+
+/// Semantic version increment required by a set of changes, from the smallest to the largest
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Bump {
+    /// Nothing to release, e.g. only documentation or CI changes
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Get the increment required by the message: major for breaking changes, minor for
+    /// features, none for the types not shipping anything, patch for the others
+    pub fn of(message: &ConventionalMessage) -> Self {
+        if message.is_breaking {
+            return Self::Major;
+        }
+        match &message.ctype {
+            CommitType::Feature => Self::Minor,
+            CommitType::Documentation
+            | CommitType::Style
+            | CommitType::Test
+            | CommitType::Build
+            | CommitType::ContinuousIntegration
+            | CommitType::Merge => Self::None,
+            CommitType::Other(other) if other.eq_ignore_ascii_case("chore") => Self::None,
+            _ => Self::Patch,
+        }
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bump = match self {
+            Self::None => "none",
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        };
+        write!(f, "{}", bump)
+    }
+}
+
+/// `X.Y.Z` version, with an optional `v` prefix kept when displayed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Version {
+    prefix: bool,
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl Version {
+    /// Get the next version after the given increment
+    pub fn bump(&self, bump: Bump) -> Self {
+        let (major, minor, patch) = match bump {
+            Bump::None => (self.major, self.minor, self.patch),
+            Bump::Patch => (self.major, self.minor, self.patch + 1),
+            Bump::Minor => (self.major, self.minor + 1, 0),
+            Bump::Major => (self.major + 1, 0, 0),
+        };
+        Self {
+            prefix: self.prefix,
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidVersion(s.to_owned());
+        let (prefix, version) = match s.strip_prefix('v') {
+            Some(version) => (true, version),
+            None => (false, s),
+        };
+        let numbers = version
+            .split('.')
+            .map(|number| number.parse().map_err(|_| invalid()))
+            .collect::<Result<Vec<u64>, _>>()?;
+        match numbers[..] {
+            [major, minor, patch] => Ok(Self {
+                prefix,
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.prefix {
+            write!(f, "v")?;
+        }
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bump_of_message() {
+        let cases = [
+            ("feat(api)!: drop v1", Bump::Major),
+            ("fix!: rename", Bump::Major),
+            ("feat: add", Bump::Minor),
+            ("fix: crash", Bump::Patch),
+            ("perf: faster", Bump::Patch),
+            ("docs: readme", Bump::None),
+            ("chore: bump", Bump::None),
+        ];
+        for (raw, expected) in &cases {
+            assert_eq!(*expected, Bump::of(&raw.parse().unwrap()), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_bump_version() {
+        let version: Version = "v1.4.2".parse().unwrap();
+        assert_eq!("v2.0.0", version.bump(Bump::Major).to_string());
+        assert_eq!("v1.5.0", version.bump(Bump::Minor).to_string());
+        assert_eq!("v1.4.3", version.bump(Bump::Patch).to_string());
+        assert_eq!("v1.4.2", version.bump(Bump::None).to_string());
+        assert_eq!(
+            "0.2.0",
+            "0.1.9"
+                .parse::<Version>()
+                .unwrap()
+                .bump(Bump::Minor)
+                .to_string()
+        );
+
+        for invalid in &["1.2", "1.2.3-rc.1", "x.y.z", ""] {
+            assert!(invalid.parse::<Version>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -1,47 +1,163 @@
-use std::{fmt, hash::Hash, str::FromStr};
+use std::{cmp::Ordering, fmt, hash::Hash, io::Write, path::Path, str::FromStr};
 
+use chrono::{DateTime, FixedOffset};
+use console::Style;
 use indexmap::map::IndexMap;
-use serde::Serialize;
+use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+use tera::{Context, Tera};
 
 use crate::{
+    bump::Bump,
     error::{Error, Result},
-    message::ConventionalMessage,
-    snapshots::{BranchName, RepositoryOrigin},
+    keep_a_changelog::{self, SectionMapping},
+    message::{normalize_body, Author, CommitType, ConventionalMessage},
+    ordering::{EntryOrder, GroupOrder, COMMIT_TYPES_PRIORITY},
+    report::{Report, ReportMeta},
+    snapshots::{BranchName, CommitHash, RepositoryOrigin},
+    tabular,
 };
 use std::fmt::Debug;
 
-#[derive(Clone, Serialize)]
+/// Metadata of the commit an entry comes from
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommitMetadata {
+    /// Hash of the commit, missing from the run reports saved before it was captured
+    #[serde(
+        rename = "commit",
+        alias = "hash",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hash: Option<CommitHash>,
+    /// Date of the commit, in its committer's timezone
+    pub date: DateTime<FixedOffset>,
+    pub author: Author,
+    pub committer: Author,
+}
+
+/// Parsed message of a commit, along with where it comes from
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ChangeLogEntry {
     origin: RepositoryOrigin,
     branch: BranchName,
+    #[serde(flatten)]
+    commit: CommitMetadata,
     message: ConventionalMessage,
+    /// Link to the commit, when origin's commits can be linked to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
 }
 
 impl ChangeLogEntry {
-    pub fn new(origin: RepositoryOrigin, branch: BranchName, message: ConventionalMessage) -> Self {
+    pub fn new(
+        origin: RepositoryOrigin,
+        branch: BranchName,
+        commit: CommitMetadata,
+        message: ConventionalMessage,
+    ) -> Self {
         Self {
             origin,
             branch,
+            commit,
             message,
+            url: None,
         }
     }
 
+    /// Set the link to the commit
+    pub fn with_url(mut self, url: Option<String>) -> Self {
+        self.url = url;
+        self
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn date(&self) -> &DateTime<FixedOffset> {
+        &self.commit.date
+    }
+
+    pub fn hash(&self) -> Option<&CommitHash> {
+        self.commit.hash.as_ref()
+    }
+
     pub fn get(&self, field: &CommitField) -> &str {
         use CommitField::*;
         match field {
             Scope => self
                 .message
-                .scope
-                .as_ref()
+                .scopes
+                .first()
                 .map(|scope| scope.as_str())
                 .unwrap_or(""),
             Branch => self.branch.as_str(),
             Origin => self.origin.as_str(),
             CommitType => self.message.ctype.as_str(),
+            Author => self.commit.author.name.as_str(),
+            Committer => self.commit.committer.name.as_str(),
+            Breaking => {
+                if self.message.is_breaking {
+                    "breaking"
+                } else {
+                    "non-breaking"
+                }
+            }
+            Trailer(token) => self
+                .message
+                .trailers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(token))
+                .map(|(_, value)| value.as_str())
+                .unwrap_or(""),
         }
     }
+
+    /// Get all the keys of the entry for the given field: each of its scopes for `Scope`,
+    /// the single key returned by `get` otherwise
+    pub fn get_all(&self, field: &CommitField) -> Vec<&str> {
+        match field {
+            CommitField::Scope if self.message.scopes.len() > 1 => self
+                .message
+                .scopes
+                .iter()
+                .map(|scope| scope.as_str())
+                .collect(),
+            _ => vec![self.get(field)],
+        }
+    }
+}
+
+impl AsRef<ConventionalMessage> for ChangeLogEntry {
+    fn as_ref(&self) -> &ConventionalMessage {
+        &self.message
+    }
 }
 
+/// Descriptions of group keys, by group-by field (e.g. `trailer:Epic`) then by key
+pub type KeyDescriptions = IndexMap<String, IndexMap<String, String>>;
+
+/// Display titles of the commit types, by type key (e.g. `security: "🔒 Security"`)
+pub type TypeTitles = IndexMap<String, String>;
+
+/// Canonical commit types, by alias (e.g. `bugfix: fix`)
+pub type TypeAliases = IndexMap<String, String>;
+
+/// Group-by fields of the entries of some origins, below their origin's group
+pub type GroupByOverrides = IndexMap<String, Vec<CommitField>>;
+
+/// Names of the projects, by origin
+pub type ProjectNames = IndexMap<String, String>;
+
+/// Comparator of the keys of a level of groups
+pub type KeyComparator<K> = Box<dyn Fn(&K, &K) -> Ordering>;
+
+/// Values grouped by keys, level by level.
+///
+/// Indexes are serialized as maps and buckets as sequences, so that a serialized changelog
+/// deserializes back into its index unambiguously. The `description`/`entries` nodes of the
+/// described keys deserialize as their entries, dropping the description: an index's values
+/// are never strings, so these nodes can't be mistaken for an index.
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum HierarchicalBuckets<K, V>
@@ -53,7 +169,42 @@ where
     Bucket(Vec<V>),
 }
 
-impl<'de, K, V> HierarchicalBuckets<K, V>
+/// Serialized forms of a node of `HierarchicalBuckets`, described key's node included
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    bound(deserialize = "K: Eq + Hash + Serialize + Deserialize<'de>, \
+                         V: Serialize + Deserialize<'de>")
+)]
+enum SerializedBuckets<K, V>
+where
+    K: Eq + Hash + Serialize,
+    V: Serialize,
+{
+    Described {
+        #[allow(dead_code)]
+        description: String,
+        entries: HierarchicalBuckets<K, V>,
+    },
+    Index(IndexMap<K, HierarchicalBuckets<K, V>>),
+    Bucket(Vec<V>),
+}
+
+impl<'de, K, V> Deserialize<'de> for HierarchicalBuckets<K, V>
+where
+    K: Eq + Hash + Serialize + Deserialize<'de>,
+    V: Serialize + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(match SerializedBuckets::deserialize(deserializer)? {
+            SerializedBuckets::Described { entries, .. } => entries,
+            SerializedBuckets::Index(index) => Self::Index(index),
+            SerializedBuckets::Bucket(bucket) => Self::Bucket(bucket),
+        })
+    }
+}
+
+impl<K, V> HierarchicalBuckets<K, V>
 where
     K: Debug + Eq + Hash + Serialize,
     V: Serialize,
@@ -63,6 +214,106 @@ where
         self.insert_helper(keys, value)
     }
 
+    /// Get the keys shared by all the values, with the depth of their level in the index
+    pub fn constant_keys(&self) -> Vec<(usize, &K)> {
+        let mut levels = Vec::new();
+        self.collect_keys(0, &mut levels);
+        levels
+            .into_iter()
+            .enumerate()
+            .filter(|(_, keys)| keys.len() == 1)
+            .map(|(depth, keys)| (depth, keys[0]))
+            .collect()
+    }
+
+    /// Collect the distinct keys of each level of the index
+    fn collect_keys<'a>(&'a self, depth: usize, levels: &mut Vec<Vec<&'a K>>) {
+        if let HierarchicalBuckets::Index(index) = self {
+            if levels.len() <= depth {
+                levels.push(Vec::new());
+            }
+            for (key, child) in index {
+                if !levels[depth].contains(&key) {
+                    levels[depth].push(key);
+                }
+                child.collect_keys(depth + 1, levels);
+            }
+        }
+    }
+
+    /// Remove a level of the index made of single-key indexes, replacing them by their child
+    pub fn flatten_level(&mut self, depth: usize) -> Result<()> {
+        match self {
+            HierarchicalBuckets::Index(index) if depth == 0 => {
+                if index.len() != 1 {
+                    return Err(Error::InvalidIndex(format!(
+                        "expected a single key to flatten, found {}",
+                        index.len()
+                    )));
+                }
+                let (_, child) = index.pop().expect("index has one key");
+                *self = child;
+                Ok(())
+            }
+            HierarchicalBuckets::Index(index) => {
+                for child in index.values_mut() {
+                    child.flatten_level(depth - 1)?;
+                }
+                Ok(())
+            }
+            HierarchicalBuckets::Bucket(_) => Err(Error::InvalidIndex(
+                "expected index to flatten, found bucket".to_string(),
+            )),
+        }
+    }
+
+    /// Sort the keys of each level of the index with the comparator of its depth, keeping
+    /// the insertion order of the levels without comparator
+    pub fn sort_levels_by(&mut self, comparators: &[Option<KeyComparator<K>>]) {
+        if let (HierarchicalBuckets::Index(index), Some((compare, comparators))) =
+            (self, comparators.split_first())
+        {
+            if let Some(compare) = compare {
+                index.sort_by(|a, _, b, _| compare(a, b));
+            }
+            for child in index.values_mut() {
+                child.sort_levels_by(comparators);
+            }
+        }
+    }
+
+    /// Stably sort the values of all the buckets
+    pub fn sort_values_by(&mut self, compare: fn(&V, &V) -> Ordering) {
+        match self {
+            HierarchicalBuckets::Index(index) => {
+                for child in index.values_mut() {
+                    child.sort_values_by(compare);
+                }
+            }
+            HierarchicalBuckets::Bucket(bucket) => bucket.sort_by(compare),
+        }
+    }
+
+    /// Iterate over the values of all the buckets
+    pub fn values(&self) -> Box<dyn Iterator<Item = &V> + '_> {
+        match self {
+            HierarchicalBuckets::Index(index) => {
+                Box::new(index.values().flat_map(|child| child.values()))
+            }
+            HierarchicalBuckets::Bucket(bucket) => Box::new(bucket.iter()),
+        }
+    }
+
+    /// Iterate mutably over the values of all the buckets
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut V> + '_> {
+        match self {
+            HierarchicalBuckets::Index(index) => {
+                Box::new(index.values_mut().flat_map(|child| child.values_mut()))
+            }
+            HierarchicalBuckets::Bucket(bucket) => Box::new(bucket.iter_mut()),
+        }
+    }
+
     fn insert_helper(&mut self, mut keys: Vec<K>, value: V) -> Result<()> {
         match (keys.pop(), self) {
             (Some(key), HierarchicalBuckets::Index(index)) => {
@@ -95,9 +346,23 @@ where
     }
 }
 
+/// Key of the entries without value for a group-by field, like the ones without scope
+pub const DEFAULT_EMPTY_KEY: &str = "(none)";
+
+/// Entries grouped by the keys of the group-by fields, level by level
 pub struct ChangeLog {
     group_by: Vec<CommitField>,
+    /// Key replacing the empty ones
+    empty_key: String,
+    group_by_overrides: GroupByOverrides,
+    project_names: ProjectNames,
     index: HierarchicalBuckets<String, ChangeLogEntry>,
+    key_descriptions: KeyDescriptions,
+    type_titles: TypeTitles,
+    /// Order of the commit type groups, their priority if empty
+    type_order: Vec<String>,
+    sections: SectionMapping,
+    counts: EntryCounts,
 }
 
 impl ChangeLog {
@@ -108,30 +373,690 @@ impl ChangeLog {
             HierarchicalBuckets::Index(IndexMap::new())
         };
 
-        Self { group_by, index }
+        Self {
+            group_by,
+            empty_key: DEFAULT_EMPTY_KEY.to_owned(),
+            group_by_overrides: GroupByOverrides::new(),
+            project_names: ProjectNames::new(),
+            index,
+            key_descriptions: KeyDescriptions::new(),
+            type_titles: TypeTitles::new(),
+            type_order: Vec::new(),
+            sections: SectionMapping::new(),
+            counts: EntryCounts::default(),
+        }
+    }
+
+    /// Set the key of the entries without value for a group-by field, instead of `(none)`.
+    /// An empty one keeps their empty key. Must be set before inserting entries.
+    pub fn with_empty_key(mut self, empty_key: String) -> Self {
+        self.empty_key = empty_key;
+        self
+    }
+
+    pub fn with_key_descriptions(mut self, key_descriptions: KeyDescriptions) -> Self {
+        self.key_descriptions = key_descriptions;
+        self
+    }
+
+    /// Set the titles of the commit types, displayed instead of their keys by the pretty
+    /// output and overriding the default ones of the HTML and template outputs
+    pub fn with_type_titles(mut self, type_titles: TypeTitles) -> Self {
+        self.type_titles = type_titles;
+        self
+    }
+
+    /// Set the order of the commit type groups, instead of their priority. The unlisted
+    /// types follow in natural order.
+    pub fn with_type_order(mut self, type_order: Vec<String>) -> Self {
+        self.type_order = type_order;
+        self
     }
 
+    /// Set the Keep a Changelog sections of the commit types, overriding the default ones
+    pub fn with_sections(mut self, sections: SectionMapping) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Set the names of the projects, displayed instead of their origin keys by the pretty and
+    /// HTML outputs
+    pub fn with_project_names(mut self, project_names: ProjectNames) -> Self {
+        self.project_names = project_names;
+        self
+    }
+
+    /// Group the entries of the given origins by their own fields. The entries are then
+    /// grouped by origin first, the other origins' entries by the remaining group-by fields.
+    /// Must be set before inserting entries.
+    pub fn with_group_by_overrides(mut self, group_by_overrides: GroupByOverrides) -> Self {
+        if !group_by_overrides.is_empty() {
+            self.group_by.retain(|field| *field != CommitField::Origin);
+            self.group_by.insert(0, CommitField::Origin);
+            self.index = HierarchicalBuckets::Index(IndexMap::new());
+            self.group_by_overrides = group_by_overrides
+                .into_iter()
+                .map(|(origin, mut group_by)| {
+                    group_by.retain(|field| *field != CommitField::Origin);
+                    (origin, group_by)
+                })
+                .collect();
+        }
+        self
+    }
+
+    /// Insert the entry in the bucket of its keys. An entry with several scopes is inserted
+    /// in the bucket of each of them when grouping by scope.
     pub fn insert(&mut self, entry: ChangeLogEntry) -> Result<()> {
-        let keys = self
-            .group_by
-            .iter()
-            .map(|field| entry.get(field).to_owned())
-            .collect();
-        self.index.insert(keys, entry)?;
+        let group_by = match self.group_by_overrides.get(entry.origin.as_str()) {
+            Some(group_by) => [&[CommitField::Origin], &group_by[..]].concat(),
+            None => self.group_by.clone(),
+        };
+        let empty_key = self.empty_key.as_str();
+        let mut keys_combinations: Vec<Vec<String>> = vec![Vec::new()];
+        for field in &group_by {
+            let field_keys = entry.get_all(field);
+            keys_combinations = keys_combinations
+                .into_iter()
+                .flat_map(|keys| {
+                    field_keys.iter().map(move |key| {
+                        let key = if key.is_empty() { empty_key } else { key };
+                        let mut keys = keys.clone();
+                        keys.push(key.to_string());
+                        keys
+                    })
+                })
+                .collect();
+        }
+        for keys in keys_combinations {
+            self.index.insert(keys, entry.clone())?;
+        }
+        self.counts.add(&entry);
         Ok(())
     }
 
+    /// Sort the keys of each level, the commit types by the configured order if any
+    pub fn sort_groups(&mut self, order: GroupOrder) {
+        let type_order = &self.type_order;
+        let comparators = |group_by: &[CommitField]| -> Vec<_> {
+            group_by
+                .iter()
+                .map(|field| order.field_comparator(field, type_order))
+                .collect()
+        };
+        match &mut self.index {
+            HierarchicalBuckets::Index(index) if !self.group_by_overrides.is_empty() => {
+                if let Some(compare) = order.field_comparator(&CommitField::Origin, type_order) {
+                    index.sort_by(|a, _, b, _| compare(a, b));
+                }
+                for (origin, child) in index.iter_mut() {
+                    let group_by = child_group_by(
+                        &self.group_by_overrides,
+                        &CommitField::Origin,
+                        origin,
+                        &self.group_by[1..],
+                    );
+                    child.sort_levels_by(&comparators(group_by));
+                }
+            }
+            index => index.sort_levels_by(&comparators(&self.group_by)),
+        }
+    }
+
+    pub fn sort_entries(&mut self, order: EntryOrder) {
+        self.index.sort_values_by(order.comparator());
+    }
+
+    /// Clean up the entries' bodies for rendering, hard-wrapping them at `wrap` characters.
+    /// See [`normalize_body`].
+    pub fn normalize_bodies(&mut self, wrap: Option<usize>) {
+        for entry in self.index.values_mut() {
+            if let Some(body) = &mut entry.message.body {
+                *body = normalize_body(body, wrap);
+            }
+        }
+    }
+
+    /// Render the changelog for terminals, indenting each level of groups. Styles are
+    /// applied only if `colors` is set.
+    pub fn to_pretty(&self, colors: bool) -> String {
+        let mut output = String::new();
+        write_pretty(&mut output, self, &self.index, &self.group_by, 0, colors);
+        output
+    }
+
     pub fn to_yaml(&self) -> Result<String> {
-        Ok(serde_yaml::to_string(&self.index)?)
+        let mut output = Vec::new();
+        self.write_yaml(&mut output)?;
+        Ok(String::from_utf8(output).expect("unfailable"))
+    }
+
+    /// Write the YAML of the changelog to `writer` as it's serialized, without building it
+    pub fn write_yaml<W: Write>(&self, writer: W) -> Result<()> {
+        Ok(serde_yaml::to_writer(writer, self)?)
+    }
+
+    /// Iterate over the entries, as many times as they are in buckets
+    pub fn entries(&self) -> impl Iterator<Item = &ChangeLogEntry> {
+        self.index.values()
+    }
+
+    /// Whether no entry was inserted
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of inserted entries, once each even if they are in several buckets
+    pub fn len(&self) -> usize {
+        self.counts.total
+    }
+
+    /// Suggest the semantic version increment required by the entries, the largest of theirs
+    pub fn suggest_bump(&self) -> Bump {
+        self.index
+            .values()
+            .map(|entry| Bump::of(&entry.message))
+            .max()
+            .unwrap_or(Bump::None)
+    }
+
+    /// Render all the entries as a Keep a Changelog release block, whatever the grouping,
+    /// along with their bodies if `with_bodies`
+    pub fn to_keep_a_changelog(&self, title: &str, with_bodies: bool) -> String {
+        keep_a_changelog::render(title, self.index.values(), &self.sections, with_bodies)
+    }
+
+    /// Render all the entries as CSV, or TSV with a tab `delimiter`, whatever the grouping.
+    /// See [`tabular::render`].
+    pub fn to_csv(&self, delimiter: u8) -> Result<String> {
+        tabular::render(self.index.values(), delimiter)
+    }
+
+    /// Write the CSV of the entries to `writer` row by row. See [`tabular::write`].
+    pub fn write_csv<W: Write>(&self, delimiter: u8, writer: W) -> Result<()> {
+        tabular::write(self.index.values(), delimiter, writer)
+    }
+
+    /// Render the changelog as HTML: each group as a `<section>` titled by a heading one level
+    /// deeper than its parent's, from `<h2>`, and the entries as a list. Breaking changes are
+    /// marked by a `<span class="breaking">`.
+    pub fn to_html(&self) -> String {
+        let mut output = String::new();
+        write_html(&mut output, self, &self.index, &self.group_by, 0);
+        output
+    }
+
+    /// Get the title of a commit type key in the rendered outputs: its configured title, its
+    /// default one if it's a known type, the key itself otherwise
+    pub fn type_title<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.type_titles.get(key) {
+            Some(title) => title,
+            None => key
+                .parse::<CommitType>()
+                .ok()
+                .and_then(|ctype| ctype.title())
+                .unwrap_or(key),
+        }
+    }
+
+    /// Get the label of an origin key in the rendered outputs: its project's name, the origin
+    /// itself otherwise
+    pub fn origin_name<'a>(&'a self, key: &'a str) -> &'a str {
+        self.project_names.get(key).map_or(key, String::as_str)
+    }
+
+    /// Get the titles of the known commit types and of the configured ones, by type key
+    fn all_type_titles(&self) -> TypeTitles {
+        let mut titles: TypeTitles = COMMIT_TYPES_PRIORITY
+            .iter()
+            .filter_map(|ctype| Some((ctype.as_str().to_owned(), ctype.title()?.to_owned())))
+            .collect();
+        titles.extend(self.type_titles.clone());
+        titles
+    }
+
+    /// Render the changelog with the given Tera template, without meta block. See
+    /// [`Report::to_template`].
+    pub fn to_template(&self, path: &Path) -> Result<String> {
+        Report {
+            meta: ReportMeta::default(),
+            changes: self,
+            with_meta: false,
+            with_bodies: false,
+        }
+        .to_template(path)
+    }
+
+    /// Render the changelog with the given Tera template and additional context. The
+    /// template receives the `changelog`, its `stats`, the commit types' titles as
+    /// `type_titles` and the projects' names by origin as `project_names` too, and can
+    /// clean up bodies with the `normalize_body` filter, given an optional `width` to wrap at.
+    pub fn render_template(&self, path: &Path, mut context: Context) -> Result<String> {
+        let mut tera = Tera::default();
+        tera.register_filter("normalize_body", normalize_body_filter);
+        tera.add_template_file(path, Some("changelog"))?;
+        context.insert("changelog", self);
+        context.insert("stats", &self.stats());
+        context.insert("type_titles", &self.all_type_titles());
+        context.insert("project_names", &self.project_names);
+        Ok(tera.render("changelog", &context)?)
+    }
+
+    /// Count the entries, once each even if they are in several buckets, and the entries
+    /// of each group
+    pub fn stats(&self) -> ChangeLogStats {
+        ChangeLogStats {
+            counts: self.counts.clone(),
+            groups: GroupStats::from(&self.index),
+        }
+    }
+
+    /// List the group-by fields sharing the same key for all the entries. Levels don't
+    /// match fields with group-by overrides, whose fields are never reported.
+    pub fn constant_fields(&self) -> Vec<(CommitField, String)> {
+        if !self.group_by_overrides.is_empty() {
+            return Vec::new();
+        }
+        self.index
+            .constant_keys()
+            .into_iter()
+            .map(|(depth, key)| (self.group_by[depth].clone(), key.clone()))
+            .collect()
+    }
+
+    /// Remove the levels of the constant group-by fields, and return these fields. Nothing is
+    /// flattened with group-by overrides.
+    pub fn flatten_constant_levels(&mut self) -> Result<Vec<CommitField>> {
+        if !self.group_by_overrides.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut depths: Vec<_> = self
+            .index
+            .constant_keys()
+            .into_iter()
+            .map(|(depth, _)| depth)
+            .collect();
+        depths.reverse();
+
+        let mut flattened = Vec::with_capacity(depths.len());
+        for depth in depths {
+            self.index.flatten_level(depth)?;
+            flattened.insert(0, self.group_by.remove(depth));
+        }
+        Ok(flattened)
+    }
+
+    /// List the group keys without description, for the fields having descriptions
+    pub fn missing_descriptions(&self) -> Vec<(CommitField, String)> {
+        let mut missing = Vec::new();
+        collect_missing_descriptions(
+            &self.index,
+            &self.group_by,
+            &self.group_by_overrides,
+            &self.key_descriptions,
+            &mut missing,
+        );
+        missing
+    }
+}
+
+/// Counts of the changelog's entries
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct ChangeLogStats {
+    #[serde(flatten)]
+    pub counts: EntryCounts,
+    pub groups: GroupStats,
+}
+
+/// Counts of distinct entries
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize)]
+pub struct EntryCounts {
+    pub total: usize,
+    pub breaking: usize,
+    pub by_type: IndexMap<String, usize>,
+}
+
+/// Counts of the entries of each group, following the changelog's levels
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum GroupStats {
+    Index(IndexMap<String, GroupStats>),
+    Bucket { count: usize, breaking: usize },
+}
+
+impl From<&HierarchicalBuckets<String, ChangeLogEntry>> for GroupStats {
+    fn from(node: &HierarchicalBuckets<String, ChangeLogEntry>) -> Self {
+        match node {
+            HierarchicalBuckets::Index(index) => Self::Index(
+                index
+                    .iter()
+                    .map(|(key, child)| (key.clone(), Self::from(child)))
+                    .collect(),
+            ),
+            HierarchicalBuckets::Bucket(entries) => Self::Bucket {
+                count: entries.len(),
+                breaking: entries
+                    .iter()
+                    .filter(|entry| entry.message.is_breaking)
+                    .count(),
+            },
+        }
+    }
+}
+
+impl EntryCounts {
+    fn add(&mut self, entry: &ChangeLogEntry) {
+        self.total += 1;
+        if entry.message.is_breaking {
+            self.breaking += 1;
+        }
+        *self
+            .by_type
+            .entry(entry.message.ctype.as_str().to_owned())
+            .or_default() += 1;
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+impl Serialize for ChangeLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        DescribedBuckets {
+            node: &self.index,
+            group_by: &self.group_by,
+            group_by_overrides: &self.group_by_overrides,
+            key_descriptions: &self.key_descriptions,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Tera filter normalizing a body, wrapped at the `width` argument if given
+fn normalize_body_filter(
+    value: &tera::Value,
+    args: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let body = tera::try_get_value!("normalize_body", "value", String, value);
+    let wrap = match args.get("width") {
+        Some(width) => Some(tera::try_get_value!(
+            "normalize_body",
+            "width",
+            usize,
+            width
+        )),
+        None => None,
+    };
+    Ok(tera::Value::String(normalize_body(&body, wrap)))
+}
+
+/// Get the group-by fields below the given key: the overriding ones of an origin, or the
+/// remaining ones
+fn child_group_by<'a>(
+    group_by_overrides: &'a GroupByOverrides,
+    field: &CommitField,
+    key: &str,
+    remaining: &'a [CommitField],
+) -> &'a [CommitField] {
+    match field {
+        CommitField::Origin => group_by_overrides.get(key).map_or(remaining, Vec::as_slice),
+        _ => remaining,
+    }
+}
+
+fn collect_missing_descriptions(
+    node: &HierarchicalBuckets<String, ChangeLogEntry>,
+    group_by: &[CommitField],
+    group_by_overrides: &GroupByOverrides,
+    key_descriptions: &KeyDescriptions,
+    missing: &mut Vec<(CommitField, String)>,
+) {
+    if let (HierarchicalBuckets::Index(index), Some((field, group_by))) =
+        (node, group_by.split_first())
+    {
+        let descriptions = key_descriptions.get(&field.to_string());
+        for (key, child) in index {
+            if let Some(descriptions) = descriptions {
+                let missing_key = (field.clone(), key.clone());
+                if !descriptions.contains_key(key) && !missing.contains(&missing_key) {
+                    missing.push(missing_key);
+                }
+            }
+            collect_missing_descriptions(
+                child,
+                child_group_by(group_by_overrides, field, key, group_by),
+                group_by_overrides,
+                key_descriptions,
+                missing,
+            );
+        }
+    }
+}
+
+/// Serialization view of the changelog's index, where each described key
+/// holds its `description` alongside its `entries`.
+struct DescribedBuckets<'a> {
+    node: &'a HierarchicalBuckets<String, ChangeLogEntry>,
+    group_by: &'a [CommitField],
+    group_by_overrides: &'a GroupByOverrides,
+    key_descriptions: &'a KeyDescriptions,
+}
+
+#[derive(Serialize)]
+struct DescribedNode<'a> {
+    description: &'a str,
+    entries: DescribedBuckets<'a>,
+}
+
+impl<'a> Serialize for DescribedBuckets<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let (index, field, group_by) = match (self.node, self.group_by.split_first()) {
+            (HierarchicalBuckets::Index(index), Some((field, group_by))) => {
+                (index, field, group_by)
+            }
+            (node, _) => return node.serialize(serializer),
+        };
+        let descriptions = self.key_descriptions.get(&field.to_string());
+
+        let mut map = serializer.serialize_map(Some(index.len()))?;
+        for (key, child) in index {
+            let entries = DescribedBuckets {
+                node: child,
+                group_by: child_group_by(self.group_by_overrides, field, key, group_by),
+                group_by_overrides: self.group_by_overrides,
+                key_descriptions: self.key_descriptions,
+            };
+            match descriptions.and_then(|descriptions| descriptions.get(key)) {
+                Some(description) => map.serialize_entry(
+                    key,
+                    &DescribedNode {
+                        description,
+                        entries,
+                    },
+                )?,
+                None => map.serialize_entry(key, &entries)?,
+            }
+        }
+        map.end()
+    }
+}
+
+fn write_pretty(
+    output: &mut String,
+    change_log: &ChangeLog,
+    node: &HierarchicalBuckets<String, ChangeLogEntry>,
+    group_by: &[CommitField],
+    depth: usize,
+    colors: bool,
+) {
+    let indent = "  ".repeat(depth);
+    match (node, group_by.split_first()) {
+        (HierarchicalBuckets::Index(index), Some((field, group_by))) => {
+            let descriptions = change_log.key_descriptions.get(&field.to_string());
+            let key_style = Style::new().bold().force_styling(colors);
+            for (key, child) in index {
+                let child_group_by =
+                    child_group_by(&change_log.group_by_overrides, field, key, group_by);
+                let key = if key.is_empty() { "-" } else { key.as_str() };
+                let label = match field {
+                    CommitField::CommitType => change_log.type_title(key),
+                    CommitField::Origin => change_log.origin_name(key),
+                    _ => key,
+                };
+                output.push_str(&format!("{}{}", indent, key_style.apply_to(label)));
+                if let Some(description) =
+                    descriptions.and_then(|descriptions| descriptions.get(key))
+                {
+                    output.push_str(&format!(" ({})", description));
+                }
+                output.push('\n');
+                write_pretty(output, change_log, child, child_group_by, depth + 1, colors);
+            }
+        }
+        _ => {
+            for entry in node.values() {
+                output.push_str(&format!("{}{}\n", indent, pretty_entry(entry, colors)));
+            }
+        }
+    }
+}
+
+fn write_html(
+    output: &mut String,
+    change_log: &ChangeLog,
+    node: &HierarchicalBuckets<String, ChangeLogEntry>,
+    group_by: &[CommitField],
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    match (node, group_by.split_first()) {
+        (HierarchicalBuckets::Index(index), Some((field, group_by))) => {
+            let descriptions = change_log.key_descriptions.get(&field.to_string());
+            let level = (depth + 2).min(6);
+            for (key, child) in index {
+                let child_group_by =
+                    child_group_by(&change_log.group_by_overrides, field, key, group_by);
+                let label = match field {
+                    CommitField::CommitType => change_log.type_title(key),
+                    CommitField::Origin => change_log.origin_name(key),
+                    _ => key.as_str(),
+                };
+                output.push_str(&format!(
+                    "{}<section>\n{}  <h{}>{}",
+                    indent,
+                    indent,
+                    level,
+                    escape_html(label)
+                ));
+                if let Some(description) =
+                    descriptions.and_then(|descriptions| descriptions.get(key))
+                {
+                    output.push_str(&format!(" ({})", escape_html(description)));
+                }
+                output.push_str(&format!("</h{}>\n", level));
+                write_html(output, change_log, child, child_group_by, depth + 1);
+                output.push_str(&format!("{}</section>\n", indent));
+            }
+        }
+        _ => {
+            output.push_str(&format!("{}<ul>\n", indent));
+            for entry in node.values() {
+                output.push_str(&format!("{}  <li>{}</li>\n", indent, html_entry(entry)));
+            }
+            output.push_str(&format!("{}</ul>\n", indent));
+        }
+    }
+}
+
+/// Render the entry's short hash then the message's headline, and its body's paragraphs
+fn html_entry(entry: &ChangeLogEntry) -> String {
+    let message = &entry.message;
+    let mut item = String::new();
+    if let Some(hash) = entry.hash() {
+        item.push_str(&format!("<code>{}</code> ", hash.short()));
+    }
+    item.push_str(&format!("<strong>{}", escape_html(message.ctype.as_str())));
+    if !message.scopes.is_empty() {
+        let scopes: Vec<_> = message.scopes.iter().map(|scope| scope.as_str()).collect();
+        item.push_str(&format!("({})", escape_html(&scopes.join(","))));
+    }
+    item.push_str("</strong>: ");
+    if message.is_breaking {
+        item.push_str("<span class=\"breaking\">BREAKING</span> ");
+    }
+    match entry.url() {
+        Some(url) => item.push_str(&format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(url),
+            escape_html(&message.summary)
+        )),
+        None => item.push_str(&escape_html(&message.summary)),
+    }
+    if let Some(body) = &message.body {
+        for paragraph in body.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+            item.push_str(&format!("<p>{}</p>", escape_html(paragraph)));
+        }
+    }
+    item
+}
+
+/// Escape the characters with a meaning in HTML text and attributes
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render the entry's short hash dimmed then the message's headline, with its type colored, its
+/// scope dimmed and its summary in bold red if it's a breaking change
+fn pretty_entry(entry: &ChangeLogEntry, colors: bool) -> String {
+    let message = &entry.message;
+    let type_style = match message.ctype {
+        CommitType::Feature => Style::new().green(),
+        CommitType::BugFix => Style::new().yellow(),
+        CommitType::Performance | CommitType::Refactoring => Style::new().magenta(),
+        CommitType::Documentation => Style::new().blue(),
+        CommitType::Revert => Style::new().red(),
+        _ => Style::new().cyan(),
+    }
+    .force_styling(colors);
+    let mut line = "- ".to_owned();
+    if let Some(hash) = entry.hash() {
+        let hash_style = Style::new().dim().force_styling(colors);
+        line.push_str(&format!("{} ", hash_style.apply_to(hash.short())));
+    }
+    line.push_str(&type_style.apply_to(message.ctype.as_str()).to_string());
+    if !message.scopes.is_empty() {
+        let scopes: Vec<_> = message.scopes.iter().map(|scope| scope.as_str()).collect();
+        let scope_style = Style::new().dim().force_styling(colors);
+        line.push_str(
+            &scope_style
+                .apply_to(format!("({})", scopes.join(",")))
+                .to_string(),
+        );
+    }
+    if message.is_breaking {
+        let breaking_style = Style::new().red().bold().force_styling(colors);
+        line.push_str(&format!(
+            "{} {}",
+            breaking_style.apply_to("!:"),
+            breaking_style.apply_to(&message.summary)
+        ));
+    } else {
+        line.push_str(&format!(": {}", message.summary));
+    }
+    line
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum CommitField {
     Scope,
     Branch,
     Origin,
     CommitType,
+    Author,
+    Committer,
+    /// `breaking` or `non-breaking`
+    Breaking,
+    Trailer(String),
 }
 
 impl fmt::Display for CommitField {
@@ -143,11 +1068,41 @@ impl fmt::Display for CommitField {
             Branch => "branch",
             Origin => "origin",
             CommitType => "commit-type",
+            Author => "author",
+            Committer => "committer",
+            Breaking => "breaking",
+            Trailer(token) => return write!(f, "trailer:{}", token),
         };
-        writeln!(f, "{}", scope)
+        write!(f, "{}", scope)
+    }
+}
+
+impl Serialize for CommitField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
+impl<'de> Deserialize<'de> for CommitField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Names of the fields, `trailer:` being followed by the trailers' token
+pub const COMMIT_FIELD_NAMES: [&str; 8] = [
+    "scope",
+    "branch",
+    "origin",
+    "commit-type",
+    "author",
+    "committer",
+    "breaking",
+    "trailer:",
+];
+
 impl FromStr for CommitField {
     type Err = Error;
 
@@ -157,7 +1112,827 @@ impl FromStr for CommitField {
             "branch" => Ok(Self::Branch),
             "origin" => Ok(Self::Origin),
             "commit-type" => Ok(Self::CommitType),
-            _ => Err(Error::InvalidSelector(s.to_owned())),
+            "author" => Ok(Self::Author),
+            "committer" => Ok(Self::Committer),
+            "breaking" => Ok(Self::Breaking),
+            _ => match s.strip_prefix("trailer:") {
+                Some(token) if !token.is_empty() => Ok(Self::Trailer(token.to_owned())),
+                _ => Err(Error::InvalidSelector(s.to_owned())),
+            },
+        }
+    }
+}
+
+/// Build commit metadata at the given date, for tests
+#[cfg(test)]
+pub fn commit_metadata(date: &str) -> CommitMetadata {
+    let author = Author {
+        name: "Jane Doe".to_string(),
+        email: Some("jane@example.com".to_string()),
+    };
+    CommitMetadata {
+        hash: None,
+        date: DateTime::parse_from_rfc3339(date).unwrap(),
+        author: author.clone(),
+        committer: author,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_change_log(key_descriptions: KeyDescriptions) -> ChangeLog {
+        let mut change_log = ChangeLog::new(vec![CommitField::Trailer("Epic".to_string())])
+            .with_key_descriptions(key_descriptions);
+        for raw_message in &[
+            "feat: onboarding\n\nEpic: EP-204",
+            "fix: billing\n\nepic: EP-300",
+        ] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
         }
+        change_log
+    }
+
+    #[test]
+    fn test_to_pretty() {
+        let mut descriptions = IndexMap::new();
+        descriptions.insert("EP-204".to_string(), "Self-serve onboarding".to_string());
+        let mut key_descriptions = KeyDescriptions::new();
+        key_descriptions.insert("trailer:Epic".to_string(), descriptions);
+        let mut change_log = build_change_log(key_descriptions);
+        let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+        metadata.hash = Some("1a2b3c4d5e6f".to_string().into());
+        change_log
+            .insert(ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                metadata,
+                "refactor(api)!: drop v1".parse().unwrap(),
+            ))
+            .unwrap();
+
+        let expected = "\
+EP-204 (Self-serve onboarding)
+  - feat: onboarding
+EP-300
+  - fix: billing
+(none)
+  - 1a2b3c4 refactor(api)!: drop v1
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+
+        let colored = change_log.to_pretty(true);
+        assert_ne!(expected, colored);
+        assert_eq!(expected, console::strip_ansi_codes(&colored));
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut descriptions = IndexMap::new();
+        descriptions.insert("EP-204".to_string(), "Onboarding & <signup>".to_string());
+        let mut key_descriptions = KeyDescriptions::new();
+        key_descriptions.insert("trailer:Epic".to_string(), descriptions);
+        let mut change_log = build_change_log(key_descriptions);
+        let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+        metadata.hash = Some("1a2b3c4d5e6f".to_string().into());
+        change_log
+            .insert(ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                metadata,
+                "refactor(api)!: drop <v1> & \"v2\"\n\nUse v3.\n\nOr v4."
+                    .parse()
+                    .unwrap(),
+            ))
+            .unwrap();
+
+        let expected = "\
+<section>
+  <h2>EP-204 (Onboarding &amp; &lt;signup&gt;)</h2>
+  <ul>
+    <li><strong>feat</strong>: onboarding</li>
+  </ul>
+</section>
+<section>
+  <h2>EP-300</h2>
+  <ul>
+    <li><strong>fix</strong>: billing</li>
+  </ul>
+</section>
+<section>
+  <h2>(none)</h2>
+  <ul>
+    <li><code>1a2b3c4</code> <strong>refactor(api)</strong>: <span class=\"breaking\">BREAKING</span> \
+drop &lt;v1&gt; &amp; &quot;v2&quot;<p>Use v3.</p><p>Or v4.</p></li>
+  </ul>
+</section>
+";
+        assert_eq!(expected, change_log.to_html());
+    }
+
+    #[test]
+    fn test_to_html_links() {
+        let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+        metadata.hash = Some("1a2b3c4d5e6f".to_string().into());
+        let mut change_log = ChangeLog::new(Vec::new());
+        change_log
+            .insert(
+                ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    metadata,
+                    "fix: escape <input>".parse().unwrap(),
+                )
+                .with_url(Some(
+                    "https://example.com/commit?id=1a2b&full=1".to_string(),
+                )),
+            )
+            .unwrap();
+
+        assert!(change_log.to_html().contains(
+            "<a href=\"https://example.com/commit?id=1a2b&amp;full=1\">escape &lt;input&gt;</a>"
+        ));
+        let output: serde_yaml::Value =
+            serde_yaml::from_str(&change_log.to_yaml().unwrap()).unwrap();
+        assert_eq!(
+            "https://example.com/commit?id=1a2b&full=1",
+            output[0]["url"].as_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_trailer_field() {
+        assert_eq!(
+            CommitField::Trailer("Epic".to_string()),
+            "trailer:Epic".parse().unwrap()
+        );
+        assert_eq!(
+            "trailer:Epic",
+            CommitField::Trailer("Epic".to_string()).to_string()
+        );
+        assert!("trailer:".parse::<CommitField>().is_err());
+    }
+
+    #[test]
+    fn test_key_descriptions() {
+        let mut descriptions = IndexMap::new();
+        descriptions.insert("EP-204".to_string(), "Self-serve onboarding".to_string());
+        let mut key_descriptions = KeyDescriptions::new();
+        key_descriptions.insert("trailer:Epic".to_string(), descriptions);
+
+        let change_log = build_change_log(key_descriptions);
+        let output: serde_yaml::Value =
+            serde_yaml::from_str(&change_log.to_yaml().unwrap()).unwrap();
+
+        assert_eq!(
+            "Self-serve onboarding",
+            output["EP-204"]["description"].as_str().unwrap()
+        );
+        assert_eq!(1, output["EP-204"]["entries"].as_sequence().unwrap().len());
+        assert_eq!(1, output["EP-300"].as_sequence().unwrap().len());
+        assert_eq!(
+            vec![(
+                CommitField::Trailer("Epic".to_string()),
+                "EP-300".to_string()
+            )],
+            change_log.missing_descriptions()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut descriptions = IndexMap::new();
+        descriptions.insert("EP-204".to_string(), "Self-serve onboarding".to_string());
+        let mut key_descriptions = KeyDescriptions::new();
+        key_descriptions.insert("trailer:Epic".to_string(), descriptions);
+        let mut change_log = build_change_log(KeyDescriptions::new());
+        let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+        metadata.hash = Some("1a2b3c4d5e6f".to_string().into());
+        change_log
+            .insert(ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                metadata,
+                "chore(api,db)!: drop v1\n\nIt's gone.\n\nEpic: EP-204\nCloses #12"
+                    .parse()
+                    .unwrap(),
+            ))
+            .unwrap();
+
+        let yaml = change_log.to_yaml().unwrap();
+        let index: HierarchicalBuckets<String, ChangeLogEntry> =
+            serde_yaml::from_str(&yaml).unwrap();
+        let described = build_change_log(key_descriptions).to_yaml().unwrap();
+        let described: HierarchicalBuckets<String, ChangeLogEntry> =
+            serde_yaml::from_str(&described).unwrap();
+
+        assert_eq!(yaml, serde_yaml::to_string(&index).unwrap());
+        assert_eq!(
+            build_change_log(KeyDescriptions::new()).to_yaml().unwrap(),
+            serde_yaml::to_string(&described).unwrap()
+        );
+        let entries: Vec<_> = index.values().collect();
+        assert_eq!(
+            CommitType::Other("chore".to_string()),
+            entries[1].message.ctype
+        );
+    }
+
+    #[test]
+    fn test_no_missing_descriptions_without_field_descriptions() {
+        let change_log = build_change_log(KeyDescriptions::new());
+        assert!(change_log.missing_descriptions().is_empty());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let path = std::env::temp_dir().join(format!("resume-test-{}.tera", std::process::id()));
+        std::fs::write(
+            &path,
+            "{{ stats.total }} changes{% for key, node in changelog %}\n{{ key }}{% endfor %}",
+        )
+        .unwrap();
+
+        let output = build_change_log(KeyDescriptions::new()).to_template(&path);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("2 changes\nEP-204\nEP-300", output.unwrap());
+    }
+
+    #[test]
+    fn test_normalize_bodies() {
+        let mut change_log = ChangeLog::new(vec![]);
+        change_log
+            .insert(ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                commit_metadata("2021-09-01T12:00:00+02:00"),
+                "feat: onboarding\n\nFirst  \nparagraph\n\n\n\nsecond one"
+                    .parse()
+                    .unwrap(),
+            ))
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("resume-test-body-{}.tera", std::process::id()));
+        std::fs::write(
+            &path,
+            "{% for entry in changelog %}{{ entry.message.body | normalize_body(width=6) }}\
+             {% endfor %}",
+        )
+        .unwrap();
+
+        let output = change_log.render_template(&path, Context::new());
+        let raw = change_log
+            .index
+            .values()
+            .next()
+            .unwrap()
+            .message
+            .body
+            .clone();
+        change_log.normalize_bodies(None);
+        let normalized = change_log
+            .index
+            .values()
+            .next()
+            .unwrap()
+            .message
+            .body
+            .clone();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("First\nparagraph\n\nsecond\none", output.unwrap());
+        assert_eq!(
+            Some("First  \nparagraph\n\n\n\nsecond one".to_string()),
+            raw
+        );
+        assert_eq!(
+            Some("First\nparagraph\n\nsecond one".to_string()),
+            normalized
+        );
+    }
+
+    #[test]
+    fn test_render_template_with_missing_variable() {
+        let path =
+            std::env::temp_dir().join(format!("resume-test-missing-{}.tera", std::process::id()));
+        std::fs::write(&path, "{{ missing }}").unwrap();
+
+        let output =
+            build_change_log(KeyDescriptions::new()).render_template(&path, Context::new());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(output, Err(Error::Template(_))));
+    }
+
+    fn build_buckets(keys: &[[&str; 3]]) -> HierarchicalBuckets<String, usize> {
+        let mut buckets = HierarchicalBuckets::Index(IndexMap::new());
+        for (value, keys) in keys.iter().enumerate() {
+            let keys = keys.iter().map(|key| key.to_string()).collect();
+            buckets.insert(keys, value).unwrap();
+        }
+        buckets
+    }
+
+    #[test]
+    fn test_constant_keys() {
+        let buckets = build_buckets(&[
+            ["origin", "main", "feat"],
+            ["origin", "main", "fix"],
+            ["origin", "main", "feat"],
+        ]);
+        let origin = "origin".to_string();
+        let main = "main".to_string();
+        assert_eq!(vec![(0, &origin), (1, &main)], buckets.constant_keys());
+    }
+
+    #[test]
+    fn test_no_constant_keys() {
+        let buckets = build_buckets(&[
+            ["a", "main", "feat"],
+            ["b", "dev", "feat"],
+            ["b", "main", "fix"],
+        ]);
+        assert!(buckets.constant_keys().is_empty());
+        assert!(HierarchicalBuckets::<String, usize>::Index(IndexMap::new())
+            .constant_keys()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_flatten_level() {
+        let mut buckets = build_buckets(&[["a", "main", "feat"], ["b", "main", "fix"]]);
+        buckets.flatten_level(1).unwrap();
+
+        let mut expected = HierarchicalBuckets::Index(IndexMap::new());
+        expected
+            .insert(vec!["a".to_string(), "feat".to_string()], 0)
+            .unwrap();
+        expected
+            .insert(vec!["b".to_string(), "fix".to_string()], 1)
+            .unwrap();
+        assert_eq!(
+            serde_yaml::to_string(&expected).unwrap(),
+            serde_yaml::to_string(&buckets).unwrap()
+        );
+        assert!(buckets.flatten_level(0).is_err());
+    }
+
+    #[test]
+    fn test_sort_levels_by() {
+        let mut buckets = build_buckets(&[
+            ["release/10", "main", "fix"],
+            ["release/9", "main", "feat"],
+            ["release/10", "dev", "feat"],
+            ["release/10", "dev", "docs"],
+            ["release/10", "dev", "build"],
+        ]);
+        buckets.sort_levels_by(&[
+            Some(Box::new(|a: &String, b: &String| a.cmp(b))),
+            Some(Box::new(|a: &String, b: &String| a.cmp(b))),
+            None,
+        ]);
+
+        let mut expected = HierarchicalBuckets::Index(IndexMap::new());
+        for (keys, value) in &[
+            (["release/10", "dev", "feat"], 2),
+            (["release/10", "dev", "docs"], 3),
+            (["release/10", "dev", "build"], 4),
+            (["release/10", "main", "fix"], 0),
+            (["release/9", "main", "feat"], 1),
+        ] {
+            let keys = keys.iter().map(|key| key.to_string()).collect();
+            expected.insert(keys, *value).unwrap();
+        }
+        assert_eq!(
+            serde_yaml::to_string(&expected).unwrap(),
+            serde_yaml::to_string(&buckets).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_flatten_constant_levels() {
+        let mut change_log = build_change_log(KeyDescriptions::new());
+        change_log.group_by = vec![CommitField::Branch, CommitField::CommitType];
+        change_log.index = HierarchicalBuckets::Index(IndexMap::new());
+        for entry in build_change_log(KeyDescriptions::new()).index.values() {
+            change_log.insert(entry.clone()).unwrap();
+        }
+
+        assert_eq!(
+            vec![(CommitField::Branch, "master".to_string())],
+            change_log.constant_fields()
+        );
+        assert_eq!(
+            vec![CommitField::Branch],
+            change_log.flatten_constant_levels().unwrap()
+        );
+        assert_eq!(vec![CommitField::CommitType], change_log.group_by);
+        assert!(change_log.constant_fields().is_empty());
+    }
+
+    #[test]
+    fn test_insert_multiple_scopes() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope, CommitField::CommitType]);
+        for raw_message in &["feat(api,db): span both", "fix(db): single", "docs: none"] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+
+        let expected = "\
+api
+  Features
+    - feat(api,db): span both
+db
+  Features
+    - feat(api,db): span both
+  Bug Fixes
+    - fix(db): single
+(none)
+  Documentation
+    - docs: none
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+        let stats = change_log.stats();
+        assert_eq!(3, stats.counts.total);
+        assert_eq!(Some(&1), stats.counts.by_type.get("feat"));
+    }
+
+    #[test]
+    fn test_empty_key() {
+        let build = |change_log: ChangeLog| {
+            let mut change_log = change_log;
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    "docs: none".parse().unwrap(),
+                ))
+                .unwrap();
+            change_log.to_yaml().unwrap()
+        };
+        let group_by = vec![CommitField::Origin, CommitField::Scope];
+
+        let placeholder: serde_yaml::Value =
+            serde_yaml::from_str(&build(ChangeLog::new(group_by.clone()))).unwrap();
+        let custom: serde_yaml::Value = serde_yaml::from_str(&build(
+            ChangeLog::new(group_by.clone()).with_empty_key("n/a".to_string()),
+        ))
+        .unwrap();
+        let empty: serde_yaml::Value = serde_yaml::from_str(&build(
+            ChangeLog::new(group_by).with_empty_key(String::new()),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            1,
+            placeholder["(none)"]["(none)"].as_sequence().unwrap().len()
+        );
+        assert_eq!(1, custom["n/a"]["n/a"].as_sequence().unwrap().len());
+        assert_eq!(1, empty[""][""].as_sequence().unwrap().len());
+    }
+
+    #[test]
+    fn test_group_stats() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope, CommitField::CommitType]);
+        for raw_message in &[
+            "feat(api,db)!: span both",
+            "fix(db): single",
+            "fix(db): other",
+        ] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+
+        let expected = "\
+---
+total: 3
+breaking: 1
+by_type:
+  feat: 1
+  fix: 2
+groups:
+  api:
+    feat:
+      count: 1
+      breaking: 1
+  db:
+    feat:
+      count: 1
+      breaking: 1
+    fix:
+      count: 2
+      breaking: 0
+";
+        assert_eq!(
+            expected,
+            serde_yaml::to_string(&change_log.stats()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pretty_type_titles() {
+        let mut type_titles = TypeTitles::new();
+        type_titles.insert("security".to_string(), "🔒 Security".to_string());
+        let mut change_log =
+            ChangeLog::new(vec![CommitField::CommitType]).with_type_titles(type_titles);
+        for raw_message in &["feat: login", "security: patch", "chore: bump"] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+
+        let expected = "\
+Features
+  - feat: login
+🔒 Security
+  - security: patch
+chore
+  - chore: bump
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+    }
+
+    #[test]
+    fn test_type_titles() {
+        let mut type_titles = TypeTitles::new();
+        type_titles.insert("fix".to_string(), "Fixes".to_string());
+        type_titles.insert("security".to_string(), "🔒 Security".to_string());
+        let change_log =
+            ChangeLog::new(vec![CommitField::CommitType]).with_type_titles(type_titles);
+
+        assert_eq!("Features", change_log.type_title("feat"));
+        assert_eq!("Performance Improvements", change_log.type_title("perf"));
+        assert_eq!("Fixes", change_log.type_title("fix"));
+        assert_eq!("🔒 Security", change_log.type_title("security"));
+        assert_eq!("chore", change_log.type_title("chore"));
+
+        let titles = change_log.all_type_titles();
+        assert_eq!("Features", titles["feat"]);
+        assert_eq!("Fixes", titles["fix"]);
+        assert_eq!("🔒 Security", titles["security"]);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope]);
+        assert!(change_log.is_empty());
+        assert_eq!(0, change_log.len());
+        change_log
+            .insert(ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                commit_metadata("2021-09-01T12:00:00+02:00"),
+                "fix(api,db): crash".parse().unwrap(),
+            ))
+            .unwrap();
+        assert!(!change_log.is_empty());
+        assert_eq!(1, change_log.len());
+        assert_eq!(2, change_log.entries().count());
+    }
+
+    #[test]
+    fn test_group_mixed_case_types() {
+        let mut change_log = ChangeLog::new(vec![CommitField::CommitType]);
+        for raw_message in &[
+            "Fix: typo",
+            "fix: crash",
+            "FIX(api): timeout",
+            "Chore: bump",
+        ] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+
+        let expected = "\
+Bug Fixes
+  - fix: typo
+  - fix: crash
+  - fix(api): timeout
+Chore
+  - Chore: bump
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+    }
+
+    #[test]
+    fn test_sort_commit_types() {
+        let insert_all = |mut change_log: ChangeLog| {
+            for raw_message in &[
+                "chore: bump",
+                "fix: crash",
+                "security: patch",
+                "feat: login",
+            ] {
+                change_log
+                    .insert(ChangeLogEntry::new(
+                        "origin".to_string().into(),
+                        "master".to_string().into(),
+                        commit_metadata("2021-09-01T12:00:00+02:00"),
+                        raw_message.parse().unwrap(),
+                    ))
+                    .unwrap();
+            }
+            change_log
+        };
+        let keys = |change_log: &ChangeLog| -> Vec<String> {
+            match &change_log.index {
+                HierarchicalBuckets::Index(index) => index.keys().cloned().collect(),
+                _ => unreachable!(),
+            }
+        };
+
+        let mut change_log = insert_all(ChangeLog::new(vec![CommitField::CommitType]));
+        change_log.sort_groups(GroupOrder::Insertion);
+        assert_eq!(vec!["chore", "fix", "security", "feat"], keys(&change_log));
+        change_log.sort_groups(GroupOrder::Canonical);
+        assert_eq!(vec!["feat", "fix", "chore", "security"], keys(&change_log));
+        change_log.sort_groups(GroupOrder::Alpha);
+        assert_eq!(vec!["chore", "feat", "fix", "security"], keys(&change_log));
+
+        let mut change_log = insert_all(
+            ChangeLog::new(vec![CommitField::CommitType])
+                .with_type_order(vec!["Security".to_string(), "fix".to_string()]),
+        );
+        change_log.sort_groups(GroupOrder::Canonical);
+        assert_eq!(vec!["security", "fix", "chore", "feat"], keys(&change_log));
+    }
+
+    #[test]
+    fn test_sort_groups_canonically() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope, CommitField::CommitType]);
+        for raw_message in &[
+            "docs(ui): guide",
+            "chore(ui): bump",
+            "fix(ui): crash",
+            "feat(api): endpoint",
+            "wip(ui): draft",
+            "feat(ui): button",
+        ] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+        change_log.sort_groups(GroupOrder::Canonical);
+
+        let expected = "\
+api
+  Features
+    - feat(api): endpoint
+ui
+  Features
+    - feat(ui): button
+  Bug Fixes
+    - fix(ui): crash
+  Documentation
+    - docs(ui): guide
+  chore
+    - chore(ui): bump
+  wip
+    - wip(ui): draft
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+    }
+
+    #[test]
+    fn test_group_by_breaking() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Breaking, CommitField::CommitType]);
+        for raw_message in &["fix: crash", "feat!: new api", "feat: button"] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+        change_log.sort_groups(GroupOrder::Canonical);
+
+        let expected = "\
+breaking
+  Features
+    - feat!: new api
+non-breaking
+  Features
+    - feat: button
+  Bug Fixes
+    - fix: crash
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+        assert_eq!(
+            CommitField::Breaking,
+            "breaking".parse::<CommitField>().unwrap()
+        );
+        assert_eq!("breaking", CommitField::Breaking.to_string());
+    }
+
+    #[test]
+    fn test_group_by_overrides() {
+        let mut overrides = GroupByOverrides::new();
+        overrides.insert("api".to_string(), vec![CommitField::Scope]);
+        let mut change_log =
+            ChangeLog::new(vec![CommitField::CommitType]).with_group_by_overrides(overrides);
+        for (origin, raw_message) in &[
+            ("web", "fix(ui): button"),
+            ("api", "feat(db): index"),
+            ("web", "feat: dark mode"),
+            ("api", "fix(db): lock"),
+        ] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    origin.to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+        change_log.sort_groups(GroupOrder::Alpha);
+
+        let expected = "\
+api
+  db
+    - feat(db): index
+    - fix(db): lock
+web
+  Features
+    - feat: dark mode
+  Bug Fixes
+    - fix(ui): button
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+        assert!(change_log.constant_fields().is_empty());
+    }
+
+    #[test]
+    fn test_project_names() {
+        let mut project_names = ProjectNames::new();
+        project_names.insert(
+            "git@example.com:team/api.git".to_string(),
+            "API & co".to_string(),
+        );
+        let mut change_log =
+            ChangeLog::new(vec![CommitField::Origin]).with_project_names(project_names);
+        for origin in &["git@example.com:team/api.git", "/srv/git/web"] {
+            change_log
+                .insert(ChangeLogEntry::new(
+                    origin.to_string().into(),
+                    "master".to_string().into(),
+                    commit_metadata("2021-09-01T12:00:00+02:00"),
+                    "feat: login".parse().unwrap(),
+                ))
+                .unwrap();
+        }
+
+        let expected = "\
+API & co
+  - feat: login
+/srv/git/web
+  - feat: login
+";
+        assert_eq!(expected, change_log.to_pretty(false));
+        assert!(change_log.to_html().contains("<h2>API &amp; co</h2>"));
+        assert!(change_log.to_html().contains("<h2>/srv/git/web</h2>"));
+        let yaml = change_log.to_yaml().unwrap();
+        assert!(yaml.contains("git@example.com:team/api.git"));
+        assert!(!yaml.contains("API & co"));
     }
 }
@@ -1,18 +1,47 @@
-use clap::Clap;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-use crate::changelog::CommitField;
-use crate::report::OutputType;
-use crate::snapshots::BranchName;
+use clap::{AppSettings, Clap, IntoApp};
+use clap_generate::{
+    generate,
+    generators::{Bash, Fish, PowerShell, Zsh},
+};
+
+use resume::bump::Version;
+use resume::changelog::{CommitField, COMMIT_FIELD_NAMES};
+use resume::error::{Error, Result};
+use resume::ordering::{EntryOrder, GroupOrder};
+use resume::period::PeriodKind;
+use resume::project::TraversalOptions;
+use resume::report::OutputType;
+use resume::snapshots::BranchName;
 
 #[derive(Clap, Debug)]
 #[clap(name = "resume")]
 pub struct Command {
     #[clap(subcommand)]
     pub sub_command: SubCommand,
-    #[clap(short, long, global(true), multiple_occurrences(true))]
-    pub verbose: bool,
-    #[clap(short, long, global(true), default_value = "yaml", possible_values = & ["yaml"])]
+    /// Log the info messages, then the debug ones and the traces as repeated
+    #[clap(short, long, global(true), parse(from_occurrences))]
+    pub verbose: u8,
+    #[clap(short, long, global(true), default_value = "yaml", possible_values = & ["yaml", "template", "pretty", "stats", "keep-a-changelog", "html", "csv", "tsv"])]
     pub output: OutputType,
+    /// Tera template file, for the `template` output
+    #[clap(long, global(true))]
+    pub template: Option<PathBuf>,
+    /// Log the progress instead of drawing progress bars, implied when stderr isn't a terminal
+    #[clap(long, global(true))]
+    pub no_progress: bool,
+    /// Only print the output and the errors: no progress, warnings nor run summary.
+    /// `--verbose` wins over it.
+    #[clap(short, long, global(true))]
+    pub quiet: bool,
+    /// Guarantee that nothing is written: branches aren't fetched, nor clones, state or
+    /// output files written
+    #[clap(long, global(true))]
+    pub read_only: bool,
 }
 
 #[derive(Clap, Debug)]
@@ -21,11 +50,28 @@ pub enum SubCommand {
     Repository(Repository),
     #[clap(alias = "p")]
     Projects(Projects),
+    /// Render again the entries saved by `projects --save-report`
+    Render(Render),
+    /// Check that a commit message follows the conventional commits format
+    Lint(Lint),
+    /// Manage the configuration of the projects
+    Config(Config),
+    /// Check the configuration, the reachability of the projects' repositories and branches,
+    /// and the state file, listing all their issues
+    Check(Check),
+    /// Write a commented configuration skeleton, listing the project of the current
+    /// repository if any
+    Init(Init),
+    /// Print the completion script of the shell
+    #[clap(setting = AppSettings::Hidden)]
+    Completions(Completions),
 }
 
 #[derive(Clap, Debug)]
 pub struct Repository {
     pub repository: String,
+    /// Branches to traverse, each one down to the previous ones. `*` and `?` wildcards, or a
+    /// `regex:` prefixed regular expression, select the matching branches
     #[clap(
         short,
         long("branch"),
@@ -34,22 +80,47 @@ pub struct Repository {
         default_value = "master"
     )]
     pub branches: Vec<BranchName>,
-    #[clap(short, long)]
-    pub team: Option<String>,
+    /// Traverse all the local branches, in name order, instead of the given ones
+    #[clap(long, conflicts_with("branches"))]
+    pub all_branches: bool,
     #[clap(
         short,
         long,
-        default_values = &["branch", "commit-type"],
-        possible_values = &["branch", "commit-type", "scope"],
+        multiple_occurrences(true),
         multiple_values(true),
         require_delimiter(true),
-        value_delimiter(','),
+        value_delimiter(',')
     )]
-    pub group_by: Vec<CommitField>,
+    pub team: Vec<String>,
+    /// Key of the trailers naming the commits' team [default: team]
+    #[clap(long)]
+    pub team_trailer_key: Option<String>,
+    /// Compare the teams case-insensitively
+    #[clap(long)]
+    pub team_ignore_case: bool,
+    /// Fields to group the entries by [default: branch,commit-type]
+    #[clap(
+        short,
+        long,
+        multiple_values(true),
+        require_delimiter(true),
+        value_delimiter(',')
+    )]
+    pub group_by: Option<Vec<CommitField>>,
+    /// YAML file of settings (`team`, `team_trailer_key`, `team_ignore_case`, `group_by`,
+    /// `include_types`, `exclude_types`, `exclude_authors`, `since_tag` and `until_tag`),
+    /// overridden by the options
+    #[clap(long)]
+    pub settings: Option<PathBuf>,
+    #[clap(flatten)]
+    pub traversal: TraversalOptions,
+    #[clap(flatten)]
+    pub report: ReportOptions,
 }
 
 #[derive(Clap, Debug)]
 pub struct Projects {
+    /// Configuration file, `-` to read it from stdin, or an `http(s)://` URL to download it
     #[clap(default_value = "resume.yaml")]
     pub config_file: String,
     #[clap(long, default_value = "resume.state")]
@@ -58,16 +129,292 @@ pub struct Projects {
     pub no_state: bool,
     #[clap(short, long)]
     pub save_state: bool,
+    #[clap(long)]
+    pub amend_state: bool,
+    /// Name the saved snapshot, to refer to it with `--from-snapshot`
+    #[clap(long)]
+    pub label: Option<String>,
+    /// Refuse to amend a snapshot older than this number of hours
+    #[clap(long, default_value = "24")]
+    pub amend_max_age: i64,
+    /// Amend the most recent snapshot even if it's too old or labeled
+    #[clap(long)]
+    pub force: bool,
+    /// Snapshot to start from, by index, `last~N`, hash or label, instead of the most recent one
     #[clap(short, long)]
     pub from_snapshot: Option<String>,
+    /// Keep only the N most recent snapshots when saving the state
+    #[clap(long)]
+    pub keep_snapshots: Option<usize>,
+    /// Remove the given snapshot (index, `last~N`, hash or label) from the state
+    #[clap(long, multiple_occurrences(true))]
+    pub remove_snapshot: Vec<String>,
+    /// List the snapshots of the state then exit
+    #[clap(long)]
+    pub list_snapshots: bool,
+    /// Abandon a project's fetch after this number of seconds, overriding the configuration's
+    /// `fetch_timeout`
+    #[clap(long)]
+    pub fetch_timeout: Option<u64>,
+    /// Retry a project's failed fetch this number of times, overriding the configuration's
+    /// `fetch_retries`
+    #[clap(long)]
+    pub fetch_retries: Option<u32>,
+    /// Clone and fetch only this number of commits from each branch's head, overriding the
+    /// configuration's `fetch_depth`
+    #[clap(long, alias = "clone-depth")]
+    pub fetch_depth: Option<u32>,
+    /// Process this number of projects in parallel, overriding the configuration's `jobs`.
+    /// 1 processes them one after the other. [default: one per CPU]
+    #[clap(short, long)]
+    pub jobs: Option<NonZeroUsize>,
+    /// Fields to group the entries by, overriding the configuration's `group_by`
+    /// [default: origin,branch,commit-type]
+    #[clap(
+        short,
+        long,
+        multiple_values(true),
+        require_delimiter(true),
+        value_delimiter(',')
+    )]
+    pub group_by: Option<Vec<CommitField>>,
+    #[clap(flatten)]
+    pub traversal: TraversalOptions,
+    #[clap(flatten)]
+    pub report: ReportOptions,
+    /// Report the projects which succeeded when others fail, then list the failures and exit
+    /// with an error
+    #[clap(long)]
+    pub keep_going: bool,
+    /// Save the entries of the run in the given JSON file, to render them again later
+    #[clap(long)]
+    pub save_report: Option<PathBuf>,
+    /// Remove the cached clones of the repositories not in the configuration
+    #[clap(long)]
+    pub prune_cache: bool,
+    /// Only list the cached clones that would be pruned
+    #[clap(long, requires("prune-cache"))]
+    pub dry_run: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct Render {
+    /// Run report saved by `projects --save-report`
+    pub run_report: PathBuf,
     #[clap(
         short,
         long,
         default_values = &["origin", "branch", "commit-type"],
-        possible_values = &["branch", "commit-type", "origin", "scope"],
         multiple_values(true),
         require_delimiter(true),
         value_delimiter(','),
     )]
     pub group_by: Vec<CommitField>,
+    #[clap(flatten)]
+    pub report: ReportOptions,
+}
+
+#[derive(Clap, Debug)]
+pub struct Lint {
+    /// File of the commit message, like `.git/COMMIT_EDITMSG`; read from stdin if omitted
+    pub message_file: Option<PathBuf>,
+    /// Maximum length of the summary, in characters
+    #[clap(long)]
+    pub max_summary_length: Option<usize>,
+    /// Forbid the summary to end with a period
+    #[clap(long)]
+    pub no_final_period: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct Config {
+    #[clap(subcommand)]
+    pub sub_command: ConfigSubCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum ConfigSubCommand {
+    /// Same as `resume check`
+    Check(Check),
+}
+
+#[derive(Clap, Debug)]
+pub struct Check {
+    /// Configuration file, `-` to read it from stdin, or an `http(s)://` URL to download it
+    #[clap(default_value = "resume.yaml")]
+    pub config_file: String,
+    #[clap(long, default_value = "resume.state")]
+    pub state_file: String,
+    /// Skip the checks of the origins, which require the network
+    #[clap(long)]
+    pub offline: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct Init {
+    /// Configuration file to write, or folder to write `resume.yaml` in
+    #[clap(long, default_value = ".")]
+    pub path: PathBuf,
+    /// Overwrite the existing configuration file
+    #[clap(long)]
+    pub force: bool,
+    /// Don't list the project of the current repository
+    #[clap(long)]
+    pub no_project: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct Completions {
+    #[clap(possible_values = &["bash", "zsh", "fish", "powershell"])]
+    pub shell: Shell,
+}
+
+/// Shell of the completion script
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl FromStr for Shell {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "powershell" => Ok(Self::PowerShell),
+            _ => Err(Error::InvalidShell(s.to_owned())),
+        }
+    }
+}
+
+impl Shell {
+    /// Write the completion script of the shell. The `--group-by` fields are completed, though
+    /// not listed as its possible values, which would reject the `trailer:<token>` ones.
+    pub fn generate(self, output: &mut dyn Write) {
+        let mut app = Command::into_app();
+        for subcommand in app.get_subcommands_mut() {
+            if subcommand
+                .get_arguments()
+                .any(|arg| arg.get_name() == "group-by")
+            {
+                *subcommand = std::mem::take(subcommand)
+                    .mut_arg("group-by", |arg| arg.possible_values(&COMMIT_FIELD_NAMES));
+            }
+        }
+        match self {
+            Self::Bash => generate::<Bash, _>(&mut app, "resume", output),
+            Self::Zsh => generate::<Zsh, _>(&mut app, "resume", output),
+            Self::Fish => generate::<Fish, _>(&mut app, "resume", output),
+            Self::PowerShell => generate::<PowerShell, _>(&mut app, "resume", output),
+        }
+    }
+}
+
+/// Options driving the rendering of the changelog from its entries
+#[derive(Clap, Debug, Clone)]
+pub struct ReportOptions {
+    /// Warn about the keys of the described group-by fields lacking a description
+    #[clap(long)]
+    pub warn_missing_descriptions: bool,
+    /// Remove the group-by levels whose key is shared by all the entries
+    #[clap(long)]
+    pub auto_flatten: bool,
+    /// Order of the groups: as met in the history, natural alphabetical, bytewise, or
+    /// canonical (commit types by priority, or by the configured `type_order`, other keys
+    /// natural alphabetical)
+    #[clap(
+        long,
+        default_value = "canonical",
+        possible_values = &["insertion", "alpha", "alpha-strict", "canonical"]
+    )]
+    pub sort_groups: GroupOrder,
+    /// Order of the entries within each group: most recent first, or by type, summary or scope
+    #[clap(
+        long,
+        default_value = "date",
+        possible_values = &["date", "type", "summary", "scope"]
+    )]
+    pub sort_by: EntryOrder,
+    /// Report each commit once, even if reachable from several branches. When grouping by
+    /// branch, each commit is reported once per branch.
+    #[clap(long)]
+    pub dedup: bool,
+    /// Render a changelog per period
+    #[clap(long, possible_values = &["week", "month"])]
+    pub slice_by: Option<PeriodKind>,
+    /// Print the semantic version increment required by the entries (major, minor, patch or
+    /// none) instead of the changelog
+    #[clap(long, conflicts_with("slice-by"))]
+    pub suggest_bump: bool,
+    /// Print the version following this `X.Y.Z` one instead of the increment
+    #[clap(long, requires("suggest-bump"))]
+    pub current_version: Option<Version>,
+    /// Key of the entries without value for a group-by field, like the ones without scope,
+    /// overriding the configuration's `empty_key`. An empty one keeps their empty key.
+    /// [default: (none)]
+    #[clap(long)]
+    pub empty_key: Option<String>,
+    /// Write each period's changelog in its own file of this folder
+    #[clap(long, requires("slice-by"))]
+    pub split_output_dir: Option<PathBuf>,
+    /// Write the output to this file instead of the standard output, as it's rendered
+    #[clap(long, conflicts_with("split-output-dir"))]
+    pub output_file: Option<PathBuf>,
+    /// Clean up the bodies rendered by the template output, and render them under their
+    /// entries in the Keep a Changelog output: trim the trailing spaces and collapse the
+    /// blank lines. The YAML output keeps the raw bodies.
+    #[clap(long)]
+    pub normalize_bodies: bool,
+    /// Hard-wrap the normalized bodies at this column
+    #[clap(long, requires("normalize-bodies"))]
+    pub wrap_bodies: Option<usize>,
+    /// Exit with 2 if the changelog has no entry, e.g. no releasable changes since the last
+    /// snapshot
+    #[clap(long)]
+    pub fail_on_empty: bool,
+    /// Render the YAML as a `meta` block (period, flattened fields, read-only run and
+    /// truncated branches) and the `changes`, instead of the changelog only
+    #[clap(long)]
+    pub with_meta: bool,
+    /// Post a summary of the changelog to this Slack incoming webhook, overriding the
+    /// configuration's `slack_webhook`
+    #[clap(long)]
+    pub slack_webhook: Option<String>,
+    /// Print the Slack message to the standard error instead of posting it
+    #[clap(long)]
+    pub slack_dry_run: bool,
+    /// Don't post to the configured Slack webhook
+    #[clap(long, conflicts_with_all(&["slack-webhook", "slack-dry-run"]))]
+    pub no_slack: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bash_completions() {
+        let mut output = Vec::new();
+        Shell::Bash.generate(&mut output);
+        let script = String::from_utf8(output).unwrap();
+        for subcommand in &[
+            "projects",
+            "repository",
+            "render",
+            "lint",
+            "config",
+            "check",
+        ] {
+            assert!(script.contains(subcommand), "missing {}", subcommand);
+        }
+        assert!(script.contains("--group-by"));
+        assert!(script.contains("keep-a-changelog"));
+        assert!(script.contains("commit-type"));
+    }
 }
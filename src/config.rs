@@ -1,32 +1,382 @@
 use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::io::{BufReader, Read};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_yaml::Value;
 
-use crate::error::Result;
+use crate::changelog::{
+    CommitField, GroupByOverrides, KeyDescriptions, ProjectNames, TypeAliases, TypeTitles,
+};
+use crate::error::{Error, Result};
+use crate::filter::TeamFilter;
+use crate::keep_a_changelog::SectionMapping;
+use crate::links::{infer_link_template, repository_name};
+use crate::message::DEFAULT_ISSUE_PATTERNS;
+use crate::project::FetchPolicy;
+use crate::project::TraversalOptions;
 use crate::snapshots::{BranchName, RepositoryOrigin};
 
+/// Projects to résumé and how, loaded from `resume.yaml`
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct Configuration {
-    #[serde(default = "default_branch")]
-    pub default_branch: BranchName,
+    /// Branch watched by the projects without `branches`, detected from origin if missing
+    #[serde(default)]
+    pub default_branch: Option<BranchName>,
     pub projects: Vec<Project>,
+    /// Fields to group the entries by when `--group-by` isn't given
+    #[serde(default)]
+    pub group_by: Option<Vec<CommitField>>,
+    /// Key of the entries without value for a group-by field when `--empty-key` isn't given
+    #[serde(default)]
+    pub empty_key: Option<String>,
+    /// Slack incoming webhook to post a summary of the changelog to
+    #[serde(default)]
+    pub slack_webhook: Option<String>,
+    #[serde(default)]
+    pub key_descriptions: KeyDescriptions,
+    /// Titles of the commit types in the rendered outputs, overriding the default ones
+    #[serde(default, alias = "types")]
+    pub type_titles: TypeTitles,
+    /// Order of the commit type groups, instead of their priority
+    #[serde(default)]
+    pub type_order: Vec<String>,
+    /// Keep a Changelog sections of the commit types, overriding the default ones
+    #[serde(default)]
+    pub sections: SectionMapping,
+    /// Canonical commit types by alias, applied to the parsed messages
+    #[serde(default)]
+    pub type_aliases: TypeAliases,
+    /// Drop the commits of the authors matching these patterns, in all the projects
+    #[serde(default)]
+    pub exclude_authors: Vec<String>,
+    /// Regular expressions of the issue references extracted from the messages
+    #[serde(default = "default_issue_patterns")]
+    pub issue_patterns: Vec<String>,
+    /// Key of the trailers naming the commits' team
+    #[serde(default = "default_team_trailer_key")]
+    pub team_trailer_key: String,
+    /// Compare the teams case-insensitively
+    #[serde(default)]
+    pub team_ignore_case: bool,
+    /// Seconds after which a project's fetch is abandoned, none by default
+    #[serde(default)]
+    pub fetch_timeout: Option<u64>,
+    /// Number of retries of a project's failed fetch, with an exponential backoff
+    #[serde(default)]
+    pub fetch_retries: u32,
+    /// Number of commits to clone and fetch from each branch's head, the whole history by
+    /// default
+    #[serde(default, alias = "clone_depth")]
+    pub fetch_depth: Option<u32>,
+    /// Template of the links to the commits, like `https://example.com/{repo}/commit/{hash}`
+    #[serde(default)]
+    pub link_template: Option<String>,
+    /// Number of projects processed in parallel, one per CPU by default
+    #[serde(default)]
+    pub jobs: Option<NonZeroUsize>,
 }
 
+/// Repository to résumé, with the branches to traverse
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct Project {
     pub name: String,
+    /// Repository to clone, `file://` followed by the canonical path for local projects
+    #[serde(default)]
     pub origin: RepositoryOrigin,
+    /// Local repository traversed as it is, without cloning nor fetching, instead of origin
+    #[serde(default)]
+    pub path: Option<PathBuf>,
     pub branches: Option<Vec<BranchName>>,
-    pub team: Option<String>,
+    /// Keep only the commits of these teams, given as a comma-separated string or a list
+    #[serde(default, deserialize_with = "deserialize_teams")]
+    pub team: Vec<String>,
+    /// Drop the commits of the authors matching these patterns
+    #[serde(default)]
+    pub exclude_authors: Vec<String>,
+    /// Fields to group this project's entries by, below its origin, overriding `group_by`
+    #[serde(default)]
+    pub group_by: Option<Vec<CommitField>>,
+    /// Token to access an HTTP(S) origin with, instead of the `GIT_TOKEN` environment variable
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Fetch timeout of this project in seconds, overriding `fetch_timeout`
+    #[serde(default)]
+    pub fetch_timeout: Option<u64>,
+    /// Fetch retries of this project, overriding `fetch_retries`
+    #[serde(default)]
+    pub fetch_retries: Option<u32>,
+    /// Fetch depth of this project, overriding `fetch_depth`
+    #[serde(default, alias = "clone_depth")]
+    pub fetch_depth: Option<u32>,
+    /// Template of the links to this project's commits, overriding `link_template`
+    #[serde(default)]
+    pub link_template: Option<String>,
 }
 
+/// Settings of the `repository` subcommand, from its `--settings` file. The command line's
+/// options win over them.
+#[derive(Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct RepositorySettings {
+    /// Keep only the commits of these teams, given as a comma-separated string or a list
+    #[serde(default, deserialize_with = "deserialize_teams")]
+    pub team: Vec<String>,
+    /// Key of the trailers naming the commits' team
+    #[serde(default)]
+    pub team_trailer_key: Option<String>,
+    /// Compare the teams case-insensitively
+    #[serde(default)]
+    pub team_ignore_case: bool,
+    #[serde(default)]
+    pub group_by: Option<Vec<CommitField>>,
+    /// Keep only the commits of these types
+    #[serde(default)]
+    pub include_types: Vec<String>,
+    /// Drop the commits of these types
+    #[serde(default)]
+    pub exclude_types: Vec<String>,
+    /// Drop the commits of the authors matching these patterns
+    #[serde(default)]
+    pub exclude_authors: Vec<String>,
+    /// Stop the traversal at this tag, excluded
+    #[serde(default)]
+    pub since_tag: Option<String>,
+    /// Start the traversal at this tag
+    #[serde(default)]
+    pub until_tag: Option<String>,
+}
+
+impl RepositorySettings {
+    /// Load the settings file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_yaml::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Get the traversal options: the given ones, else the settings' ones
+    pub fn traversal_options(&self, options: &TraversalOptions) -> TraversalOptions {
+        let or = |given: &Vec<String>, configured: &Vec<String>| {
+            if given.is_empty() {
+                configured.clone()
+            } else {
+                given.clone()
+            }
+        };
+        TraversalOptions {
+            include_type: or(&options.include_type, &self.include_types),
+            exclude_type: or(&options.exclude_type, &self.exclude_types),
+            exclude_author: or(&options.exclude_author, &self.exclude_authors),
+            since_tag: options.since_tag.clone().or_else(|| self.since_tag.clone()),
+            until_tag: options.until_tag.clone().or_else(|| self.until_tag.clone()),
+            ..options.clone()
+        }
+    }
+
+    /// Get the team filter: the given teams and trailer key, else the settings' ones
+    pub fn team_filter(
+        &self,
+        teams: &[String],
+        trailer_key: Option<&str>,
+        ignore_case: bool,
+    ) -> TeamFilter {
+        let default = TeamFilter::default();
+        TeamFilter {
+            teams: if teams.is_empty() {
+                self.team.clone()
+            } else {
+                teams.to_vec()
+            },
+            trailer_key: trailer_key
+                .or(self.team_trailer_key.as_deref())
+                .map_or(default.trailer_key, str::to_owned),
+            ignore_case: ignore_case || self.team_ignore_case,
+        }
+    }
+
+    /// Get the fields to group the entries by: the given ones, the settings' ones, or the
+    /// branch then the commit type
+    pub fn get_group_by(&self, group_by: Option<&[CommitField]>) -> Vec<CommitField> {
+        group_by
+            .or(self.group_by.as_deref())
+            .map(<[CommitField]>::to_vec)
+            .unwrap_or_else(|| vec![CommitField::Branch, CommitField::CommitType])
+    }
+}
+
+/// Deserialize the YAML file, expanding the `${VAR}` and `$VAR` references of the projects'
+/// origins, tokens and paths with the environment variables
+fn read_yaml<T: DeserializeOwned, R: Read>(reader: R) -> Result<T> {
+    read_yaml_with(reader, &|name| std::env::var(name).ok())
+}
+
+/// Deserialize the YAML file, expanding the variable references with the given lookup
+fn read_yaml_with<T: DeserializeOwned, R: Read>(
+    reader: R,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<T> {
+    let mut value = serde_yaml::from_reader(reader)?;
+    expand_variables(&mut value, lookup)?;
+    Ok(serde_yaml::from_value(value)?)
+}
+
+/// Abandon the download of a remote configuration after this delay
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
 impl Configuration {
+    /// Load the configuration from the standard input if the source is `-`, downloading it if
+    /// it's an `http(s)://` URL, or from the file at this path otherwise
+    pub fn from_source(source: &str) -> Result<Self> {
+        let mut config = Self::read_source(source)?;
+        config.resolve_local_projects()?;
+        Ok(config)
+    }
+
+    /// Load the configuration like `from_source`, leaving the local projects unresolved, e.g.
+    /// to check them
+    pub fn read_source(source: &str) -> Result<Self> {
+        if source == "-" {
+            read_yaml(std::io::stdin().lock())
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            let response = reqwest::blocking::Client::builder()
+                .timeout(DOWNLOAD_TIMEOUT)
+                .build()?
+                .get(source)
+                .send()?
+                .error_for_status()?;
+            read_yaml(response)
+        } else {
+            Self::read_file(source)
+        }
+    }
+
+    /// Load the configuration file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        Ok(serde_yaml::from_reader(reader)?)
+        let mut config = Self::read_file(path)?;
+        config.resolve_local_projects()?;
+        Ok(config)
+    }
+
+    /// Read the configuration file, the relative paths of its local projects being relative
+    /// to the file's folder
+    fn read_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut config: Self = read_yaml(BufReader::new(File::open(&path)?))?;
+        let folder = path.as_ref().canonicalize()?;
+        let folder = folder.parent().unwrap_or(&folder);
+        for project_path in config.projects.iter_mut().filter_map(|p| p.path.as_mut()) {
+            if project_path.is_relative() {
+                *project_path = folder.join(&project_path);
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load the YAML configuration
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut config: Self = read_yaml(reader)?;
+        config.resolve_local_projects()?;
+        Ok(config)
+    }
+
+    /// Check that each project has either an origin or a path, and give the local projects
+    /// the `file://` origin of their canonical path. Out of a configuration file, the paths
+    /// must be absolute.
+    pub fn resolve_local_projects(&mut self) -> Result<()> {
+        for project in &mut self.projects {
+            let has_origin = !project.origin.as_str().is_empty();
+            match &project.path {
+                Some(_) if has_origin => {
+                    return Err(Error::InvalidProject(
+                        project.name.clone(),
+                        "has both an origin and a path".to_string(),
+                    ))
+                }
+                Some(path) if path.is_relative() => {
+                    return Err(Error::InvalidProject(
+                        project.name.clone(),
+                        "has a relative path out of a configuration file".to_string(),
+                    ))
+                }
+                Some(path) => {
+                    let path = path.canonicalize()?;
+                    project.origin = format!("file://{}", path.display()).into();
+                }
+                None if !has_origin => {
+                    return Err(Error::InvalidProject(
+                        project.name.clone(),
+                        "has neither an origin nor a path".to_string(),
+                    ))
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the fields to group the entries by: the given ones, the configured ones, or the
+    /// origin, the branch then the commit type
+    pub fn get_group_by(&self, group_by: Option<&[CommitField]>) -> Vec<CommitField> {
+        group_by
+            .or(self.group_by.as_deref())
+            .map(<[CommitField]>::to_vec)
+            .unwrap_or_else(|| {
+                vec![
+                    CommitField::Origin,
+                    CommitField::Branch,
+                    CommitField::CommitType,
+                ]
+            })
+    }
+
+    /// Get the group-by fields of the projects overriding them, by origin
+    pub fn get_group_by_overrides(&self) -> GroupByOverrides {
+        self.projects
+            .iter()
+            .filter_map(|project| {
+                let group_by = project.group_by.clone()?;
+                Some((project.origin.as_str().to_owned(), group_by))
+            })
+            .collect()
+    }
+
+    /// Get the names of the projects, by origin
+    pub fn get_project_names(&self) -> ProjectNames {
+        self.projects
+            .iter()
+            .filter(|project| !project.name.trim().is_empty())
+            .map(|project| {
+                (
+                    project.origin.as_str().to_owned(),
+                    project.name.trim().to_owned(),
+                )
+            })
+            .collect()
+    }
+
+    /// Get how to fetch the project's branches: its own fetch timeout and retries, or the
+    /// root ones
+    pub fn get_fetch_policy(&self, project: &Project) -> FetchPolicy {
+        FetchPolicy {
+            timeout: project
+                .fetch_timeout
+                .or(self.fetch_timeout)
+                .map(Duration::from_secs),
+            retries: project.fetch_retries.unwrap_or(self.fetch_retries),
+            depth: project.fetch_depth.or(self.fetch_depth),
+        }
+    }
+
+    /// Get the template of the links to the project's commits: its own, the root one, or the
+    /// one inferred from a GitHub or GitLab origin. `{repo}` is replaced by the name of
+    /// origin's repository, or the project's name.
+    pub fn get_link_template(&self, project: &Project) -> Option<String> {
+        let template = project
+            .link_template
+            .clone()
+            .or_else(|| self.link_template.clone())
+            .or_else(|| infer_link_template(&project.origin))?;
+        let repo = repository_name(&project.origin).unwrap_or(&project.name);
+        Some(template.replace("{repo}", repo))
     }
 
     pub fn get_branch_name_max_len(&self) -> usize {
@@ -39,13 +389,111 @@ impl Configuration {
 }
 
 impl Project {
-    pub fn get_branches_name(&self, default: &[BranchName]) -> Vec<BranchName> {
-        self.branches.as_deref().unwrap_or(default).to_owned()
+    /// Get the token to access origin with: the configured one, or `GIT_TOKEN`'s value
+    pub fn get_token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| std::env::var("GIT_TOKEN").ok())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Get the branches to watch, `None` if they must be detected from origin
+    pub fn get_branches_name(&self, default: Option<&BranchName>) -> Option<Vec<BranchName>> {
+        match &self.branches {
+            Some(branches) => Some(branches.clone()),
+            None => default.map(|branch| vec![branch.clone()]),
+        }
+    }
+}
+
+/// Project fields whose variable references are expanded, the other strings are kept as written
+const EXPANDED_FIELDS: [&str; 3] = ["origin", "token", "path"];
+
+/// Expand the variable references of the projects' expanded fields
+fn expand_variables(value: &mut Value, lookup: &impl Fn(&str) -> Option<String>) -> Result<()> {
+    let projects = match value.get_mut("projects") {
+        Some(Value::Sequence(projects)) => projects,
+        _ => return Ok(()),
+    };
+    for project in projects {
+        for field in EXPANDED_FIELDS.iter() {
+            if let Some(Value::String(string)) = project.get_mut(*field) {
+                *string = expand_string(string, lookup)?;
+            }
+        }
     }
+    Ok(())
+}
+
+/// Replace the `${VAR}` and `$VAR` references by the variables' values, and `$$` by `$`.
+/// Referencing an unset variable is an error; other `$` are kept as they are.
+fn expand_string(string: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, next) = if let Some(after) = after.strip_prefix('{') {
+            match after.find('}') {
+                Some(end) => (&after[..end], &after[end + 1..]),
+                None => ("", after),
+            }
+        } else if let Some(after) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        match lookup(name) {
+            Some(value) => expanded.push_str(&value),
+            None => return Err(Error::UnsetVariable(name.to_owned())),
+        }
+        rest = next;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn default_issue_patterns() -> Vec<String> {
+    DEFAULT_ISSUE_PATTERNS
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
-fn default_branch() -> BranchName {
-    "master".to_string().into()
+fn default_team_trailer_key() -> String {
+    "team".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Teams {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Deserialize the teams from a comma-separated string or a list of strings
+fn deserialize_teams<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let teams = match Teams::deserialize(deserializer)? {
+        Teams::One(teams) => teams.split(',').map(str::to_owned).collect(),
+        Teams::Many(teams) => teams,
+    };
+    Ok(teams
+        .iter()
+        .map(|team| team.trim().to_owned())
+        .filter(|team| !team.is_empty())
+        .collect())
 }
 
 #[cfg(test)]
@@ -60,13 +508,38 @@ projects:
     origin: git@example.com:user/repository.git
 "#;
         let expected = Configuration {
-            default_branch: "master".to_string().into(),
+            default_branch: None,
+            group_by: None,
+            empty_key: None,
+            slack_webhook: None,
             projects: vec![Project {
                 name: "repo".to_string(),
                 origin: "git@example.com:user/repository.git".to_string().into(),
                 branches: None,
-                team: None,
+                team: Vec::new(),
+                exclude_authors: Vec::new(),
+                group_by: None,
+                path: None,
+                token: None,
+                fetch_timeout: None,
+                fetch_retries: None,
+                fetch_depth: None,
+                link_template: None,
             }],
+            key_descriptions: KeyDescriptions::new(),
+            type_titles: TypeTitles::new(),
+            type_order: Vec::new(),
+            sections: SectionMapping::new(),
+            type_aliases: TypeAliases::new(),
+            exclude_authors: Vec::new(),
+            issue_patterns: default_issue_patterns(),
+            team_trailer_key: "team".to_string(),
+            team_ignore_case: false,
+            fetch_timeout: None,
+            fetch_retries: 0,
+            fetch_depth: None,
+            link_template: None,
+            jobs: None,
         };
         let output = serde_yaml::from_str(input).unwrap();
         assert_eq!(expected, output);
@@ -82,17 +555,404 @@ projects:
       - foo
       - bar
     team: X functional
+    exclude_authors:
+      - "*[bot]"
+exclude_authors:
+  - renovate
+jobs: 4
 "#;
         let expected = Configuration {
-            default_branch: "master".to_string().into(),
+            default_branch: None,
+            group_by: None,
+            empty_key: None,
+            slack_webhook: None,
             projects: vec![Project {
                 name: "repo".to_string(),
                 origin: "git@example.com:user/repository.git".to_string().into(),
                 branches: Some(vec!["foo".to_string().into(), "bar".to_string().into()]),
-                team: Some("X functional".to_string()),
+                team: vec!["X functional".to_string()],
+                exclude_authors: vec!["*[bot]".to_string()],
+                group_by: None,
+                path: None,
+                token: None,
+                fetch_timeout: None,
+                fetch_retries: None,
+                fetch_depth: None,
+                link_template: None,
             }],
+            key_descriptions: KeyDescriptions::new(),
+            type_titles: TypeTitles::new(),
+            type_order: Vec::new(),
+            sections: SectionMapping::new(),
+            type_aliases: TypeAliases::new(),
+            exclude_authors: vec!["renovate".to_string()],
+            issue_patterns: default_issue_patterns(),
+            team_trailer_key: "team".to_string(),
+            team_ignore_case: false,
+            fetch_timeout: None,
+            fetch_retries: 0,
+            fetch_depth: None,
+            link_template: None,
+            jobs: NonZeroUsize::new(4),
         };
         let ouput = serde_yaml::from_str(input).unwrap();
         assert_eq!(expected, ouput);
     }
+
+    #[test]
+    fn test_get_branches_name() {
+        let mut project = Project {
+            name: "repo".to_string(),
+            origin: "git@example.com:user/repository.git".to_string().into(),
+            branches: None,
+            team: Vec::new(),
+            exclude_authors: Vec::new(),
+            group_by: None,
+            path: None,
+            token: None,
+            fetch_timeout: None,
+            fetch_retries: None,
+            fetch_depth: None,
+            link_template: None,
+        };
+        let main: BranchName = "main".to_string().into();
+
+        assert_eq!(None, project.get_branches_name(None));
+        assert_eq!(
+            Some(vec![main.clone()]),
+            project.get_branches_name(Some(&main))
+        );
+
+        project.branches = Some(vec!["dev".to_string().into()]);
+        assert_eq!(
+            Some(vec!["dev".to_string().into()]),
+            project.get_branches_name(Some(&main))
+        );
+    }
+
+    #[test]
+    fn test_group_by() {
+        let input = r#"
+group_by: [origin, commit-type]
+projects: []
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        let configured = vec![CommitField::Origin, CommitField::CommitType];
+        assert_eq!(configured, config.get_group_by(None));
+        assert_eq!(
+            vec![CommitField::Scope],
+            config.get_group_by(Some(&[CommitField::Scope]))
+        );
+
+        let config: Configuration = serde_yaml::from_str("projects: []").unwrap();
+        assert_eq!(3, config.get_group_by(None).len());
+
+        let invalid = serde_yaml::from_str::<Configuration>("group_by: [nope]\nprojects: []");
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_type_titles() {
+        let config: Configuration =
+            serde_yaml::from_str("type_titles:\n  fix: Fixes\nprojects: []").unwrap();
+        assert_eq!("Fixes", config.type_titles["fix"]);
+
+        // the former `types` attribute is still accepted
+        let config: Configuration =
+            serde_yaml::from_str("types:\n  fix: Fixes\nprojects: []").unwrap();
+        assert_eq!("Fixes", config.type_titles["fix"]);
+    }
+
+    #[test]
+    fn test_group_by_overrides() {
+        let input = r#"
+projects:
+  - name: repo
+    origin: git@example.com:user/repository.git
+    group_by: [scope]
+  - name: other
+    origin: git@example.com:user/other.git
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        let overrides = config.get_group_by_overrides();
+        assert_eq!(1, overrides.len());
+        assert_eq!(
+            Some(&vec![CommitField::Scope]),
+            overrides.get("git@example.com:user/repository.git")
+        );
+    }
+
+    #[test]
+    fn test_parse_teams() {
+        let input = r#"
+projects:
+  - name: repo
+    origin: git@example.com:user/repository.git
+    team: X functional, core,
+  - name: other
+    origin: git@example.com:user/other.git
+  - name: listed
+    origin: git@example.com:user/listed.git
+    team:
+      - " X functional "
+      - core
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        let expected = vec!["X functional".to_string(), "core".to_string()];
+        assert_eq!(expected, config.projects[0].team);
+        assert!(config.projects[1].team.is_empty());
+        assert_eq!(expected, config.projects[2].team);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let yaml = r#"
+projects:
+  - name: repo
+    origin: git@example.com:org/repo.git
+    branches: [main]
+"#;
+        let config = Configuration::from_reader(yaml.as_bytes()).unwrap();
+        assert_eq!("repo", config.projects[0].name);
+        assert_eq!(
+            Some(vec![BranchName::from("main".to_string())]),
+            config.projects[0].branches
+        );
+
+        let invalid = Configuration::from_reader("projects: 42".as_bytes());
+        assert!(matches!(invalid, Err(Error::Configuration(_))));
+    }
+
+    #[test]
+    fn test_repository_settings() {
+        let settings: RepositorySettings = serde_yaml::from_str(
+            r#"
+team: core, infra
+team_trailer_key: squad
+group_by: [commit-type]
+include_types: [feat, fix]
+exclude_types: [chore]
+exclude_authors: ["*[bot]"]
+since_tag: v1.0.0
+"#,
+        )
+        .unwrap();
+
+        let options = TraversalOptions {
+            exclude_type: vec!["docs".to_string()],
+            until_tag: Some("v2.0.0".to_string()),
+            ..TraversalOptions::default()
+        };
+        let options = settings.traversal_options(&options);
+        assert_eq!(vec!["feat", "fix"], options.include_type);
+        assert_eq!(vec!["docs"], options.exclude_type);
+        assert_eq!(vec!["*[bot]"], options.exclude_author);
+        assert_eq!(Some("v1.0.0".to_string()), options.since_tag);
+        assert_eq!(Some("v2.0.0".to_string()), options.until_tag);
+        let options = settings.traversal_options(&TraversalOptions {
+            include_type: vec!["perf".to_string()],
+            since_tag: Some("v1.1.0".to_string()),
+            ..TraversalOptions::default()
+        });
+        assert_eq!(vec!["perf"], options.include_type);
+        assert_eq!(Some("v1.1.0".to_string()), options.since_tag);
+
+        let filter = settings.team_filter(&[], None, false);
+        assert_eq!(vec!["core", "infra"], filter.teams);
+        assert_eq!("squad", filter.trailer_key);
+        let filter = settings.team_filter(&["web".to_string()], Some("team"), true);
+        assert_eq!(vec!["web"], filter.teams);
+        assert_eq!("team", filter.trailer_key);
+        assert!(filter.ignore_case);
+
+        assert_eq!(vec![CommitField::CommitType], settings.get_group_by(None));
+        assert_eq!(
+            vec![CommitField::Branch, CommitField::CommitType],
+            RepositorySettings::default().get_group_by(None)
+        );
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let lookup = |name: &str| match name {
+            "RESUME_TEST_TOKEN" => Some("s3cr3t".to_string()),
+            "RESUME_TEST_BRANCH" => Some("main".to_string()),
+            _ => None,
+        };
+        let yaml = r#"
+projects:
+  - name: repo
+    origin: https://${RESUME_TEST_TOKEN}@example.com/user/repository.git
+    branches: [$RESUME_TEST_BRANCH]
+  - name: local
+    path: /home/$RESUME_TEST_BRANCH/cost-$$5
+    token: $RESUME_TEST_TOKEN
+issue_patterns: ['\bGH-(\d+)$', '^v\d+$UNSET_SUFFIX', '${UNSET}']
+"#;
+        let config: Configuration = read_yaml_with(yaml.as_bytes(), &lookup).unwrap();
+        assert_eq!(
+            "https://s3cr3t@example.com/user/repository.git",
+            config.projects[0].origin.as_str()
+        );
+        assert_eq!(
+            Some(vec![BranchName::from("$RESUME_TEST_BRANCH".to_string())]),
+            config.projects[0].branches
+        );
+        assert_eq!(
+            Some(PathBuf::from("/home/main/cost-$5")),
+            config.projects[1].path
+        );
+        assert_eq!(Some("s3cr3t"), config.projects[1].token.as_deref());
+        assert_eq!(
+            vec![
+                r"\bGH-(\d+)$".to_string(),
+                r"^v\d+$UNSET_SUFFIX".to_string(),
+                "${UNSET}".to_string()
+            ],
+            config.issue_patterns
+        );
+
+        for origin in &[
+            "https://${RESUME_TEST_UNSET}@example.com",
+            "https://$RESUME_TEST_UNSET@example.com",
+        ] {
+            let yaml = format!("projects:\n  - name: repo\n    origin: {}\n", origin);
+            let unset = read_yaml_with::<Configuration, _>(yaml.as_bytes(), &lookup);
+            assert!(
+                matches!(unset, Err(Error::UnsetVariable(name)) if name == "RESUME_TEST_UNSET")
+            );
+        }
+    }
+
+    #[test]
+    fn test_fetch_policy() {
+        let input = r#"
+projects:
+  - name: repo
+    origin: git@example.com:user/repository.git
+  - name: slow
+    origin: git@example.com:user/slow.git
+    fetch_timeout: 300
+    fetch_retries: 0
+    clone_depth: 50
+fetch_timeout: 60
+fetch_retries: 2
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        let policy = config.get_fetch_policy(&config.projects[0]);
+        assert_eq!(Some(Duration::from_secs(60)), policy.timeout);
+        assert_eq!(2, policy.retries);
+        assert_eq!(None, policy.depth);
+        let policy = config.get_fetch_policy(&config.projects[1]);
+        assert_eq!(Some(Duration::from_secs(300)), policy.timeout);
+        assert_eq!(0, policy.retries);
+        assert_eq!(Some(50), policy.depth);
+    }
+
+    #[test]
+    fn test_link_template() {
+        let input = r#"
+projects:
+  - name: hosted
+    origin: git@github.com:org/hosted.git
+  - name: internal
+    origin: git@git.example.com:team/internal-api.git
+  - name: legacy
+    origin: git@git.example.com:team/legacy.git
+    link_template: https://legacy.example.com/commits/{short_hash}
+link_template: https://git.example.com/team/{repo}/commit/{hash}
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        let templates: Vec<_> = config
+            .projects
+            .iter()
+            .map(|project| config.get_link_template(project))
+            .collect();
+        assert_eq!(
+            vec![
+                Some("https://git.example.com/team/hosted/commit/{hash}".to_string()),
+                Some("https://git.example.com/team/internal-api/commit/{hash}".to_string()),
+                Some("https://legacy.example.com/commits/{short_hash}".to_string()),
+            ],
+            templates
+        );
+
+        // without a root template, only the GitHub and GitLab origins are linked
+        let input = r#"
+projects:
+  - name: hosted
+    origin: git@github.com:org/hosted.git
+  - name: internal
+    origin: git@git.example.com:team/internal-api.git
+"#;
+        let config: Configuration = serde_yaml::from_str(input).unwrap();
+        assert_eq!(
+            Some("https://github.com/org/hosted/commit/{hash}".to_string()),
+            config.get_link_template(&config.projects[0])
+        );
+        assert_eq!(None, config.get_link_template(&config.projects[1]));
+    }
+
+    #[test]
+    fn test_resolve_local_projects() {
+        let path = std::env::temp_dir();
+        let input = format!(
+            r#"
+projects:
+  - name: remote
+    origin: git@example.com:user/repository.git
+  - name: local
+    path: {}
+"#,
+            path.display()
+        );
+        let mut config: Configuration = serde_yaml::from_str(&input).unwrap();
+        config.resolve_local_projects().unwrap();
+        assert_eq!(
+            "git@example.com:user/repository.git",
+            config.projects[0].origin.as_str()
+        );
+        assert_eq!(
+            format!("file://{}", path.canonicalize().unwrap().display()),
+            config.projects[1].origin.as_str()
+        );
+
+        for (project, reason) in &[
+            (
+                "{ name: both, origin: git@example.com:user/repository.git, path: . }",
+                "has both an origin and a path",
+            ),
+            ("{ name: neither }", "has neither an origin nor a path"),
+            (
+                "{ name: relative, path: repository }",
+                "has a relative path out of a configuration file",
+            ),
+        ] {
+            let input = format!("projects:\n  - {}\n", project);
+            let mut config: Configuration = serde_yaml::from_str(&input).unwrap();
+            let result = config.resolve_local_projects();
+            assert!(matches!(result, Err(Error::InvalidProject(_, found)) if found == *reason));
+        }
+    }
+
+    #[test]
+    fn test_relative_local_project() {
+        let folder =
+            std::env::temp_dir().join(format!("resume-relative-project-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(folder.join("repository")).unwrap();
+        let file = folder.join("resume.yaml");
+        std::fs::write(&file, "projects:\n  - name: local\n    path: repository\n").unwrap();
+
+        let config = Configuration::from_file(&file);
+        let unresolved = Configuration::read_source(&file.to_string_lossy());
+
+        let expected = folder.canonicalize().unwrap().join("repository");
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(
+            format!("file://{}", expected.display()),
+            config.unwrap().projects[0].origin.as_str()
+        );
+        assert_eq!(Some(expected), unresolved.unwrap().projects[0].path);
+    }
 }
@@ -0,0 +1,360 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Formatter},
+};
+
+use crate::{
+    changelog::CommitField, config::Configuration, filter::BranchPattern, links::split_origin,
+    message::IssuePatterns, project::Project, snapshots::BranchName,
+};
+
+/// Problem of a configuration, which deserialized fine but can't run as intended
+#[derive(Debug, Eq, PartialEq)]
+pub enum Issue {
+    /// Index of the project without name
+    EmptyName(usize),
+    DuplicateName(String),
+    NoOrigin(String),
+    OriginAndPath(String),
+    InvalidOrigin(String, String),
+    /// Origin shared by the named projects
+    DuplicateOrigin(String, Vec<String>),
+    /// Field repeated in the root `group_by`, or in the named project's
+    DuplicateGroupBy(Option<String>, CommitField),
+    /// Project's `group_by` including the origin, which its entries are always grouped by
+    ProjectGroupByOrigin(String),
+    DuplicateTypeOrder(String),
+    /// Alias of a commit type whose canonical type is an alias too, which isn't followed
+    ChainedTypeAlias(String, String),
+    InvalidIssuePattern(String, String),
+    /// Project's branch pattern, with its error
+    InvalidBranchPattern(String, BranchName, String),
+    /// Project whose repository can't be reached or opened, with the error
+    UnreachableRepository(String, String),
+    /// Project's branch, or branch pattern, matching no branch of its repository
+    MissingBranch(String, BranchName),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyName(index) => write!(f, "project #{} has no name", index + 1),
+            Self::DuplicateName(name) => write!(f, "project name '{}' is used twice", name),
+            Self::NoOrigin(name) => {
+                write!(f, "project '{}' has neither an origin nor a path", name)
+            }
+            Self::OriginAndPath(name) => {
+                write!(f, "project '{}' has both an origin and a path", name)
+            }
+            Self::InvalidOrigin(name, origin) => write!(
+                f,
+                "project '{}' has an invalid origin '{}', expected a URL or `host:path`",
+                name, origin
+            ),
+            Self::DuplicateOrigin(origin, names) => write!(
+                f,
+                "origin '{}' is shared by projects '{}'",
+                origin,
+                names.join("', '")
+            ),
+            Self::DuplicateGroupBy(None, field) => {
+                write!(f, "group_by lists '{}' more than once", field)
+            }
+            Self::DuplicateGroupBy(Some(name), field) => write!(
+                f,
+                "project '{}' group_by lists '{}' more than once",
+                name, field
+            ),
+            Self::ProjectGroupByOrigin(name) => write!(
+                f,
+                "project '{}' group_by lists 'origin', which its entries are always grouped by",
+                name
+            ),
+            Self::DuplicateTypeOrder(ctype) => {
+                write!(f, "type_order lists '{}' more than once", ctype)
+            }
+            Self::ChainedTypeAlias(alias, canonical) => write!(
+                f,
+                "type alias '{}' maps to '{}', which is an alias too",
+                alias, canonical
+            ),
+            Self::InvalidIssuePattern(pattern, error) => {
+                write!(f, "issue pattern '{}' is invalid: {}", pattern, error)
+            }
+            Self::InvalidBranchPattern(name, branch, error) => write!(
+                f,
+                "project '{}' branch pattern '{}' is invalid: {}",
+                name, branch, error
+            ),
+            Self::UnreachableRepository(name, error) => {
+                write!(f, "project '{}' repository is unreachable: {}", name, error)
+            }
+            Self::MissingBranch(name, branch) => write!(
+                f,
+                "project '{}' branch '{}' doesn't exist in its repository",
+                name, branch
+            ),
+        }
+    }
+}
+
+/// Check the configuration beyond its deserialization, listing all its issues
+pub fn check(config: &Configuration) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut names = HashMap::new();
+    let mut origins: HashMap<&str, Vec<String>> = HashMap::new();
+    for (index, project) in config.projects.iter().enumerate() {
+        let name = project.name.trim();
+        if name.is_empty() {
+            issues.push(Issue::EmptyName(index));
+        } else if names.insert(name, index).is_some() {
+            issues.push(Issue::DuplicateName(name.to_owned()));
+        }
+
+        let origin = project.origin.as_str();
+        match &project.path {
+            Some(_) if !origin.is_empty() => issues.push(Issue::OriginAndPath(name.to_owned())),
+            Some(_) => {}
+            None if origin.is_empty() => issues.push(Issue::NoOrigin(name.to_owned())),
+            None if !origin.starts_with("file://") && split_origin(origin).is_none() => {
+                issues.push(Issue::InvalidOrigin(name.to_owned(), origin.to_owned()))
+            }
+            None => origins.entry(origin).or_default().push(name.to_owned()),
+        }
+
+        for branch_name in project.branches.iter().flatten() {
+            if let Err(error) = BranchPattern::parse(branch_name.as_str()) {
+                issues.push(Issue::InvalidBranchPattern(
+                    name.to_owned(),
+                    branch_name.clone(),
+                    error.root_cause().to_string(),
+                ));
+            }
+        }
+
+        if let Some(group_by) = &project.group_by {
+            if group_by.contains(&CommitField::Origin) {
+                issues.push(Issue::ProjectGroupByOrigin(name.to_owned()));
+            }
+            check_group_by(group_by, Some(name), &mut issues);
+        }
+    }
+    let mut origins: Vec<_> = origins
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .collect();
+    origins.sort();
+    for (origin, names) in origins {
+        issues.push(Issue::DuplicateOrigin(origin.to_owned(), names));
+    }
+
+    if let Some(group_by) = &config.group_by {
+        check_group_by(group_by, None, &mut issues);
+    }
+    for (index, ctype) in config.type_order.iter().enumerate() {
+        if config.type_order[..index].contains(ctype) {
+            issues.push(Issue::DuplicateTypeOrder(ctype.to_owned()));
+        }
+    }
+    for (alias, canonical) in &config.type_aliases {
+        let is_alias = config
+            .type_aliases
+            .keys()
+            .any(|other| other.eq_ignore_ascii_case(canonical));
+        if is_alias && !alias.eq_ignore_ascii_case(canonical) {
+            issues.push(Issue::ChainedTypeAlias(
+                alias.to_owned(),
+                canonical.to_owned(),
+            ));
+        }
+    }
+    for pattern in &config.issue_patterns {
+        if let Err(error) = IssuePatterns::new(&[pattern]) {
+            issues.push(Issue::InvalidIssuePattern(
+                pattern.to_owned(),
+                error.to_string(),
+            ));
+        }
+    }
+    issues
+}
+
+/// Check that the projects' repositories are reachable and have their branches: the local
+/// ones, and unless offline the origins, listing their branches without fetching them
+pub fn check_repositories(config: &Configuration, offline: bool) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for project in &config.projects {
+        let branches = match (&project.path, project.origin.as_str()) {
+            (Some(path), "") => Project::from_standalone_repository(&path.to_string_lossy(), &[])
+                .and_then(|repository| repository.list_branches()),
+            (None, origin) if !offline && !origin.is_empty() => {
+                Project::list_origin_branches(&project.origin, project.get_token())
+            }
+            _ => continue,
+        };
+        let branches = match branches {
+            Ok(branches) => branches,
+            Err(error) => {
+                issues.push(Issue::UnreachableRepository(
+                    project.name.clone(),
+                    error.root_cause().to_string(),
+                ));
+                continue;
+            }
+        };
+        let configured = project.get_branches_name(config.default_branch.as_ref());
+        for branch_name in configured.iter().flatten() {
+            let exists = match BranchPattern::parse(branch_name.as_str()) {
+                Ok(Some(pattern)) => branches.iter().any(|name| pattern.matches(name.as_str())),
+                Ok(None) => branches.contains(branch_name),
+                // already reported by `check`
+                Err(_) => true,
+            };
+            if !exists {
+                issues.push(Issue::MissingBranch(
+                    project.name.clone(),
+                    branch_name.clone(),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+fn check_group_by(group_by: &[CommitField], project: Option<&str>, issues: &mut Vec<Issue>) {
+    for (index, field) in group_by.iter().enumerate() {
+        if group_by[..index].contains(field) {
+            issues.push(Issue::DuplicateGroupBy(
+                project.map(str::to_owned),
+                field.clone(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(yaml: &str) -> Configuration {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_check_valid_configuration() {
+        let config = parse(
+            r#"
+projects:
+  - name: api
+    origin: git@example.com:org/api.git
+  - name: web
+    origin: https://example.com/org/web.git
+    group_by: [branch, commit-type]
+  - name: local
+    path: /srv/git/local
+group_by: [origin, commit-type]
+type_order: [feat, fix]
+type_aliases:
+  bugfix: fix
+"#,
+        );
+        assert_eq!(Vec::<Issue>::new(), check(&config));
+    }
+
+    #[test]
+    fn test_check_lists_all_issues() {
+        let config = parse(
+            r#"
+projects:
+  - name: ""
+    origin: git@example.com:org/api.git
+  - name: web
+    origin: https://example.com/org/web.git
+    group_by: [origin, scope, scope]
+  - name: web
+    origin: git@example.com:org/api.git
+  - name: nowhere
+  - name: both
+    origin: git@example.com:org/both.git
+    path: /srv/git/both
+  - name: typo
+    origin: example.com/org/typo
+group_by: [branch, branch]
+type_order: [feat, fix, feat]
+type_aliases:
+  bug: bugfix
+  bugfix: fix
+issue_patterns: ['JIRA-(\d+']
+"#,
+        );
+        let issues: Vec<_> = check(&config).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            vec![
+                "project #1 has no name",
+                "project 'web' group_by lists 'origin', which its entries are always grouped by",
+                "project 'web' group_by lists 'scope' more than once",
+                "project name 'web' is used twice",
+                "project 'nowhere' has neither an origin nor a path",
+                "project 'both' has both an origin and a path",
+                "project 'typo' has an invalid origin 'example.com/org/typo', expected a URL or `host:path`",
+                "origin 'git@example.com:org/api.git' is shared by projects '', 'web'",
+                "group_by lists 'branch' more than once",
+                "type_order lists 'feat' more than once",
+                "type alias 'bug' maps to 'bugfix', which is an alias too",
+            ],
+            issues[..11].to_vec()
+        );
+        assert_eq!(12, issues.len());
+        assert!(issues[11].starts_with("issue pattern 'JIRA-(\\d+' is invalid: "));
+    }
+
+    #[test]
+    fn test_check_repositories() {
+        let path = std::env::temp_dir().join(format!("resume-check-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let repository = git2::Repository::init(&path).unwrap();
+        let signature = git2::Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        let commit = repository
+            .commit(None, &signature, &signature, "feat: init", &tree, &[])
+            .unwrap();
+        let commit = repository.find_commit(commit).unwrap();
+        repository.branch("main", &commit, false).unwrap();
+        repository.branch("release/1.0", &commit, false).unwrap();
+
+        let config = parse(&format!(
+            r#"
+projects:
+  - name: remote
+    origin: file://{0}
+    branches: [main, "release/*", develop]
+  - name: local
+    path: {0}
+    branches: ["regex:hotfix/.*"]
+  - name: gone
+    origin: file://{0}-gone
+    branches: [main]
+"#,
+            path.display()
+        ));
+        let issues: Vec<_> = check_repositories(&config, false)
+            .iter()
+            .map(Issue::to_string)
+            .collect();
+        assert_eq!(3, issues.len(), "{:?}", issues);
+        assert_eq!(
+            "project 'remote' branch 'develop' doesn't exist in its repository",
+            issues[0]
+        );
+        assert_eq!(
+            "project 'local' branch 'regex:hotfix/.*' doesn't exist in its repository",
+            issues[1]
+        );
+        assert!(issues[2].starts_with("project 'gone' repository is unreachable: "));
+
+        let issues = check_repositories(&config, true);
+        assert_eq!(1, issues.len());
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{fs, path::Path};
+
+use crate::error::{Error, Result};
+
+/// Capabilities granted to the current run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunContext {
+    /// Forbid any write to the file system: cache, state and output files.
+    /// Writing to the standard output stays allowed.
+    pub read_only: bool,
+}
+
+impl RunContext {
+    /// Fail with a `ReadOnlyViolation` if the run isn't allowed to write to the given path
+    pub fn check_write(&self, path: &Path) -> Result<()> {
+        if self.read_only {
+            Err(Error::ReadOnlyViolation(path.to_path_buf()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create the folder and its missing parents, if the run is allowed to write
+    pub fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.check_write(path)?;
+        Ok(fs::create_dir_all(path)?)
+    }
+
+    /// Write the file, if the run is allowed to
+    pub fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        self.check_write(path)?;
+        Ok(fs::write(path, contents)?)
+    }
+
+    /// Create or truncate the file at the given path, to write it as a stream
+    pub fn create(&self, path: &Path) -> Result<fs::File> {
+        self.check_write(path)?;
+        Ok(fs::File::create(path)?)
+    }
+}
+
+/// Extract the path named by a `ReadOnlyViolation`
+#[cfg(test)]
+pub fn violated_path(result: Result<impl std::fmt::Debug>) -> std::path::PathBuf {
+    match result {
+        Err(Error::ReadOnlyViolation(path)) => path,
+        other => panic!("expected a read-only violation, got {:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const READ_ONLY: RunContext = RunContext { read_only: true };
+
+    #[test]
+    fn test_read_only_create_dir() {
+        let path = std::env::temp_dir().join(format!("resume-ro-dir-{}", std::process::id()));
+        assert_eq!(path, violated_path(READ_ONLY.create_dir_all(&path)));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_read_only_write() {
+        let path = std::env::temp_dir().join(format!("resume-ro-file-{}", std::process::id()));
+        assert_eq!(path, violated_path(READ_ONLY.write(&path, "output")));
+        assert!(!path.exists());
+        assert_eq!(path, violated_path(READ_ONLY.create(&path)));
+        assert!(!path.exists());
+
+        RunContext::default().write(&path, "output").unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!("output", written);
+    }
+}
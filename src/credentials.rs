@@ -0,0 +1,186 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Formatter},
+};
+
+use git2::CredentialType;
+use git2_credentials::CredentialUI;
+
+/// Answer to a credentials request of git
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CredentialStep {
+    /// Provide no actual credentials, to access public repositories
+    Anonymous,
+    /// Provide the configured token as password
+    Token,
+    /// Look for actual credentials: SSH agent, credential helpers, prompt, etc
+    Escalate,
+}
+
+/// Way a repository has been accessed
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum AuthMethod {
+    Anonymous,
+    Token,
+    Credentials,
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Anonymous => write!(f, "anonymous"),
+            Self::Token => write!(f, "token"),
+            Self::Credentials => write!(f, "credentials"),
+        }
+    }
+}
+
+/// Escalation of the credentials given to an origin. HTTP(S) origins are first accessed
+/// anonymously, then with actual credentials if the server rejects the anonymous access.
+/// Given a token, they're then accessed with it before any other credentials.
+/// Other origins, like SSH ones, get actual credentials straight away.
+#[derive(Debug, Clone)]
+pub struct CredentialEscalation {
+    anonymous_first: bool,
+    token: Option<String>,
+    last_step: Option<CredentialStep>,
+    requests: usize,
+}
+
+impl CredentialEscalation {
+    pub fn new(url: &str) -> Self {
+        Self {
+            anonymous_first: url.starts_with("https://") || url.starts_with("http://"),
+            token: None,
+            last_step: None,
+            requests: 0,
+        }
+    }
+
+    /// Access HTTP(S) origins with the given token, ignored for other origins
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token.filter(|_| self.anonymous_first);
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Decide how to answer the next credentials request, given the allowed credential types
+    pub fn next_step(&mut self, allowed: CredentialType) -> CredentialStep {
+        self.requests += 1;
+        let anonymous_allowed =
+            allowed.intersects(CredentialType::USER_PASS_PLAINTEXT | CredentialType::DEFAULT);
+        let step = if self.anonymous_first && self.requests == 1 && anonymous_allowed {
+            CredentialStep::Anonymous
+        } else if self.token.is_some()
+            && !matches!(
+                self.last_step,
+                Some(CredentialStep::Token) | Some(CredentialStep::Escalate)
+            )
+            && allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+        {
+            CredentialStep::Token
+        } else {
+            CredentialStep::Escalate
+        };
+        self.last_step = Some(step);
+        step
+    }
+
+    /// Get the method used by the last successful access
+    pub fn method(&self) -> AuthMethod {
+        match self.last_step {
+            None | Some(CredentialStep::Anonymous) => AuthMethod::Anonymous,
+            Some(CredentialStep::Token) => AuthMethod::Token,
+            Some(CredentialStep::Escalate) => AuthMethod::Credentials,
+        }
+    }
+}
+
+/// Credentials UI failing instead of prompting, for sessions without a terminal
+pub struct NonInteractiveUI;
+
+impl CredentialUI for NonInteractiveUI {
+    fn ask_user_password(&self, _username: &str) -> Result<(String, String), Box<dyn StdError>> {
+        Err("no terminal to prompt for a username and password, set GIT_TOKEN".into())
+    }
+
+    fn ask_ssh_passphrase(&self, _passphrase_prompt: &str) -> Result<String, Box<dyn StdError>> {
+        Err("no terminal to prompt for an SSH key passphrase".into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_public_https_repository() {
+        // the server doesn't ask for credentials at all
+        let escalation = CredentialEscalation::new("https://example.com/public.git");
+        assert_eq!(AuthMethod::Anonymous, escalation.method());
+
+        // or accepts the anonymous answer of its single request
+        let mut escalation = CredentialEscalation::new("https://example.com/public.git");
+        assert_eq!(
+            CredentialStep::Anonymous,
+            escalation.next_step(CredentialType::USER_PASS_PLAINTEXT)
+        );
+        assert_eq!(AuthMethod::Anonymous, escalation.method());
+    }
+
+    #[test]
+    fn test_private_https_repository() {
+        let mut escalation = CredentialEscalation::new("https://example.com/private.git");
+        let allowed = CredentialType::USER_PASS_PLAINTEXT;
+        assert_eq!(CredentialStep::Anonymous, escalation.next_step(allowed));
+        assert_eq!(CredentialStep::Escalate, escalation.next_step(allowed));
+        assert_eq!(CredentialStep::Escalate, escalation.next_step(allowed));
+        assert_eq!(AuthMethod::Credentials, escalation.method());
+    }
+
+    #[test]
+    fn test_ssh_repository() {
+        let mut escalation = CredentialEscalation::new("git@example.com:user/repository.git");
+        assert_eq!(
+            CredentialStep::Escalate,
+            escalation.next_step(CredentialType::USERNAME)
+        );
+        assert_eq!(
+            CredentialStep::Escalate,
+            escalation.next_step(CredentialType::SSH_KEY)
+        );
+        assert_eq!(AuthMethod::Credentials, escalation.method());
+    }
+
+    #[test]
+    fn test_https_without_anonymous_credential_type() {
+        let mut escalation = CredentialEscalation::new("https://example.com/repository.git");
+        assert_eq!(
+            CredentialStep::Escalate,
+            escalation.next_step(CredentialType::SSH_KEY)
+        );
+        assert_eq!(AuthMethod::Credentials, escalation.method());
+    }
+
+    #[test]
+    fn test_https_repository_with_token() {
+        let token = Some("s3cr3t".to_string());
+        let mut escalation =
+            CredentialEscalation::new("https://example.com/private.git").with_token(token.clone());
+        let allowed = CredentialType::USER_PASS_PLAINTEXT;
+        assert_eq!(CredentialStep::Anonymous, escalation.next_step(allowed));
+        assert_eq!(CredentialStep::Token, escalation.next_step(allowed));
+        assert_eq!(AuthMethod::Token, escalation.method());
+        // a rejected token escalates to the other credentials
+        assert_eq!(CredentialStep::Escalate, escalation.next_step(allowed));
+        assert_eq!(CredentialStep::Escalate, escalation.next_step(allowed));
+        assert_eq!(AuthMethod::Credentials, escalation.method());
+
+        let escalation =
+            CredentialEscalation::new("git@example.com:user/repository.git").with_token(token);
+        assert_eq!(None, escalation.token());
+    }
+}
@@ -1,19 +1,117 @@
 use std::{
     error::Error as StdError,
     fmt::{self, Formatter},
+    path::PathBuf,
+};
+
+use crate::{
+    message::Rule,
+    snapshots::{BranchName, CommitHash, RepositoryOrigin},
 };
 
 #[derive(Debug)]
 pub enum Error {
     InvalidSelector(String),
     InvalidIndex(String),
+    InvalidPeriod(String),
+    InvalidGroupOrder(String),
+    InvalidEntryOrder(String),
+    InvalidVersion(String),
+    /// Environment variable referenced by the configuration but not set
+    UnsetVariable(String),
+    /// Misconfigured project, with its name and the reason
+    InvalidProject(String, String),
     OutputType(String),
+    InvalidShell(String),
     SnapshotDoesntExist(String),
     InvalidSnapshotRef(String),
+    /// Refusal to amend the most recent snapshot, with the reason
+    NotAmendable(String),
+    InvalidCommitHash(RepositoryOrigin, BranchName, CommitHash),
+    UnreachableSentinel(RepositoryOrigin, BranchName, CommitHash),
+    /// Sentinel missing from a shallow repository, whose history doesn't reach it
+    ShallowSentinel(RepositoryOrigin, BranchName, CommitHash),
+    TagNotFound(String),
+    UnknownDefaultBranch(String),
+    /// Repository without any branch, by name
+    NoBranch(String),
+    /// Fetch abandoned after the given timeout
+    FetchTimeout(std::time::Duration),
+    /// Fetch timed out then still running after as long again, which isn't retried
+    FetchStalled(std::time::Duration),
+    ReadOnlyViolation(PathBuf),
+    /// Configuration file which `init` won't overwrite without `--force`
+    ConfigurationExists(PathBuf),
     Git(git2::Error),
     IO(std::io::Error),
     Configuration(YamlErrorWrapper),
+    RunReport(serde_json::Error),
+    InvalidIssuePattern(regex::Error),
+    /// Branch specification with an invalid regular expression
+    InvalidBranchPattern(String, regex::Error),
     Format(std::fmt::Error),
+    Template(tera::Error),
+    /// Failure to write the CSV or TSV output
+    Csv(csv::Error),
+    /// Failed HTTP request, or error response, like the Slack webhook's. The URL is left
+    /// out, webhooks' ones being secrets.
+    Http(reqwest::Error),
+    /// Failure to start the threads processing the projects
+    ThreadPool(rayon::ThreadPoolBuildError),
+    MissingTemplate,
+    InvalidMessage(Box<pest::error::Error<Rule>>),
+    LintViolations(usize),
+    /// Number of problems found by `config check`
+    CheckFailures(usize),
+    /// Number of projects which failed with `--keep-going`
+    FailedProjects(usize),
+    /// No changes to report with `--fail-on-empty`
+    EmptyChangeLog,
+    UnparsableCommits(Vec<UnparsableCommit>),
+    /// Failure of a project's processing, with its name and origin
+    Project(String, RepositoryOrigin, Box<Error>),
+    /// Failure of a step on branches
+    Branch(BranchStep, Vec<BranchName>, Box<Error>),
+}
+
+/// Step of a project's processing done on its branches
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BranchStep {
+    Fetch,
+    Read,
+    Traverse,
+}
+
+impl fmt::Display for BranchStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let step = match self {
+            Self::Fetch => "fetch",
+            Self::Read => "read",
+            Self::Traverse => "traverse",
+        };
+        write!(f, "{}", step)
+    }
+}
+
+impl Error {
+    /// Attach the branches the failed step was done on
+    pub fn in_branches(self, step: BranchStep, branches: &[BranchName]) -> Self {
+        Self::Branch(step, branches.to_vec(), Box::new(self))
+    }
+
+    /// Get the innermost error of the chain of sources
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        let mut cause: &(dyn StdError + 'static) = self;
+        while let Some(source) = cause.source() {
+            cause = source;
+        }
+        cause
+    }
+
+    /// Attach the project whose processing failed
+    pub fn in_project(self, name: &str, origin: &RepositoryOrigin) -> Self {
+        Self::Project(name.to_owned(), origin.clone(), Box::new(self))
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -27,19 +125,127 @@ impl fmt::Display for Error {
             Self::InvalidIndex(index) => {
                 write!(f, "invalid index {}", index)
             }
+            Self::InvalidPeriod(period) => {
+                write!(f, "invalid period '{}'", period)
+            }
+            Self::InvalidGroupOrder(order) => {
+                write!(f, "invalid group order '{}'", order)
+            }
+            Self::InvalidEntryOrder(order) => {
+                write!(f, "invalid entry order '{}'", order)
+            }
+            Self::InvalidVersion(version) => {
+                write!(f, "invalid version '{}', expected X.Y.Z", version)
+            }
+            Self::UnsetVariable(name) => {
+                write!(
+                    f,
+                    "the environment variable '{}' referenced by the configuration isn't set",
+                    name
+                )
+            }
+            Self::InvalidProject(name, reason) => {
+                write!(f, "the project '{}' {}", name, reason)
+            }
             Self::OutputType(format) => {
                 write!(f, "invalid output type '{}'", format)
             }
+            Self::InvalidShell(shell) => write!(f, "unsupported shell '{}'", shell),
             Self::SnapshotDoesntExist(reference) => {
                 write!(f, "the snapshot '{}' doesn't exist", reference)
             }
             Self::InvalidSnapshotRef(reference) => {
                 write!(f, "'{}' is not a valid snapshot reference", reference)
             }
+            Self::NotAmendable(reason) => write!(
+                f,
+                "can't amend the most recent snapshot, {}; use --force to amend it anyway",
+                reason
+            ),
+            Self::InvalidCommitHash(origin, branch, hash) => write!(
+                f,
+                "invalid commit hash '{}' in snapshot of {} branch {}",
+                hash, origin, branch
+            ),
+            Self::UnreachableSentinel(origin, branch, hash) => write!(
+                f,
+                "commit '{}' of {} branch {} doesn't exist in the repository",
+                hash, origin, branch
+            ),
+            Self::ShallowSentinel(origin, branch, hash) => write!(
+                f,
+                "commit '{}' of {} branch {} is beyond the shallow history of the repository; \
+                 deepen it to reach the commit",
+                hash, origin, branch
+            ),
+            Self::TagNotFound(tag) => write!(f, "the tag '{}' doesn't exist", tag),
+            Self::FetchTimeout(timeout) => write!(f, "the fetch timed out after {:?}", timeout),
+            Self::FetchStalled(timeout) => write!(
+                f,
+                "the fetch timed out after {:?} and didn't stop once cancelled",
+                timeout
+            ),
+            Self::ReadOnlyViolation(path) => {
+                write!(f, "read-only mode forbids writing to {}", path.display())
+            }
+            Self::ConfigurationExists(path) => write!(
+                f,
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            ),
+            Self::NoBranch(repository) => write!(
+                f,
+                "the repository '{}' has no branch, its commits may only be reachable from a \
+                 detached HEAD; create a branch to traverse them",
+                repository
+            ),
+            Self::UnknownDefaultBranch(project) => {
+                write!(
+                    f,
+                    "can't detect the default branch of project '{}'",
+                    project
+                )
+            }
             Self::Git(_) => write!(f, "git error"),
             Self::IO(_) => write!(f, "I/O error"),
             Self::Configuration(_) => write!(f, "Invalid configuration"),
+            Self::RunReport(_) => write!(f, "invalid run report"),
+            Self::InvalidIssuePattern(_) => write!(f, "invalid issue pattern"),
+            Self::InvalidBranchPattern(spec, _) => write!(f, "invalid branch pattern '{}'", spec),
             Self::Format(_) => write!(f, "Formatting error"),
+            Self::Template(_) => write!(f, "template rendering error"),
+            Self::Csv(_) => write!(f, "CSV rendering error"),
+            Self::Http(_) => write!(f, "HTTP request failed"),
+            Self::ThreadPool(_) => write!(f, "failed to start the processing threads"),
+            Self::MissingTemplate => write!(f, "the template output requires a --template file"),
+            Self::InvalidMessage(_) => write!(
+                f,
+                "not a conventional commit message, expected a `type(scope)!: summary` headline"
+            ),
+            Self::LintViolations(count) => write!(f, "{} lint violation(s)", count),
+            Self::CheckFailures(count) => write!(f, "{} problem(s) found", count),
+            Self::FailedProjects(count) => write!(f, "{} project(s) failed", count),
+            Self::EmptyChangeLog => write!(f, "no changes to report"),
+            Self::UnparsableCommits(commits) => {
+                write!(
+                    f,
+                    "{} commit(s) aren't conventional commits:",
+                    commits.len()
+                )?;
+                for commit in commits.iter().take(MAX_LISTED_COMMITS) {
+                    write!(f, "\n  {}", commit)?;
+                }
+                if commits.len() > MAX_LISTED_COMMITS {
+                    write!(f, "\n  and {} more", commits.len() - MAX_LISTED_COMMITS)?;
+                }
+                Ok(())
+            }
+            Self::Project(name, origin, _) => write!(f, "project {} ({})", name, origin),
+            Self::Branch(step, branches, _) => {
+                let names: Vec<_> = branches.iter().map(BranchName::as_str).collect();
+                let plural = if branches.len() > 1 { "es" } else { "" };
+                write!(f, "{} branch{} {}", step, plural, names.join(", "))
+            }
         }
     }
 }
@@ -50,7 +256,17 @@ impl StdError for Error {
             Self::Git(source) => Some(source),
             Self::IO(source) => Some(source),
             Self::Configuration(source) => Some(source),
+            Self::RunReport(source) => Some(source),
+            Self::InvalidIssuePattern(source) => Some(source),
+            Self::InvalidBranchPattern(_, source) => Some(source),
             Self::Format(source) => Some(source),
+            Self::Template(source) => Some(source),
+            Self::Csv(source) => Some(source),
+            Self::Http(source) => Some(source),
+            Self::ThreadPool(source) => Some(source),
+            Self::InvalidMessage(source) => Some(source),
+            Self::Project(_, _, source) => Some(source.as_ref()),
+            Self::Branch(_, _, source) => Some(source.as_ref()),
             _ => None,
         }
     }
@@ -68,25 +284,142 @@ impl From<serde_yaml::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::RunReport(error)
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(error: regex::Error) -> Self {
+        Error::InvalidIssuePattern(error)
+    }
+}
+
 impl From<std::fmt::Error> for Error {
     fn from(error: std::fmt::Error) -> Self {
         Error::Format(error)
     }
 }
 
+impl From<tera::Error> for Error {
+    fn from(error: tera::Error) -> Self {
+        Error::Template(error)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Http(error.without_url())
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for Error {
+    fn from(error: rayon::ThreadPoolBuildError) -> Self {
+        Error::ThreadPool(error)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(error: csv::Error) -> Self {
+        Error::Csv(error)
+    }
+}
+
 impl From<git2::Error> for Error {
     fn from(error: git2::Error) -> Self {
         Error::Git(error)
     }
 }
 
+/// Maximum number of commits listed by the `UnparsableCommits` error
+const MAX_LISTED_COMMITS: usize = 10;
+
+/// Commit whose message failed to parse, reported in strict mode
+#[derive(Debug, Eq, PartialEq)]
+pub struct UnparsableCommit {
+    pub hash: String,
+    pub headline: String,
+    /// Line and column where the parser failed
+    pub position: (usize, usize),
+}
+
+impl fmt::Display for UnparsableCommit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} (at {}:{})",
+            &self.hash[..self.hash.len().min(7)],
+            self.headline,
+            self.position.0,
+            self.position.1
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct YamlErrorWrapper(serde_yaml::Error);
 
 impl fmt::Display for YamlErrorWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0.to_string())
+        write!(f, "{}", self.0)
     }
 }
 
 impl StdError for YamlErrorWrapper {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Join the error's display with its sources', like `main` prints them
+    fn chain(error: &Error) -> String {
+        let mut chain = vec![error.to_string()];
+        let mut source = error.source();
+        while let Some(cause) = source {
+            chain.push(cause.to_string());
+            source = cause.source();
+        }
+        chain.join(": ")
+    }
+
+    #[test]
+    fn test_context_display() {
+        let origin: RepositoryOrigin = "git@example.com:user/foo.git".to_string().into();
+        let main: BranchName = "main".to_string().into();
+        let dev: BranchName = "dev".to_string().into();
+
+        let error = Error::TagNotFound("v1.0".to_string())
+            .in_branches(BranchStep::Traverse, std::slice::from_ref(&main))
+            .in_project("foo", &origin);
+        assert_eq!(
+            "project foo (git@example.com:user/foo.git): traverse branch main: the tag 'v1.0' doesn't exist",
+            chain(&error)
+        );
+
+        let error = Error::from(git2::Error::from_str("connection refused"))
+            .in_branches(BranchStep::Fetch, &[main, dev])
+            .in_project("foo", &origin);
+        assert_eq!(
+            "project foo (git@example.com:user/foo.git): fetch branches main, dev: git error: connection refused",
+            chain(&error)
+        );
+    }
+
+    #[test]
+    fn test_unparsable_commits_display() {
+        let commits = (0..12)
+            .map(|i| UnparsableCommit {
+                hash: format!("{:040}", i),
+                headline: format!("WIP {}", i),
+                position: (1, 4),
+            })
+            .collect();
+        let display = Error::UnparsableCommits(commits).to_string();
+        let lines: Vec<_> = display.lines().collect();
+        assert_eq!("12 commit(s) aren't conventional commits:", lines[0]);
+        assert_eq!("  0000000 WIP 0 (at 1:4)", lines[1]);
+        assert_eq!(12, lines.len());
+        assert_eq!("  and 2 more", lines[11]);
+    }
+}
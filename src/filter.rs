@@ -0,0 +1,322 @@
+use regex::Regex;
+
+use crate::{
+    error::{Error, Result},
+    message::{Author, CommitType, ConventionalMessage},
+};
+
+/// Filter on people (authors or committers). A pattern matches a person if it's
+/// contained, case-insensitively, in their name or email. Patterns with `*` or `?`
+/// wildcards must match the whole name or email instead.
+pub struct PersonFilter<'a> {
+    /// Keep only the people matching any of these patterns, everyone if empty
+    pub include: &'a [String],
+    /// Drop the people matching any of these patterns
+    pub exclude: &'a [String],
+}
+
+impl<'a> PersonFilter<'a> {
+    pub fn matches(&self, person: &Author) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| is_match(pattern, person)))
+            && !self.exclude.iter().any(|pattern| is_match(pattern, person))
+    }
+}
+
+fn is_match(pattern: &str, person: &Author) -> bool {
+    let pattern = pattern.to_lowercase();
+    let matches = |value: &str| {
+        let value = value.to_lowercase();
+        if pattern.contains(['*', '?']) {
+            glob_match(&pattern, &value)
+        } else {
+            value.contains(&pattern)
+        }
+    };
+    matches(&person.name) || person.email.as_deref().map(matches).unwrap_or(false)
+}
+
+/// Filter on the commit types, compared case-insensitively
+pub struct TypeFilter<'a> {
+    /// Keep only these types, all of them if empty
+    pub include: &'a [String],
+    /// Drop these types
+    pub exclude: &'a [String],
+}
+
+impl<'a> TypeFilter<'a> {
+    pub fn matches(&self, ctype: &CommitType) -> bool {
+        let listed = |types: &[String]| {
+            types
+                .iter()
+                .any(|listed| listed.eq_ignore_ascii_case(ctype.as_str()))
+        };
+        (self.include.is_empty() || listed(self.include)) && !listed(self.exclude)
+    }
+}
+
+/// Match the whole value against a pattern where `*` matches any sequence of characters
+/// and `?` a single character. Backtracks to the last `*` only, in linear space.
+pub(crate) fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // position of the last `*` in the pattern, and of the value's character it stands up to
+    let mut backtrack = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    v = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Prefix of the branch patterns given as regular expressions, like `regex:release/\d+\.\d+`
+pub const REGEX_PREFIX: &str = "regex:";
+
+/// Pattern selecting branches by name: a glob with `*` and `?` wildcards, or a regular
+/// expression prefixed by `regex:`. Both must match the whole name.
+pub enum BranchPattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl BranchPattern {
+    /// Parse the branch specification as a pattern, `None` if it's a plain branch name
+    pub fn parse(spec: &str) -> Result<Option<Self>> {
+        if let Some(regex) = spec.strip_prefix(REGEX_PREFIX) {
+            let regex = Regex::new(&format!("^(?:{})$", regex))
+                .map_err(|error| Error::InvalidBranchPattern(spec.to_owned(), error))?;
+            Ok(Some(Self::Regex(regex)))
+        } else if spec.contains(['*', '?']) {
+            Ok(Some(Self::Glob(spec.to_owned())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => glob_match(pattern, name),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Filter on the team trailers of the messages
+#[derive(Debug, Clone)]
+pub struct TeamFilter {
+    /// Keep only the messages of these teams, all of them if empty
+    pub teams: Vec<String>,
+    /// Key of the trailers naming the teams, compared case-insensitively
+    pub trailer_key: String,
+    /// Compare the teams case-insensitively
+    pub ignore_case: bool,
+}
+
+impl Default for TeamFilter {
+    fn default() -> Self {
+        Self {
+            teams: Vec::new(),
+            trailer_key: "team".to_string(),
+            ignore_case: false,
+        }
+    }
+}
+
+impl TeamFilter {
+    /// Check if any of the message's team trailers matches any of the teams, ignoring
+    /// surrounding whitespaces
+    pub fn matches(&self, message: &ConventionalMessage) -> bool {
+        self.teams.is_empty()
+            || message.trailer_values(&self.trailer_key).any(|value| {
+                self.teams.iter().any(|team| {
+                    let (team, value) = (team.trim(), value.trim());
+                    if self.ignore_case {
+                        team.to_lowercase() == value.to_lowercase()
+                    } else {
+                        team == value
+                    }
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn team_filter(teams: &[&str]) -> TeamFilter {
+        TeamFilter {
+            teams: teams.iter().map(|team| team.to_string()).collect(),
+            ..TeamFilter::default()
+        }
+    }
+
+    #[test]
+    fn test_branch_pattern() {
+        let pattern = |spec| BranchPattern::parse(spec).unwrap().unwrap();
+
+        assert!(BranchPattern::parse("release/1.0").unwrap().is_none());
+        assert!(pattern("release/*").matches("release/1.0"));
+        assert!(!pattern("release/*").matches("hotfix/release/1.0"));
+        assert!(pattern(r"regex:release/\d+\.\d+").matches("release/1.10"));
+        assert!(!pattern(r"regex:release/\d+\.\d+").matches("release/1.10-rc"));
+        assert!(pattern("regex:main|master").matches("master"));
+        assert!(matches!(
+            BranchPattern::parse("regex:release/("),
+            Err(Error::InvalidBranchPattern(spec, _)) if spec == "regex:release/("
+        ));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b?d", "axxbcd"));
+        assert!(!glob_match("a*b?d", "axxbd"));
+        assert!(glob_match("*[bot]", "dependabot[bot]"));
+        // `?` stands for a whole character, even a multi-byte one
+        assert!(glob_match("jos? garc?a", "josé garcía"));
+        assert!(!glob_match("jos??", "josé"));
+        // no exponential backtracking on repeated stars
+        let value = "a".repeat(100);
+        assert!(!glob_match(&format!("{}b", "a*".repeat(30)), &value));
+    }
+
+    #[test]
+    fn test_type_filter() {
+        let include = vec!["feat".to_string(), "Fix".to_string()];
+        let exclude = vec!["FEAT".to_string()];
+        let filter = TypeFilter {
+            include: &include,
+            exclude: &[],
+        };
+        assert!(filter.matches(&CommitType::Feature));
+        assert!(filter.matches(&CommitType::BugFix));
+        assert!(!filter.matches(&CommitType::Other("chore".to_string())));
+        let filter = TypeFilter {
+            include: &[],
+            exclude: &exclude,
+        };
+        assert!(!filter.matches(&CommitType::Feature));
+        assert!(filter.matches(&CommitType::Other("chore".to_string())));
+    }
+
+    #[test]
+    fn test_team_filter() {
+        let message: ConventionalMessage = "feat: new feature\n\nTeam: core\nteam: infra"
+            .parse()
+            .unwrap();
+
+        assert!(team_filter(&["core"]).matches(&message));
+        assert!(team_filter(&["front", "infra"]).matches(&message));
+        assert!(!team_filter(&["front", "Core"]).matches(&message));
+        assert!(team_filter(&[" infra "]).matches(&message));
+        assert!(team_filter(&[]).matches(&message));
+    }
+
+    #[test]
+    fn test_team_filter_with_mixed_case_key() {
+        let message: ConventionalMessage = "fix: bug\n\nTEAM: core".parse().unwrap();
+        assert!(team_filter(&["core"]).matches(&message));
+    }
+
+    #[test]
+    fn test_team_filter_with_custom_key() {
+        let message: ConventionalMessage = "fix: bug\n\nSquad: Core\nTeam: front".parse().unwrap();
+        let mut filter = TeamFilter {
+            trailer_key: "squad".to_string(),
+            ..team_filter(&["core"])
+        };
+        assert!(!filter.matches(&message));
+
+        filter.ignore_case = true;
+        assert!(filter.matches(&message));
+
+        filter.teams = vec!["front".to_string()];
+        assert!(!filter.matches(&message));
+    }
+
+    fn bot() -> Author {
+        Author {
+            name: "Release Bot".to_string(),
+            email: Some("release-bot@example.com".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_person_filter() {
+        let patterns = vec!["release-bot".to_string()];
+        let none = Vec::new();
+
+        let include = PersonFilter {
+            include: &patterns,
+            exclude: &none,
+        };
+        assert!(include.matches(&bot()));
+        assert!(!include.matches(&Author {
+            name: "Jane Doe".to_string(),
+            email: None,
+        }));
+
+        let exclude = PersonFilter {
+            include: &none,
+            exclude: &patterns,
+        };
+        assert!(!exclude.matches(&bot()));
+
+        let everyone = PersonFilter {
+            include: &none,
+            exclude: &none,
+        };
+        assert!(everyone.matches(&bot()));
+    }
+
+    #[test]
+    fn test_person_filter_is_case_insensitive() {
+        let patterns = vec!["RELEASE BOT".to_string()];
+        let filter = PersonFilter {
+            include: &patterns,
+            exclude: &[],
+        };
+        assert!(filter.matches(&bot()));
+    }
+
+    #[test]
+    fn test_person_filter_with_glob() {
+        let dependabot = Author {
+            name: "dependabot[bot]".to_string(),
+            email: Some("49699333+dependabot[bot]@users.noreply.github.com".to_string()),
+        };
+        let patterns = vec!["*[bot]".to_string(), "renovate?bot".to_string()];
+        let filter = PersonFilter {
+            include: &[],
+            exclude: &patterns,
+        };
+        assert!(!filter.matches(&dependabot));
+        assert!(!filter.matches(&Author {
+            name: "Renovate Bot".to_string(),
+            email: None,
+        }));
+        // globs match the whole value, unlike plain patterns
+        assert!(filter.matches(&bot()));
+        assert!(filter.matches(&Author {
+            name: "renovate bot fan".to_string(),
+            email: None,
+        }));
+    }
+}
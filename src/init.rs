@@ -0,0 +1,220 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use git2::{ErrorCode, Repository};
+
+use crate::{error::Result, snapshots::BranchName};
+
+/// Name of the configuration file written in a folder
+pub const CONFIG_FILE_NAME: &str = "resume.yaml";
+
+/// Project of the local repository, pre-populating the configuration skeleton
+#[derive(Debug, Eq, PartialEq)]
+pub struct DetectedProject {
+    /// Name of the repository's folder
+    pub name: String,
+    /// `remote.origin.url`, if set
+    pub origin: Option<String>,
+    /// Root of the working tree, used as the project's path without origin
+    pub path: PathBuf,
+    /// Checked out branch, unless the `HEAD` is detached
+    pub branch: Option<BranchName>,
+}
+
+/// Detect the project of the repository containing `dir`. Return `None` outside of a
+/// repository, or in a bare one.
+pub fn detect_project(dir: &Path) -> Result<Option<DetectedProject>> {
+    let repository = match Repository::discover(dir) {
+        Ok(repository) => repository,
+        Err(error) if error.code() == ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    let path = match repository.workdir() {
+        Some(workdir) => workdir.canonicalize()?,
+        None => return Ok(None),
+    };
+    let name = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let origin = match repository.config()?.get_string("remote.origin.url") {
+        Ok(origin) => Some(origin),
+        Err(error) if error.code() == ErrorCode::NotFound => None,
+        Err(error) => return Err(error.into()),
+    };
+    // the symbolic target is readable before the first commit too, unlike the resolved HEAD
+    let branch = repository
+        .find_reference("HEAD")?
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .map(|branch| branch.to_owned().into());
+    Ok(Some(DetectedProject {
+        name,
+        origin,
+        path,
+        branch,
+    }))
+}
+
+/// Path of the configuration file to write: `path` itself, or the default file in it if
+/// it's a folder
+pub fn config_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(CONFIG_FILE_NAME)
+    } else {
+        path.to_owned()
+    }
+}
+
+/// Render a commented configuration, listing the project if any, the optional attributes
+/// commented out
+pub fn skeleton(project: Option<&DetectedProject>) -> String {
+    let mut output = String::from(
+        "# Configuration of resume, see the Configuration section of its README\n\
+         \n\
+         # Branch watched by the projects without `branches`, asked to their origin if missing\n\
+         # default_branch: main\n\
+         \n\
+         # Projects to résumé, each with a name, and an origin URL or the path of a local repository\n",
+    );
+    match project {
+        Some(project) => {
+            output.push_str("projects:\n");
+            writeln!(output, "  - name: {}", quote(&project.name)).expect("unfailable");
+            match &project.origin {
+                Some(origin) => writeln!(output, "    origin: {}", quote(origin)),
+                None => writeln!(
+                    output,
+                    "    path: {}",
+                    quote(&project.path.to_string_lossy())
+                ),
+            }
+            .expect("unfailable");
+            match &project.branch {
+                Some(branch) => {
+                    writeln!(output, "    branches:\n      - {}", quote(branch.as_str()))
+                        .expect("unfailable")
+                }
+                None => output.push_str("    # branches: [main]\n"),
+            }
+            output.push_str(
+                "    # Keep the commits of these teams only, named by their trailers\n\
+                 \x20   # team: [backend]\n\
+                 \x20   # Fields to group the project's entries by, after their origin\n\
+                 \x20   # group_by: [branch, commit-type]\n",
+            );
+        }
+        None => output.push_str(
+            "projects: []\n\
+             #  - name: api\n\
+             #    origin: git@example.com:org/api.git\n\
+             #    branches: [main]\n\
+             #    team: [backend]\n",
+        ),
+    }
+    output.push_str(
+        "\n\
+         # Fields to group the entries by when `--group-by` isn't given\n\
+         # group_by: [origin, branch, commit-type]\n\
+         \n\
+         # Drop the commits of the authors matching these patterns, in all the projects\n\
+         # exclude_authors: [\"*[bot]\"]\n\
+         \n\
+         # Order of the commit type groups, instead of their priority\n\
+         # type_order: [feat, fix, perf]\n\
+         \n\
+         # Canonical commit types by alias\n\
+         # type_aliases:\n\
+         #   hotfix: fix\n\
+         \n\
+         # Slack incoming webhook to post a summary of the changelog to\n\
+         # slack_webhook: https://hooks.slack.com/services/...\n",
+    );
+    output
+}
+
+/// Quote a string as a YAML scalar, JSON strings being valid double-quoted ones
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).expect("unfailable")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Configuration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("resume-init-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path.canonicalize().unwrap()
+    }
+
+    fn load(path: &Path, skeleton: String) -> Configuration {
+        let file = path.join(CONFIG_FILE_NAME);
+        std::fs::write(&file, skeleton).unwrap();
+        Configuration::from_file(&file).unwrap()
+    }
+
+    #[test]
+    fn test_skeleton_round_trip() {
+        let path = temp_dir("round-trip");
+        assert!(load(&path, skeleton(None)).projects.is_empty());
+
+        let project = DetectedProject {
+            name: "api: v2".to_owned(),
+            origin: Some("git@example.com:org/api.git".to_owned()),
+            path: path.clone(),
+            branch: Some("release/1.0".to_owned().into()),
+        };
+        let config = load(&path, skeleton(Some(&project)));
+        assert_eq!(1, config.projects.len());
+        assert_eq!("api: v2", config.projects[0].name);
+        assert_eq!(
+            "git@example.com:org/api.git",
+            config.projects[0].origin.as_str()
+        );
+        assert_eq!(
+            Some(vec![project.branch.unwrap()]),
+            config.projects[0].branches
+        );
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_detect_project() {
+        let path = temp_dir("detect");
+        assert_eq!(None, detect_project(&path).unwrap());
+
+        let repository_path = path.join("api");
+        let repository = Repository::init(&repository_path).unwrap();
+        repository.set_head("refs/heads/develop").unwrap();
+        std::fs::create_dir(repository_path.join("src")).unwrap();
+        let detected = detect_project(&repository_path.join("src"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            DetectedProject {
+                name: "api".to_owned(),
+                origin: None,
+                path: repository_path.clone(),
+                branch: Some("develop".to_owned().into()),
+            },
+            detected
+        );
+        let config = load(&path, skeleton(Some(&detected)));
+        assert_eq!(Some(repository_path.clone()), config.projects[0].path);
+
+        repository
+            .remote("origin", "https://example.com/org/api.git")
+            .unwrap();
+        let detected = detect_project(&repository_path).unwrap().unwrap();
+        assert_eq!(
+            Some("https://example.com/org/api.git"),
+            detected.origin.as_deref()
+        );
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
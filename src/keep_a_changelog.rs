@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use crate::{
+    changelog::ChangeLogEntry,
+    message::{CommitScope, CommitType, ConventionalMessage},
+};
+
+/// Sections of a release, in the order of [Keep a Changelog](https://keepachangelog.com)
+pub const SECTIONS: [&str; 6] = [
+    "Added",
+    "Changed",
+    "Deprecated",
+    "Removed",
+    "Fixed",
+    "Security",
+];
+
+/// Sections of the commit types, by type key (e.g. `docs: Changed`), overriding the default
+/// ones. A `null` section drops the type.
+pub type SectionMapping = IndexMap<String, Option<String>>;
+
+/// Get the section of the commit type, from the mapping if it's overridden there
+fn section_of<'a>(ctype: &CommitType, mapping: &'a SectionMapping) -> Option<&'a str> {
+    let key = ctype.as_str();
+    if let Some((_, section)) = mapping
+        .iter()
+        .find(|(type_key, _)| type_key.eq_ignore_ascii_case(key))
+    {
+        return section.as_deref();
+    }
+    match ctype {
+        CommitType::Feature => Some("Added"),
+        CommitType::Performance | CommitType::Refactoring => Some("Changed"),
+        CommitType::Revert => Some("Removed"),
+        CommitType::BugFix => Some("Fixed"),
+        CommitType::Other(other) if other.eq_ignore_ascii_case("deprecate") => Some("Deprecated"),
+        CommitType::Other(other) if other.eq_ignore_ascii_case("security") => Some("Security"),
+        _ => None,
+    }
+}
+
+/// Render the entries as a single release block titled `title`, each commit once, under the
+/// section of its type, followed by its indented body if `with_bodies`. Sections outside the
+/// spec's ones follow them.
+pub fn render<'a>(
+    title: &str,
+    entries: impl Iterator<Item = &'a ChangeLogEntry>,
+    mapping: &SectionMapping,
+    with_bodies: bool,
+) -> String {
+    let mut sections: IndexMap<&str, Vec<String>> = SECTIONS
+        .iter()
+        .map(|section| (*section, Vec::new()))
+        .collect();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        if let Some(hash) = entry.hash() {
+            if !seen.insert(hash) {
+                continue;
+            }
+        }
+        let message: &ConventionalMessage = entry.as_ref();
+        if let Some(section) = section_of(&message.ctype, mapping) {
+            sections
+                .entry(section)
+                .or_default()
+                .push(render_entry(entry, message, with_bodies));
+        }
+    }
+
+    let mut output = format!("## {}\n", title);
+    for (section, lines) in sections.iter().filter(|(_, lines)| !lines.is_empty()) {
+        output.push_str(&format!("\n### {}\n\n", section));
+        for line in lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Render the entry as a list item, like `- **api:** add endpoint (1a2b3c4)`, its summary
+/// linked to the commit if possible
+fn render_entry(entry: &ChangeLogEntry, message: &ConventionalMessage, with_body: bool) -> String {
+    let mut line = "- ".to_owned();
+    if message.is_breaking {
+        line.push_str("**⚠ BREAKING** ");
+    }
+    if !message.scopes.is_empty() {
+        let scopes: Vec<_> = message.scopes.iter().map(CommitScope::as_str).collect();
+        line.push_str(&format!("**{}:** ", scopes.join(",")));
+    }
+    match entry.url() {
+        Some(url) => line.push_str(&format!("[{}]({})", message.summary, url)),
+        None => line.push_str(&message.summary),
+    }
+    if let Some(hash) = entry.hash() {
+        line.push_str(&format!(" ({})", hash.short()));
+    }
+    if let Some(body) = message.body.as_ref().filter(|_| with_body) {
+        line.push('\n');
+        for body_line in body.lines() {
+            line.push('\n');
+            if !body_line.is_empty() {
+                line.push_str("  ");
+                line.push_str(body_line);
+            }
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::commit_metadata;
+
+    fn entry(raw_message: &str, hash: Option<&str>) -> ChangeLogEntry {
+        let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+        metadata.hash = hash.map(|hash| hash.to_string().into());
+        ChangeLogEntry::new(
+            "origin".to_string().into(),
+            "master".to_string().into(),
+            metadata,
+            raw_message.parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_render() {
+        let entries = [
+            entry("fix: billing", Some("1a2b3c4d5e6f")),
+            entry("feat(api,db)!: new endpoint", None),
+            entry("docs: readme", None),
+            entry("security: escape input", None),
+            entry("fix: billing", Some("1a2b3c4d5e6f")),
+            entry("chore: bump", None),
+        ];
+        let mut mapping = SectionMapping::new();
+        mapping.insert("Security".to_string(), None);
+        mapping.insert("chore".to_string(), Some("Maintenance".to_string()));
+
+        let expected = "\
+## [Unreleased]
+
+### Added
+
+- **⚠ BREAKING** **api,db:** new endpoint
+
+### Fixed
+
+- billing (1a2b3c4)
+
+### Maintenance
+
+- bump
+";
+        assert_eq!(
+            expected,
+            render("[Unreleased]", entries.iter(), &mapping, false)
+        );
+    }
+
+    #[test]
+    fn test_render_links() {
+        let url = "https://github.com/org/repo/commit/1a2b3c4d5e6f";
+        let entries = [entry("fix: billing", Some("1a2b3c4d5e6f")).with_url(Some(url.to_string()))];
+        let output = render(
+            "[Unreleased]",
+            entries.iter(),
+            &SectionMapping::new(),
+            false,
+        );
+        assert!(output.contains(&format!("- [billing]({}) (1a2b3c4)\n", url)));
+    }
+
+    #[test]
+    fn test_render_bodies() {
+        let entries = [entry(
+            "fix: billing\n\nRound the totals.\n\nNot the taxes.",
+            None,
+        )];
+        let expected = "\
+## [Unreleased]
+
+### Fixed
+
+- billing
+
+  Round the totals.
+
+  Not the taxes.
+";
+        let mapping = SectionMapping::new();
+        assert_eq!(
+            expected,
+            render("[Unreleased]", entries.iter(), &mapping, true)
+        );
+        assert!(!render("[Unreleased]", entries.iter(), &mapping, false).contains("taxes"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Changelog generation and aggregation from git logs.
+//!
+//! Traverse the branches of repositories, parse their commit messages following the
+//! [Conventional Commits](https://www.conventionalcommits.org/en/v1.0.0) convention, then
+//! group them in a [`ChangeLog`].
+//!
+//! Résumé a local repository:
+//!
+//! ```
+//! use resume::{process_repository, CommitField, TeamFilter, TraversalOptions};
+//!
+//! # let path = std::env::temp_dir().join(format!("resume-doctest-{}", std::process::id()));
+//! # let _ = std::fs::remove_dir_all(&path);
+//! # let repository = git2::Repository::init(&path).unwrap();
+//! # let signature = git2::Signature::now("Jane Doe", "jane@example.com").unwrap();
+//! # let tree = repository.find_tree(repository.index().unwrap().write_tree().unwrap()).unwrap();
+//! # for message in &["feat(api): add endpoint", "fix: billing"] {
+//! #     let parent = repository.head().ok().map(|head| head.peel_to_commit().unwrap());
+//! #     let parents: Vec<_> = parent.iter().collect();
+//! #     repository.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap();
+//! # }
+//! # let branch = repository.head().unwrap().shorthand().unwrap().to_string().into();
+//! # let path = path.to_str().unwrap();
+//! let run_report = process_repository(
+//!     path,
+//!     &[branch],
+//!     TeamFilter::default(),
+//!     &TraversalOptions::default(),
+//! )?;
+//!
+//! let change_log = run_report.into_change_log(vec![CommitField::CommitType])?;
+//! assert_eq!(2, change_log.stats().counts.total);
+//! println!("{}", change_log.to_yaml()?);
+//! # std::fs::remove_dir_all(path).unwrap();
+//! # Ok::<(), resume::Error>(())
+//! ```
+//!
+//! Résumé the projects of a configuration with [`process_projects`], reporting their progress
+//! to a [`Progress`] of your own, like [`LogProgress`]. Its [`ProjectsRun`]'s report builds the
+//! changelog the same way, with the configuration's descriptions, labels and sections.
+//!
+//! A [`ChangeLog`] renders as YAML, text, HTML, CSV, Keep a Changelog or a [Tera](https://tera.netlify.app/)
+//! template.
+
+/// Semantic version increment required by the changes
+pub mod bump;
+/// Changelog entries, grouped by the commits' fields
+pub mod changelog;
+/// Configuration of the projects, and settings of a single repository
+pub mod config;
+/// Checks of a configuration beyond its deserialization
+pub mod config_check;
+/// Capabilities of a run, like read-only
+pub mod context;
+/// Escalation of the credentials used to access origins
+pub mod credentials;
+/// Errors of the crate
+pub mod error;
+/// Filters of the commits: teams, people, types and branch patterns
+pub mod filter;
+/// Skeleton of a configuration
+pub mod init;
+/// Keep a Changelog rendering
+pub mod keep_a_changelog;
+/// Links to the commits on their hosting service
+pub mod links;
+/// Rules checking a commit message
+pub mod lint;
+/// Conventional commit messages and their parsing
+pub mod message;
+/// Order of the groups and of the entries of a changelog
+pub mod ordering;
+/// Parsed messages kept across runs
+pub mod parse_cache;
+/// Slices of the entries by period
+pub mod period;
+/// Processing of a repository or of the configured projects
+pub mod process;
+/// Reports of a project's processing progress
+pub mod progress;
+/// Repository of a project, fetched and traversed
+pub mod project;
+/// Outputs of a changelog
+pub mod report;
+/// Entries of a run, before their grouping into a changelog
+pub mod run_report;
+/// Heads of the branches of each run, to start the next one from
+pub mod snapshots;
+/// CSV and TSV outputs
+pub mod tabular;
+#[cfg(test)]
+mod test_utils;
+/// Timings of a project's processing steps
+pub mod timings;
+/// Cache folder of the cloned repositories
+pub mod utils;
+
+pub use changelog::{ChangeLog, ChangeLogEntry, CommitField};
+pub use config::Configuration;
+pub use context::RunContext;
+pub use error::{Error, Result};
+pub use filter::TeamFilter;
+pub use message::ConventionalMessage;
+pub use process::{
+    process_projects, process_repository, process_repository_with_context, ProjectSummary,
+    ProjectsRun,
+};
+pub use progress::{LogProgress, Progress};
+pub use project::{Project, TraversalOptions};
+pub use run_report::RunReport;
+pub use snapshots::{Snapshot, SnapshotHistory};
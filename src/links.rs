@@ -0,0 +1,100 @@
+use crate::snapshots::{CommitHash, RepositoryOrigin};
+
+/// Split a remote origin in its host and repository path, without the `.git` suffix.
+/// `scp`-like (`git@host:org/repo.git`), SSH and HTTP(S) origins are supported.
+pub(crate) fn split_origin(origin: &str) -> Option<(&str, &str)> {
+    let (host, path) = match origin.split_once("://") {
+        Some((scheme, rest)) if ["ssh", "git", "http", "https"].contains(&scheme) => {
+            rest.split_once('/')?
+        }
+        Some(_) => return None,
+        None => origin.split_once(':')?,
+    };
+    // drop the credentials and the port
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || path.is_empty() {
+        None
+    } else {
+        Some((host, path))
+    }
+}
+
+/// Infer the commit link template of the GitHub and GitLab origins
+pub fn infer_link_template(origin: &RepositoryOrigin) -> Option<String> {
+    match split_origin(origin.as_str())? {
+        ("github.com", path) => Some(format!("https://github.com/{}/commit/{{hash}}", path)),
+        ("gitlab.com", path) => Some(format!("https://gitlab.com/{}/-/commit/{{hash}}", path)),
+        _ => None,
+    }
+}
+
+/// Get the name of the origin's repository, its path's last segment
+pub fn repository_name(origin: &RepositoryOrigin) -> Option<&str> {
+    let (_, path) = split_origin(origin.as_str())?;
+    path.rsplit('/').next()
+}
+
+/// Build the link to a commit, replacing the `{hash}` and `{short_hash}` placeholders of the
+/// template
+pub fn commit_url(template: &str, hash: &CommitHash) -> String {
+    template
+        .replace("{short_hash}", hash.short())
+        .replace("{hash}", hash.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn origin(origin: &str) -> RepositoryOrigin {
+        origin.to_string().into()
+    }
+
+    #[test]
+    fn test_infer_link_template() {
+        let github = Some("https://github.com/org/repo/commit/{hash}".to_string());
+        assert_eq!(
+            github,
+            infer_link_template(&origin("git@github.com:org/repo.git"))
+        );
+        assert_eq!(
+            github,
+            infer_link_template(&origin("https://github.com/org/repo"))
+        );
+        assert_eq!(
+            github,
+            infer_link_template(&origin("ssh://git@github.com/org/repo.git"))
+        );
+        assert_eq!(
+            Some("https://gitlab.com/group/sub/repo/-/commit/{hash}".to_string()),
+            infer_link_template(&origin("https://token@gitlab.com/group/sub/repo.git"))
+        );
+        assert_eq!(
+            None,
+            infer_link_template(&origin("git@example.com:org/repo.git"))
+        );
+        assert_eq!(None, infer_link_template(&origin("file:///srv/git/repo")));
+        assert_eq!(None, infer_link_template(&origin("")));
+    }
+
+    #[test]
+    fn test_repository_name() {
+        assert_eq!(
+            Some("repo"),
+            repository_name(&origin("git@example.com:org/repo.git"))
+        );
+        assert_eq!(None, repository_name(&origin("/srv/git/repo")));
+    }
+
+    #[test]
+    fn test_commit_url() {
+        let hash = "1a2b3c4d5e6f".to_string().into();
+        assert_eq!(
+            "https://example.com/repo/1a2b3c4d5e6f?short=1a2b3c4",
+            commit_url("https://example.com/repo/{hash}?short={short_hash}", &hash)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    error::{Error, Result},
+    message::{strip_comments, ConventionalMessage, DEFAULT_COMMENT_CHAR},
+};
+
+/// Optional rules enforced on the parsed messages
+#[derive(Debug, Default, Clone)]
+pub struct LintRules {
+    /// Maximum length of the summary, in characters
+    pub max_summary_length: Option<usize>,
+    /// Forbid the summary to end with a period
+    pub no_final_period: bool,
+}
+
+/// Broken rule of a parsed message
+#[derive(Debug, Eq, PartialEq)]
+pub enum Violation {
+    EmptySummary,
+    SummaryTooLong { length: usize, max: usize },
+    FinalPeriod,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptySummary => write!(f, "the summary must not be empty"),
+            Self::SummaryTooLong { length, max } => write!(
+                f,
+                "the summary is {} characters long, the maximum is {}",
+                length, max
+            ),
+            Self::FinalPeriod => write!(f, "the summary must not end with a period"),
+        }
+    }
+}
+
+/// Parse the message then check it against the rules
+pub fn lint(raw: &str, rules: &LintRules) -> Result<Vec<Violation>> {
+    let message: ConventionalMessage = strip_comments(raw, DEFAULT_COMMENT_CHAR)
+        .trim_matches('\n')
+        .parse()
+        .map_err(|error| Error::InvalidMessage(Box::new(error)))?;
+
+    let summary = message.summary.trim();
+    let mut violations = Vec::new();
+    if summary.is_empty() {
+        violations.push(Violation::EmptySummary);
+    }
+    if let Some(max) = rules.max_summary_length {
+        let length = summary.chars().count();
+        if length > max {
+            violations.push(Violation::SummaryTooLong { length, max });
+        }
+    }
+    if rules.no_final_period && summary.ends_with('.') {
+        violations.push(Violation::FinalPeriod);
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lint_valid_message() {
+        let rules = LintRules {
+            max_summary_length: Some(72),
+            no_final_period: true,
+        };
+        let raw = "feat(api): add endpoint\n\nSome body.\n\n# Please enter the commit message\n";
+        assert!(lint(raw, &rules).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lint_invalid_message() {
+        let result = lint("Added an endpoint", &LintRules::default());
+        assert!(matches!(result, Err(Error::InvalidMessage(_))));
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        let raw = "fix: a summary which is way too long for the rule.";
+        assert!(lint(raw, &LintRules::default()).unwrap().is_empty());
+
+        let rules = LintRules {
+            max_summary_length: Some(20),
+            no_final_period: true,
+        };
+        assert_eq!(
+            vec![
+                Violation::SummaryTooLong {
+                    length: 45,
+                    max: 20
+                },
+                Violation::FinalPeriod
+            ],
+            lint(raw, &rules).unwrap()
+        );
+        assert_eq!(
+            vec![Violation::EmptySummary],
+            lint("fix: ", &LintRules::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let raw = "feat: x\n# comment\n\nbody\n# ------------------------ >8 ------------------------\ndiff";
+        assert_eq!("feat: x\n\nbody", strip_comments(raw, '#'));
+    }
+}
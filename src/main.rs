@@ -1,149 +1,539 @@
 use std::{
     error::Error as StdError,
+    io::{BufWriter, Read, Write},
+    path::Path,
     sync::mpsc::channel,
     thread::{sleep, spawn},
     time::Duration,
 };
 
+use chrono::Utc;
 use clap::Clap;
-use git2::Oid;
+use console::{colors_enabled, Term};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use rayon::prelude::*;
 
-use crate::changelog::{ChangeLog, ChangeLogEntry, CommitField};
-use crate::snapshots::{
-    BranchName, RepositoryOrigin, RepositorySnapshot, Snapshot, SnapshotBuilder, SnapshotHistory,
-};
-use crate::{
-    cli::{Command, SubCommand},
-    config::Configuration,
+use resume::{
+    changelog::{ChangeLog, ChangeLogEntry, CommitField},
+    config::{Configuration, RepositorySettings},
+    config_check,
+    context::RunContext,
     error::{
-        Error::{InvalidSnapshotRef, SnapshotDoesntExist},
+        Error::{
+            self, CheckFailures, ConfigurationExists, EmptyChangeLog, FailedProjects,
+            LintViolations, MissingTemplate,
+        },
         Result,
     },
-    project::{Project, Sentinels},
-    report::OutputType,
+    init,
+    lint::{lint, LintRules},
+    period::{slice_entries, Period},
+    process::{
+        list_repository_branches, process_projects, process_repository_with_context,
+        ProjectSummary, ProjectsRun,
+    },
+    progress::{LogProgress, Progress},
+    project::TraversalOptions,
+    report::{slack, OutputType, Report, ReportMeta},
+    run_report::RunReport,
+    snapshots::{Snapshot, SnapshotHistory},
+    utils::prune_cache,
 };
 
-mod changelog;
+use crate::cli::{Check, Command, ConfigSubCommand, ReportOptions, SubCommand};
+
 mod cli;
-mod config;
-mod error;
-mod message;
-mod project;
-mod report;
-mod snapshots;
-mod utils;
 
 fn main() {
     if let Err(error) = run() {
-        eprintln!("Error: {}", error);
-        let mut error = error.source();
-        while let Some(cause) = error {
-            eprintln!("⤷ caused by: {}", &cause);
-            error = cause.source();
-        }
-        std::process::exit(1);
+        print_error(&error);
+        std::process::exit(exit_code(&error));
+    }
+}
+
+/// Get the exit code of the failed run: 2 if the changelog is empty with `--fail-on-empty`,
+/// 1 otherwise
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        EmptyChangeLog => 2,
+        _ => 1,
+    }
+}
+
+/// Print the error then its sources, one per line
+fn print_error(error: &dyn StdError) {
+    eprintln!("Error: {}", error);
+    let mut error = error.source();
+    while let Some(cause) = error {
+        eprintln!("⤷ caused by: {}", &cause);
+        error = cause.source();
+    }
+}
+
+/// Join the error and its sources on a single line
+fn error_chain(error: &dyn StdError) -> String {
+    let mut chain = error.to_string();
+    let mut error = error.source();
+    while let Some(cause) = error {
+        chain.push_str(&format!(": {}", cause));
+        error = cause.source();
+    }
+    chain
+}
+
+/// Get the level of the logged messages: info, debug then trace as `--verbose` is repeated,
+/// even if quiet, errors only when quiet, warnings otherwise
+fn log_level(command: &Command) -> log::Level {
+    match command.verbose {
+        0 if command.quiet => log::Level::Error,
+        0 => log::Level::Warn,
+        1 => log::Level::Info,
+        2 => log::Level::Debug,
+        _ => log::Level::Trace,
     }
 }
 
 fn run() -> Result<()> {
     let command = Command::parse();
 
-    if command.verbose {
-        simple_logger::init_with_level(log::Level::Info).unwrap();
-    } else {
-        simple_logger::init_with_level(log::Level::Warn).unwrap();
-    }
+    simple_logger::init_with_level(log_level(&command)).unwrap();
+    let context = RunContext {
+        read_only: command.read_only,
+    };
 
     match &command.sub_command {
         SubCommand::Repository(subcmd) => {
-            let change_log = process_repository(
+            let branches = if subcmd.all_branches {
+                list_repository_branches(&subcmd.repository)?
+            } else {
+                subcmd.branches.clone()
+            };
+            let settings = match &subcmd.settings {
+                Some(path) => RepositorySettings::from_file(path)?,
+                None => RepositorySettings::default(),
+            };
+            let run_report = process_repository_with_context(
                 &subcmd.repository,
-                subcmd.group_by.clone(),
-                &subcmd.branches,
-                subcmd.team.to_owned(),
+                &branches,
+                settings.team_filter(
+                    &subcmd.team,
+                    subcmd.team_trailer_key.as_deref(),
+                    subcmd.team_ignore_case,
+                ),
+                &settings.traversal_options(&subcmd.traversal),
+                &context,
             )?;
-
-            if command.output == OutputType::Yaml {
-                println!("{}", change_log.to_yaml()?);
-            }
+            let group_by = settings.get_group_by(subcmd.group_by.as_deref());
+            let empty = output_report(&command, run_report, &group_by, &subcmd.report, &context)?;
+            check_not_empty(&subcmd.report, empty)?;
         }
         SubCommand::Projects(subcmd) => {
-            let config = Configuration::from_file(&subcmd.config_file)?;
+            // listing the snapshots only needs the state file, not the configuration
+            if subcmd.list_snapshots {
+                list_snapshots(&SnapshotHistory::from_file(&subcmd.state_file)?);
+                return Ok(());
+            }
+            let mut config = Configuration::from_source(&subcmd.config_file)?;
+            config.fetch_timeout = subcmd.fetch_timeout.or(config.fetch_timeout);
+            config.fetch_retries = subcmd.fetch_retries.unwrap_or(config.fetch_retries);
+            config.fetch_depth = subcmd.fetch_depth.or(config.fetch_depth);
+            config.jobs = subcmd.jobs.or(config.jobs);
+            let group_by = config.get_group_by(subcmd.group_by.as_deref());
+            let report_options = ReportOptions {
+                empty_key: subcmd
+                    .report
+                    .empty_key
+                    .clone()
+                    .or_else(|| config.empty_key.clone()),
+                slack_webhook: if subcmd.report.no_slack {
+                    None
+                } else {
+                    subcmd
+                        .report
+                        .slack_webhook
+                        .clone()
+                        .or_else(|| config.slack_webhook.clone())
+                },
+                ..subcmd.report.clone()
+            };
 
-            let mut history = SnapshotHistory::from_file(&subcmd.state_file)
-                .unwrap_or_else(|_| SnapshotHistory::new());
+            let mut history = SnapshotHistory::from_file(&subcmd.state_file)?;
+            for snapshot_ref in &subcmd.remove_snapshot {
+                history.remove(snapshot_ref)?;
+            }
 
             let snapshot = if subcmd.no_state {
                 None
             } else if let Some(snapshot_ref) = &subcmd.from_snapshot {
-                let snapshot = if let Ok(index) = snapshot_ref.parse() {
-                    history.get_by_index(index).cloned()
-                } else if let Ok(hash) = snapshot_ref.parse().as_ref() {
-                    history.get_by_hash(hash).cloned()
-                } else {
-                    return Err(InvalidSnapshotRef(snapshot_ref.to_owned()));
-                };
-
-                if snapshot.is_none() {
-                    return Err(SnapshotDoesntExist(snapshot_ref.to_owned()));
-                }
-
-                snapshot
+                Some(history.resolve(snapshot_ref)?.clone())
             } else {
                 history.last().cloned()
             };
 
-            let (change_log_entries, snapshot) = process_projects(config, snapshot)?;
+            if subcmd.amend_state && !subcmd.force {
+                history
+                    .check_amendable(chrono::Duration::hours(subcmd.amend_max_age), Utc::now())?;
+            }
+
+            let origins: Vec<_> = config
+                .projects
+                .iter()
+                .map(|project| project.origin.clone())
+                .collect();
+            let ProjectsRun {
+                report: run_report,
+                snapshot,
+                summaries,
+                failures,
+            } = if command.no_progress || command.quiet || !Term::stderr().is_term() {
+                process_projects(
+                    &config,
+                    snapshot.as_ref(),
+                    &subcmd.traversal,
+                    &context,
+                    subcmd.keep_going,
+                    |cfg_project, _| LogProgress(cfg_project.name.to_owned()),
+                )?
+            } else {
+                process_projects_with_bars(
+                    config,
+                    snapshot,
+                    subcmd.traversal.clone(),
+                    context,
+                    subcmd.keep_going,
+                )?
+            };
+            if !command.quiet {
+                print_run_summary(&summaries);
+            }
 
-            if subcmd.save_state {
+            let snapshot = snapshot.with_label(subcmd.label.clone());
+            if subcmd.amend_state {
+                history.amend(snapshot);
+            } else if subcmd.save_state {
                 history.push(snapshot);
-                history.to_file(&subcmd.state_file)?;
+            }
+            if subcmd.amend_state || subcmd.save_state || !subcmd.remove_snapshot.is_empty() {
+                if let Some(max) = subcmd.keep_snapshots {
+                    history.prune(max);
+                }
+                history.to_file(&subcmd.state_file, &context)?;
+            }
+
+            if subcmd.prune_cache {
+                for path in prune_cache(&origins, subcmd.dry_run, &context)? {
+                    if subcmd.dry_run {
+                        eprintln!("would remove {}", path.display());
+                    }
+                }
             }
 
-            let mut change_log = ChangeLog::new(subcmd.group_by.to_owned());
-            for change_log_entry in change_log_entries.into_iter() {
-                change_log.insert(change_log_entry)?;
+            if let Some(path) = &subcmd.save_report {
+                run_report.to_file(path, &context)?;
             }
-            if command.output == OutputType::Yaml {
-                println!("{}", change_log.to_yaml()?);
+            let empty = output_report(&command, run_report, &group_by, &report_options, &context)?;
+            if !failures.is_empty() {
+                for failure in &failures {
+                    print_error(failure);
+                }
+                return Err(FailedProjects(failures.len()));
             }
+            check_not_empty(&report_options, empty)?;
+        }
+        SubCommand::Lint(subcmd) => {
+            let raw = match &subcmd.message_file {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => {
+                    let mut raw = String::new();
+                    std::io::stdin().read_to_string(&mut raw)?;
+                    raw
+                }
+            };
+            let rules = LintRules {
+                max_summary_length: subcmd.max_summary_length,
+                no_final_period: subcmd.no_final_period,
+            };
+            let violations = lint(&raw, &rules)?;
+            for violation in &violations {
+                eprintln!("{}", violation);
+            }
+            if !violations.is_empty() {
+                return Err(LintViolations(violations.len()));
+            }
+        }
+        SubCommand::Config(subcmd) => match &subcmd.sub_command {
+            ConfigSubCommand::Check(check) => run_check(check)?,
+        },
+        SubCommand::Check(subcmd) => run_check(subcmd)?,
+        SubCommand::Init(subcmd) => {
+            let path = init::config_path(&subcmd.path);
+            if path.exists() && !subcmd.force {
+                return Err(ConfigurationExists(path));
+            }
+            let project = if subcmd.no_project {
+                None
+            } else {
+                init::detect_project(&std::env::current_dir()?)?
+            };
+            context.write(&path, init::skeleton(project.as_ref()))?;
+            log::info!("wrote {}", path.display());
+        }
+        SubCommand::Completions(subcmd) => subcmd.shell.generate(&mut std::io::stdout()),
+        SubCommand::Render(subcmd) => {
+            let run_report = RunReport::from_file(&subcmd.run_report)?;
+            let empty = output_report(
+                &command,
+                run_report,
+                &subcmd.group_by,
+                &subcmd.report,
+                &context,
+            )?;
+            check_not_empty(&subcmd.report, empty)?;
         }
     }
 
     Ok(())
 }
 
-fn process_repository(
-    repository: &str,
-    order_by: Vec<CommitField>,
-    branches_name: &[BranchName],
-    team: Option<String>,
-) -> Result<ChangeLog> {
-    let mut project = Project::from_standalone_repository(repository, branches_name)?;
-    project.team = team;
-    let mut sentinels = Sentinels::new();
-    let mut change_log = ChangeLog::new(order_by);
-    for branch_name in &project.branches_name {
-        let walker = project.build_walker(branch_name.as_str(), &sentinels)?;
-        let (change_log_entries, new_sentinels) = project.extract_messages(walker);
-        sentinels.extend(new_sentinels);
-        for entry in change_log_entries {
-            change_log.insert(ChangeLogEntry::new(
-                "".to_string().into(),
-                branch_name.to_owned(),
-                entry,
-            ))?;
+/// Build the changelog of the entries then render it, once per period when slicing. Return
+/// whether the changelog is empty.
+fn output_report(
+    command: &Command,
+    mut run_report: RunReport,
+    group_by: &[CommitField],
+    options: &ReportOptions,
+    context: &RunContext,
+) -> Result<bool> {
+    if options.dedup {
+        run_report.dedup(group_by.contains(&CommitField::Branch));
+    }
+    let entries = std::mem::take(&mut run_report.entries);
+    let (scanned, truncated) = (run_report.scanned, run_report.truncated.clone());
+    let build_change_log = |entries: Vec<ChangeLogEntry>| -> Result<(ChangeLog, Vec<String>)> {
+        let mut change_log = run_report.new_change_log(group_by.to_owned());
+        if let Some(empty_key) = &options.empty_key {
+            change_log = change_log.with_empty_key(empty_key.clone());
         }
+        for change_log_entry in entries.into_iter() {
+            change_log.insert(change_log_entry)?;
+        }
+        if options.warn_missing_descriptions {
+            for (field, key) in change_log.missing_descriptions() {
+                log::warn!("missing description for {} key '{}'", field, key);
+            }
+        }
+        change_log.sort_groups(options.sort_groups);
+        change_log.sort_entries(options.sort_by);
+        let renders_bodies = matches!(
+            command.output,
+            OutputType::Template | OutputType::KeepAChangelog
+        );
+        if options.normalize_bodies && renders_bodies {
+            change_log.normalize_bodies(options.wrap_bodies);
+        }
+        let flattened = check_constant_fields(&mut change_log, options.auto_flatten)?;
+        Ok((change_log, flattened))
+    };
+
+    let stdout = std::io::stdout();
+    let mut output: Box<dyn Write> = match &options.output_file {
+        Some(path) => Box::new(BufWriter::new(context.create(path)?)),
+        None => Box::new(stdout.lock()),
+    };
+    let colors = options.output_file.is_none() && colors_enabled();
+    let mut empty = true;
+    if options.suggest_bump {
+        let (change_log, _) = build_change_log(entries)?;
+        empty = change_log.is_empty();
+        let bump = change_log.suggest_bump();
+        match &options.current_version {
+            Some(version) => writeln!(output, "{}", version.bump(bump))?,
+            None => writeln!(output, "{}", bump)?,
+        }
+    } else if let Some(period_kind) = options.slice_by {
+        if let Some(output_dir) = &options.split_output_dir {
+            context.create_dir_all(output_dir)?;
+        }
+        // Slack gets a single message, spanning all the periods
+        let slack_entries = if slack_enabled(options) {
+            entries.clone()
+        } else {
+            Vec::new()
+        };
+        let mut span: Option<Period> = None;
+        for (period, entries) in slice_entries(period_kind, entries) {
+            span = Some(Period {
+                start: span.map_or(period.start, |span| span.start),
+                end: period.end,
+            });
+            let (change_log, flattened) = build_change_log(entries)?;
+            empty &= change_log.is_empty();
+            let report = Report {
+                meta: ReportMeta {
+                    period: Some(period),
+                    flattened,
+                    read_only: context.read_only,
+                    scanned,
+                    truncated: truncated.clone(),
+                },
+                changes: &change_log,
+                with_meta: options.with_meta,
+                with_bodies: options.normalize_bodies,
+            };
+            match &options.split_output_dir {
+                Some(output_dir) => context.write(
+                    &output_dir.join(format!("{}.{}", period.start, output_extension(command))),
+                    render(command, &report, false)?,
+                )?,
+                None => write_report(command, &report, colors, &mut output)?,
+            }
+        }
+        if slack_enabled(options) {
+            let (change_log, _) = build_change_log(slack_entries)?;
+            let report = Report {
+                meta: ReportMeta {
+                    period: span,
+                    ..ReportMeta::default()
+                },
+                changes: &change_log,
+                with_meta: false,
+                with_bodies: false,
+            };
+            notify_slack(options, &report)?;
+        }
+    } else {
+        let (change_log, flattened) = build_change_log(entries)?;
+        empty = change_log.is_empty();
+        let report = Report {
+            meta: ReportMeta {
+                flattened,
+                read_only: context.read_only,
+                scanned,
+                truncated,
+                ..ReportMeta::default()
+            },
+            changes: &change_log,
+            with_meta: options.with_meta,
+            with_bodies: options.normalize_bodies,
+        };
+        write_report(command, &report, colors, &mut output)?;
+        notify_slack(options, &report)?;
+    }
+    output.flush()?;
+    Ok(empty)
+}
+
+/// Fail if the changelog is empty and `--fail-on-empty` is set
+fn check_not_empty(options: &ReportOptions, empty: bool) -> Result<()> {
+    if options.fail_on_empty && empty {
+        Err(EmptyChangeLog)
+    } else {
+        Ok(())
+    }
+}
+
+/// Post the report to the Slack webhook if any, or print the message on dry run
+fn notify_slack(options: &ReportOptions, report: &Report) -> Result<()> {
+    if !slack_enabled(options) {
+        return Ok(());
+    }
+    if report.changes.is_empty() {
+        log::info!("no changes to post to Slack");
+        return Ok(());
+    }
+    let payload = slack::payload(report);
+    if options.slack_dry_run {
+        eprintln!("{}", serde_json::to_string_pretty(&payload)?);
+    } else if let Some(webhook) = &options.slack_webhook {
+        slack::post(webhook, &payload)?;
+        log::info!("posted the changelog to Slack");
+    }
+    Ok(())
+}
+
+/// Whether the report is posted to Slack, or printed on dry run
+fn slack_enabled(options: &ReportOptions) -> bool {
+    options.slack_dry_run || options.slack_webhook.is_some()
+}
+
+/// Render the report in the requested output type. Pretty output is colored if `colors` is set.
+fn render(command: &Command, report: &Report, colors: bool) -> Result<String> {
+    match command.output {
+        OutputType::Yaml => report.to_yaml(),
+        OutputType::Template => report.to_template(template_path(command)?),
+        OutputType::Pretty => Ok(report.to_pretty(colors)),
+        OutputType::Stats => report.to_stats(),
+        OutputType::KeepAChangelog => Ok(report.to_keep_a_changelog()),
+        OutputType::Html => Ok(report.to_html()),
+        OutputType::Csv => report.to_csv(b','),
+        OutputType::Tsv => report.to_csv(b'\t'),
+    }
+}
+
+/// Write the report in the requested output type to `output`, followed by a newline. The
+/// YAML and tabular outputs are streamed instead of rendered first.
+fn write_report(
+    command: &Command,
+    report: &Report,
+    colors: bool,
+    output: &mut dyn Write,
+) -> Result<()> {
+    match command.output {
+        OutputType::Yaml => report.write_yaml(&mut *output)?,
+        OutputType::Csv => report.write_csv(b',', &mut *output)?,
+        OutputType::Tsv => report.write_csv(b'\t', &mut *output)?,
+        _ => output.write_all(render(command, report, colors)?.as_bytes())?,
+    }
+    Ok(writeln!(output)?)
+}
+
+/// Warn about the group-by fields sharing the same key for all the entries, or remove
+/// their levels when `auto_flatten` is set. Return the removed fields.
+fn check_constant_fields(change_log: &mut ChangeLog, auto_flatten: bool) -> Result<Vec<String>> {
+    if auto_flatten {
+        let flattened = change_log.flatten_constant_levels()?;
+        Ok(flattened.iter().map(|field| field.to_string()).collect())
+    } else {
+        for (field, key) in change_log.constant_fields() {
+            log::warn!(
+                "all entries share {0} '{1}'; consider removing '{0}' from --group-by",
+                field,
+                key
+            );
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Get the extension of the files rendered in the requested output type
+fn output_extension(command: &Command) -> &str {
+    match command.output {
+        OutputType::Yaml | OutputType::Stats => "yaml",
+        OutputType::Pretty => "txt",
+        OutputType::KeepAChangelog => "md",
+        OutputType::Html => "html",
+        OutputType::Csv => "csv",
+        OutputType::Tsv => "tsv",
+        OutputType::Template => command
+            .template
+            .as_ref()
+            .and_then(|path| path.extension())
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("txt"),
     }
-    Ok(change_log)
 }
 
-fn process_projects(
+fn template_path(command: &Command) -> Result<&Path> {
+    command.template.as_deref().ok_or(MissingTemplate)
+}
+
+/// Process the projects in a dedicated thread, drawing a progress bar for each of them
+fn process_projects_with_bars(
     config: Configuration,
     snapshot: Option<Snapshot>,
-) -> Result<(Vec<ChangeLogEntry>, Snapshot)> {
+    options: TraversalOptions,
+    context: RunContext,
+    keep_going: bool,
+) -> Result<ProjectsRun> {
     let bars = MultiProgress::new();
 
     let name_max_len = config.get_branch_name_max_len();
@@ -156,114 +546,121 @@ fn process_projects(
 
     let (tx_bars, rx_bars) = channel();
     let projects_count = config.projects.len();
-    // Spawn the parallel iterator in a dedicated thread, because of the call
+    // Spawn the processing in a dedicated thread, because of the call
     // of `MultiProcess.join_and_clear()` blocking method is required to draws bars.
     let handle = spawn(move || {
-        let default_branches_name = vec![config.default_branch.clone()];
-        config
-            .projects
-            .par_iter()
-            .map_with(
-                tx_bars.clone(),
-                |tx_bars,
-                 cfg_project|
-                 -> Result<(Vec<ChangeLogEntry>, RepositoryOrigin, RepositorySnapshot)> {
-                    let branches_name = cfg_project.get_branches_name(&default_branches_name);
-
-                    let steps = 1 + (branches_name.len() as u64) * 2;
-                    let bar = ProgressBar::new(steps);
-                    tx_bars.send(bar.clone()).unwrap();
-                    // wait a little to let the MultiProgress processes the message
-                    // otherwise display non-styled,  non-managed, bars
-                    sleep(Duration::from_millis(10));
-                    bar.set_style(bar_style.clone());
-                    bar.set_prefix(cfg_project.name.to_owned());
-                    bar.set_message("pending");
-                    bar.enable_steady_tick(100);
-                    bar.set_message(format!(
-                        "try to open cached repository: {}",
-                        cfg_project.origin
-                    ));
-
-                    let team = cfg_project.team.clone();
-
-                    let mut project = if let Ok(project) =
-                        Project::from_cache(&cfg_project.name, &cfg_project.origin, &branches_name)
-                    {
-                        project
-                    } else {
-                        bar.set_message(format!("clone repository: {}", cfg_project.origin));
-                        Project::from_remote(
-                            &cfg_project.name,
-                            &cfg_project.origin,
-                            &branches_name,
-                        )?
-                    };
-                    project.team = team;
-                    if let Some(snapshot) = &snapshot {
-                        project.snapshot = snapshot.get(&cfg_project.origin).cloned();
-                    }
-                    bar.inc(1);
-
-                    let mut repo_snapshot = RepositorySnapshot::new();
-                    let mut change_sets = Vec::new();
-                    for branch_name in &project.branches_name {
-                        bar.set_message(format!("fetch branch: {}", &branch_name));
-                        let hash = project.fetch_branch(branch_name)?;
-                        repo_snapshot.insert(branch_name.clone(), hash);
-                        bar.inc(1);
-                    }
-
-                    change_sets.extend(report_branches(&bar, &project)?);
-
-                    bar.set_message("done");
-                    bar.finish();
-                    Ok((change_sets, cfg_project.origin.clone(), repo_snapshot))
-                },
-            )
-            .collect::<Vec<_>>()
+        process_projects(
+            &config,
+            snapshot.as_ref(),
+            &options,
+            &context,
+            keep_going,
+            |cfg_project, steps| {
+                let bar = ProgressBar::new(steps);
+                tx_bars.send(bar.clone()).unwrap();
+                // wait a little to let the MultiProgress processes the message
+                // otherwise display non-styled,  non-managed, bars
+                sleep(Duration::from_millis(10));
+                bar.set_style(bar_style.clone());
+                bar.set_prefix(cfg_project.name.to_owned());
+                bar.set_message("pending");
+                bar.enable_steady_tick(100);
+                Bar(bar)
+            },
+        )
     });
     rx_bars.iter().take(projects_count).for_each(|bar| {
         bars.add(bar);
     });
     bars.join_and_clear().unwrap();
-    let results = handle.join().unwrap();
+    handle.join().unwrap()
+}
 
-    let mut builder = SnapshotBuilder::new();
-    let mut all_change_sets = Vec::new();
+/// Progress bar of a project
+struct Bar(ProgressBar);
+
+impl Progress for Bar {
+    fn set_message(&self, message: String) {
+        self.0.set_message(message);
+    }
 
-    for result in results {
-        let (change_sets, origin, repo_snapshot) = result?;
-        builder.add_repository_snapshot(origin, repo_snapshot);
-        all_change_sets.extend(change_sets);
+    fn inc(&self, delta: u64) {
+        self.0.inc(delta);
     }
 
-    Ok((all_change_sets, builder.build()))
+    fn finish_with_message(&self, message: String) {
+        self.0.finish_with_message(message);
+    }
+
+    fn abandon_with_message(&self, message: String) {
+        self.0.abandon_with_message(message);
+    }
 }
 
-fn report_branches(bar: &ProgressBar, project: &Project) -> Result<Vec<ChangeLogEntry>> {
-    let mut sentinels = Sentinels::new();
-    let mut entries = Vec::new();
-    for branch_name in &project.branches_name {
-        bar.set_message(format!("traverse branch {}", branch_name));
-        if let Some(Some(head)) = project
-            .snapshot
-            .as_ref()
-            .map(|snapshot| snapshot.get(branch_name))
-        {
-            sentinels.insert(Oid::from_str(head.as_str())?);
+/// Check the configuration, the projects' repositories and the state file, listing all their
+/// issues before failing
+fn run_check(check: &Check) -> Result<()> {
+    let mut problems = Vec::new();
+    match Configuration::read_source(&check.config_file) {
+        Ok(config) => {
+            let issues = config_check::check(&config)
+                .into_iter()
+                .chain(config_check::check_repositories(&config, check.offline));
+            problems.extend(issues.map(|issue| issue.to_string()));
         }
-        let walker = project.build_walker(branch_name.as_str(), &sentinels)?;
-        let (messages, new_sentinels) = project.extract_messages(walker);
-        entries.extend(messages.into_iter().map(|message| {
-            ChangeLogEntry::new(
-                project.get_origin().unwrap(),
-                branch_name.to_owned(),
-                message,
-            )
-        }));
-        sentinels.extend(&new_sentinels);
-        bar.inc(1);
+        Err(error) => problems.push(error_chain(&error)),
+    }
+    if let Err(error) = SnapshotHistory::from_file(&check.state_file) {
+        problems.push(format!(
+            "state file {}: {}",
+            check.state_file,
+            error_chain(&error)
+        ));
+    }
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    if !problems.is_empty() {
+        return Err(CheckFailures(problems.len()));
+    }
+    Ok(())
+}
+
+/// Print the index, hash, creation date, label and repositories count of each snapshot
+fn list_snapshots(history: &SnapshotHistory) {
+    for (index, snapshot) in history.iter_indexed() {
+        let created_at = snapshot
+            .created_at()
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_else(|| "-".to_owned());
+        println!(
+            "{} | {} | {} | {} | {}",
+            index,
+            snapshot.hash(),
+            created_at,
+            snapshot.label().unwrap_or("-"),
+            snapshot.repositories_count()
+        );
+    }
+}
+
+/// Print how long each project took, slowest first, and how its origin was accessed,
+/// once all of them are processed
+fn print_run_summary(summaries: &[ProjectSummary]) {
+    let mut projects: Vec<_> = summaries.iter().collect();
+    projects.sort_by_key(|summary| std::cmp::Reverse(summary.timings.total()));
+    let name_max_len = summaries
+        .iter()
+        .map(|summary| summary.name.len())
+        .max()
+        .unwrap_or(0);
+    for summary in projects {
+        eprintln!(
+            "{:>width$}: {}, {} access",
+            summary.name,
+            summary.timings.summary(),
+            summary.auth_method,
+            width = name_max_len
+        );
     }
-    Ok(entries)
 }
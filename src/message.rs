@@ -5,9 +5,12 @@ use std::str::FromStr;
 use pest::iterators::Pairs;
 use pest::Parser;
 use pest_derive::Parser;
-use serde::Serialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize)]
+use crate::changelog::TypeAliases;
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub struct CommitScope(String);
 
 impl CommitScope {
@@ -42,19 +45,115 @@ impl fmt::Display for CommitScope {
     }
 }
 
+/// Person identity, parsed from the `Name <email>` format
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+impl FromStr for Author {
+    type Err = ();
+
+    /// Parse `Name <email>`, degrading to a name-only author on malformed values
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let (Some(start), true) = (s.find('<'), s.ends_with('>')) {
+            let email = s[start + 1..s.len() - 1].trim();
+            if !email.is_empty() {
+                return Ok(Self {
+                    name: s[..start].trim().to_owned(),
+                    email: Some(email.to_owned()),
+                });
+            }
+        }
+        Ok(Self {
+            name: s.to_owned(),
+            email: None,
+        })
+    }
+}
+
 /// Parsed commit message following [Conventional Commits](https://www.conventionalcommits.org/en/v1.0.0/)
 /// convention.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ConventionalMessage {
     pub ctype: CommitType,
-    pub scope: Option<CommitScope>,
+    /// Scopes of the headline, like `api` and `db` in `feat(api,db): ...`
+    pub scopes: Vec<CommitScope>,
     pub is_breaking: bool,
     pub summary: String,
     pub body: Option<String>,
     pub trailers: Vec<(String, String)>,
+    pub references: Vec<String>,
+    /// Co-authors from the `Co-authored-by` trailers, which aren't kept in `trailers`
+    pub co_authors: Vec<Author>,
+    /// Hash of the commit reverted by this one, for revert commits
+    pub reverted_commit: Option<String>,
+    /// Issue references matching the issue patterns, like `#123` or `JIRA-456`
+    #[serde(default)]
+    pub issues: Vec<String>,
+    /// Gitmoji prefixing the headline, as an emoji like `✨` or a shortcode like `:sparkles:`
+    #[serde(default)]
+    pub gitmoji: Option<String>,
+    /// Description of the `BREAKING CHANGE` (or `BREAKING-CHANGE`) footers, one per line
+    #[serde(default)]
+    pub breaking_change: Option<String>,
+}
+
+/// Tokens of the breaking change footer, synonyms
+const BREAKING_CHANGE_TOKENS: [&str; 2] = ["BREAKING CHANGE", "BREAKING-CHANGE"];
+
+/// Issue references matched by default: `#123`, `GH-123` and `JIRA-456`, whose project key
+/// has at least two characters
+pub const DEFAULT_ISSUE_PATTERNS: [&str; 3] = [r"#\d+\b", r"\bGH-\d+\b", r"\b[A-Z][A-Z0-9]+-\d+\b"];
+
+/// Prefixes of the `ABC-123` like identifiers which aren't issue references, e.g. `UTF-8` or
+/// `SHA-256`
+const NON_ISSUE_PREFIXES: [&str; 6] = ["CVE", "ISO", "RFC", "SHA", "UCS", "UTF"];
+
+/// Compiled patterns of the issue references
+#[derive(Debug, Clone)]
+pub struct IssuePatterns(Vec<Regex>);
+
+impl IssuePatterns {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self, regex::Error> {
+        patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern.as_ref()))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Find the references in the text, in order of appearance
+    fn find_all<'t>(&self, text: &'t str) -> Vec<(usize, &'t str)> {
+        let mut found: Vec<_> = self
+            .0
+            .iter()
+            .flat_map(|regex| regex.find_iter(text))
+            .filter(|found| !is_non_issue(found.as_str()))
+            .map(|found| (found.start(), found.as_str()))
+            .collect();
+        found.sort_unstable();
+        found
+    }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize)]
+/// Check whether the reference is a well-known identifier rather than an issue, like `UTF-8`
+fn is_non_issue(reference: &str) -> bool {
+    match reference.split_once('-') {
+        Some((prefix, _)) => NON_ISSUE_PREFIXES.contains(&prefix),
+        None => false,
+    }
+}
+
+impl Default for IssuePatterns {
+    fn default() -> Self {
+        Self::new(&DEFAULT_ISSUE_PATTERNS).expect("valid default patterns")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub enum CommitType {
     ContinuousIntegration,
     Build,
@@ -65,9 +164,143 @@ pub enum CommitType {
     Refactoring,
     Style,
     Test,
+    Revert,
+    Merge,
     Other(String),
 }
 
+/// How the commits' raw messages are parsed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ParseOptions {
+    /// Accept the near-miss conventional messages
+    pub lenient: bool,
+    /// Character starting the comment lines stripped before parsing
+    pub comment_char: char,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            lenient: false,
+            comment_char: DEFAULT_COMMENT_CHAR,
+        }
+    }
+}
+
+impl ConventionalMessage {
+    /// Parse the commit's raw message, once stripped of its comment lines and of the verbose
+    /// commits' diff
+    pub fn parse_with(raw: &str, options: &ParseOptions) -> Result<Self, pest::error::Error<Rule>> {
+        let stripped = strip_comments(raw, options.comment_char);
+        let stripped = stripped.trim_matches('\n');
+        if options.lenient {
+            Self::parse_lenient(stripped)
+        } else {
+            stripped.parse()
+        }
+    }
+
+    /// Remove the breaking change footers from the trailers, their description being in
+    /// `breaking_change`
+    pub fn drop_breaking_change_trailers(&mut self) {
+        self.trailers
+            .retain(|(token, _)| !BREAKING_CHANGE_TOKENS.contains(&token.as_str()));
+    }
+
+    /// Parse the message, tolerating the near-miss headlines: missing space after the colon,
+    /// whitespaces before it, or a final period
+    pub fn parse_lenient(s: &str) -> Result<Self, pest::error::Error<Rule>> {
+        match normalize_headline(s) {
+            Some(normalized) => normalized.parse(),
+            None => s.parse(),
+        }
+    }
+
+    /// Iterate over the values of the trailers with the given key, compared case-insensitively
+    pub fn trailer_values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.trailers
+            .iter()
+            .filter(move |(token, _)| token.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Replace the type by its canonical one if it's an alias, compared case-insensitively
+    pub fn apply_type_aliases(&mut self, aliases: &TypeAliases) {
+        let ctype = self.ctype.as_str();
+        if let Some((_, canonical)) = aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(ctype))
+        {
+            self.ctype = canonical.parse().expect("unfailable");
+        }
+    }
+
+    /// Set the issues referenced by the summary, the body and the trailers' values, once each
+    pub fn extract_issues(&mut self, patterns: &IssuePatterns) {
+        let texts = std::iter::once(self.summary.as_str())
+            .chain(self.body.as_deref())
+            .chain(self.trailers.iter().map(|(_, value)| value.as_str()));
+        let mut issues = Vec::new();
+        for text in texts {
+            for (_, issue) in patterns.find_all(text) {
+                if !issues.iter().any(|known| known == issue) {
+                    issues.push(issue.to_owned());
+                }
+            }
+        }
+        self.issues = issues;
+    }
+
+    /// Rebuild the message's headline, e.g. `feat(scope)!: summary`
+    pub fn headline(&self) -> String {
+        let mut headline = self.ctype.as_str().to_owned();
+        if !self.scopes.is_empty() {
+            let scopes: Vec<_> = self.scopes.iter().map(CommitScope::as_str).collect();
+            headline.push_str(&format!("({})", scopes.join(",")));
+        }
+        if self.is_breaking {
+            headline.push('!');
+        }
+        headline.push_str(": ");
+        headline.push_str(&self.summary);
+        headline
+    }
+}
+
+/// Remove the revert commits and the commits they revert, when both are present
+pub fn apply_reverts<T: AsRef<ConventionalMessage>>(mut messages: Vec<T>) -> Vec<T> {
+    while let Some((revert, original)) = find_revert_pair(&messages) {
+        messages.remove(revert.max(original));
+        messages.remove(revert.min(original));
+    }
+    messages
+}
+
+/// Find the indices of the first revert commit and of the commit it reverts
+fn find_revert_pair<T: AsRef<ConventionalMessage>>(messages: &[T]) -> Option<(usize, usize)> {
+    messages
+        .iter()
+        .map(AsRef::as_ref)
+        .enumerate()
+        .filter(|(_, message)| message.ctype == CommitType::Revert)
+        .find_map(|(revert, message)| {
+            messages
+                .iter()
+                .map(AsRef::as_ref)
+                .enumerate()
+                .position(|(original, other)| {
+                    original != revert && other.headline() == message.summary
+                })
+                .map(|original| (revert, original))
+        })
+}
+
+impl AsRef<ConventionalMessage> for ConventionalMessage {
+    fn as_ref(&self) -> &ConventionalMessage {
+        self
+    }
+}
+
 /// PEG parser based on Pest definition
 #[derive(Parser)]
 #[grammar = "conventional_message.pest"]
@@ -77,14 +310,22 @@ impl FromStr for ConventionalMessage {
     type Err = pest::error::Error<Rule>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rewritten = rewrite_revert_headline(s);
+        let s = rewritten.as_deref().unwrap_or(s);
         let mut parser = ConventionalMessageParser::parse(Rule::message, s)?;
         let mut message = ConventionalMessage {
             ctype: CommitType::Other("".to_owned()),
-            scope: None,
+            scopes: vec![],
             is_breaking: false,
             summary: "".to_string(),
             body: None,
             trailers: vec![],
+            references: vec![],
+            co_authors: vec![],
+            reverted_commit: None,
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
         };
 
         let pairs = parser.next().unwrap().into_inner();
@@ -101,9 +342,15 @@ impl FromStr for ConventionalMessage {
                             Rule::ctype => {
                                 message.ctype = pair.as_str().parse().expect("unfailable")
                             }
-                            Rule::scope => {
-                                message.scope = Some(pair.as_str().parse().expect("unfailable"))
+                            Rule::scopes => {
+                                message.scopes = pair
+                                    .into_inner()
+                                    .map(|scope| scope.as_str().trim())
+                                    .filter(|scope| !scope.is_empty())
+                                    .map(|scope| scope.parse().expect("unfailable"))
+                                    .collect()
                             }
+                            Rule::gitmoji => message.gitmoji = Some(pair.as_str().to_owned()),
                             Rule::summary => message.summary = pair.as_str().to_owned(),
                             Rule::break_mark => message.is_breaking = true,
                             _ => unreachable!(),
@@ -116,10 +363,270 @@ impl FromStr for ConventionalMessage {
             }
         }
 
+        message.references = parse_references(&message);
+        let (co_authors, trailers): (Vec<_>, Vec<_>) = std::mem::take(&mut message.trailers)
+            .into_iter()
+            .partition(|(token, _)| token.eq_ignore_ascii_case("co-authored-by"));
+        message.trailers = trailers;
+        message.co_authors = co_authors
+            .iter()
+            .map(|(_, value)| value.parse().expect("unfailable"))
+            .collect();
+        let breaking_changes: Vec<_> = message
+            .trailers
+            .iter()
+            .filter(|(token, _)| BREAKING_CHANGE_TOKENS.contains(&token.as_str()))
+            .map(|(_, value)| value.as_str())
+            .collect();
+        if !breaking_changes.is_empty() {
+            message.is_breaking = true;
+            message.breaking_change = Some(breaking_changes.join("\n"));
+        }
+        if message.ctype == CommitType::Revert {
+            message.reverted_commit = message.body.as_deref().and_then(parse_reverted_commit);
+        }
+
         Ok(message)
     }
 }
 
+/// Wrap a message which isn't a conventional one, with its first line as the summary and
+/// the remaining lines as the body
+pub fn unparsed_message(s: &str) -> ConventionalMessage {
+    let (summary, body) = s.trim().split_once('\n').unwrap_or((s.trim(), ""));
+    let body = body.trim();
+    ConventionalMessage {
+        ctype: CommitType::Other("unparsed".to_owned()),
+        scopes: vec![],
+        is_breaking: false,
+        summary: summary.trim().to_owned(),
+        body: if body.is_empty() {
+            None
+        } else {
+            Some(body.to_owned())
+        },
+        trailers: vec![],
+        references: vec![],
+        co_authors: vec![],
+        reverted_commit: None,
+        issues: vec![],
+        gitmoji: None,
+        breaking_change: None,
+    }
+}
+
+/// Clean up a body for rendering: trim the lines' trailing spaces, collapse consecutive blank
+/// lines into one, and hard-wrap the lines longer than `wrap` characters between their words,
+/// keeping their indentation
+pub fn normalize_body(body: &str, wrap: Option<usize>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.trim().lines().map(str::trim_end) {
+        if line.is_empty() && matches!(lines.last().map(String::as_str), None | Some("")) {
+            continue;
+        }
+        match wrap {
+            Some(width) if line.chars().count() > width => lines.extend(wrap_line(line, width)),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Split the line between its words into lines of at most `width` characters, unless a word
+/// is longer, each indented like the line
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let mut lines = Vec::new();
+    let mut current = indent.to_owned();
+    for word in line.split_whitespace() {
+        let is_empty = current.len() == indent.len();
+        if !is_empty && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::replace(&mut current, indent.to_owned()));
+        } else if !is_empty {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+/// Parse the messages of merge commits, like the GitHub's ones:
+/// `Merge pull request #123 from user/branch` followed by the pull request's title.
+/// The summary is the title if any, the merged branch's name otherwise.
+pub fn parse_merge_message(s: &str) -> Option<ConventionalMessage> {
+    let mut lines = s.lines();
+    let headline = lines.next()?.trim();
+    let (pull_request, branch) = if let Some(rest) = headline.strip_prefix("Merge pull request #") {
+        let (number, branch) = rest.split_once(" from ")?;
+        (Some(number.trim()), branch.trim())
+    } else {
+        let rest = headline.strip_prefix("Merge branch ")?;
+        let branch = rest.split(" into ").next()?;
+        (None, branch.trim_matches('\''))
+    };
+    let title = lines
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or(branch);
+
+    Some(ConventionalMessage {
+        ctype: CommitType::Merge,
+        scopes: vec![],
+        is_breaking: false,
+        summary: title.to_owned(),
+        body: None,
+        trailers: vec![],
+        references: pull_request
+            .map(|number| vec![format!("#{}", number)])
+            .unwrap_or_default(),
+        co_authors: vec![],
+        reverted_commit: None,
+        issues: vec![],
+        gitmoji: None,
+        breaking_change: None,
+    })
+}
+
+/// Character starting the comment lines of the edited commit messages, unless configured
+/// otherwise with git's `core.commentChar`
+pub const DEFAULT_COMMENT_CHAR: char = '#';
+
+/// Remove what git strips from an edited commit message: the comment lines and everything
+/// below the scissors line of `git commit --verbose`. Messages without git's template,
+/// recognized by its lines starting with the comment character then a space, or made of
+/// it alone, are kept as they are.
+pub fn strip_comments(raw: &str, comment_char: char) -> String {
+    let template = format!("{} ", comment_char);
+    let has_template = raw
+        .lines()
+        .any(|line| line.starts_with(&template) || line.strip_prefix(comment_char) == Some(""));
+    if !has_template {
+        return raw.to_string();
+    }
+    let scissors = format!("{} ------------------------ >8", comment_char);
+    raw.lines()
+        .take_while(|line| !line.starts_with(&scissors))
+        .filter(|line| !line.starts_with(comment_char))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rewrite the headline of the near-miss conventional messages to a conventional one:
+/// whitespaces around the colon are normalized to a single space after it, and the
+/// summary's final period is dropped (`feat :add thing.` becomes `feat: add thing`)
+fn normalize_headline(s: &str) -> Option<String> {
+    let headline = s.lines().next()?;
+    let start = match headline.strip_prefix(':') {
+        Some(rest) => headline.len() - rest.len() + rest.find(':')? + 1,
+        None => headline.len() - headline.trim_start_matches(|c: char| !c.is_ascii()).len(),
+    };
+    let start = headline.len() - headline[start..].trim_start().len();
+    let mut depth = 0;
+    let colon = headline[start..].find(|c: char| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        c == ':' && depth == 0
+    })? + start;
+    let prefix = headline[start..colon].trim_end();
+    let summary = headline[colon + 1..].trim();
+    let summary = match summary.strip_suffix('.') {
+        Some(stripped) if !stripped.ends_with('.') => stripped.trim_end(),
+        _ => summary,
+    };
+    if prefix.is_empty() || summary.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{}{}: {}{}",
+        &headline[..start],
+        prefix,
+        summary,
+        &s[headline.len()..]
+    ))
+}
+
+/// Rewrite the headline of messages generated by `git revert` (`Revert "feat: add thing"`)
+/// to a conventional one (`revert: feat: add thing`)
+fn rewrite_revert_headline(s: &str) -> Option<String> {
+    let headline = s.lines().next()?;
+    let original = headline.strip_prefix("Revert \"")?.strip_suffix('"')?;
+    Some(format!("revert: {}{}", original, &s[headline.len()..]))
+}
+
+/// Extract the commit hash from the `This reverts commit <hash>.` line
+fn parse_reverted_commit(body: &str) -> Option<String> {
+    body.lines().find_map(|line| {
+        let hash: String = line
+            .trim()
+            .strip_prefix("This reverts commit ")?
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .collect();
+        if hash.is_empty() {
+            None
+        } else {
+            Some(hash)
+        }
+    })
+}
+
+/// Trailer tokens whose bare value (`Closes #42`) is an issue/PR reference
+const CLOSING_KEYWORDS: &[&str] = &["close", "closes", "fix", "fixes", "resolve", "resolves"];
+
+/// Collect the issue/PR references (`#NNN`) from the body and the trailers' values
+fn parse_references(message: &ConventionalMessage) -> Vec<String> {
+    let mut references = Vec::new();
+    if let Some(body) = &message.body {
+        references.extend(scan_references(body));
+    }
+    for (token, value) in &message.trailers {
+        let is_closing = CLOSING_KEYWORDS.contains(&token.to_lowercase().as_str());
+        if is_closing && !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+            // hash trailers (`<token> #<value>`) are stored without the leading `#`
+            references.push(format!("#{}", value));
+        } else {
+            references.extend(scan_references(value));
+        }
+    }
+
+    let mut unique = Vec::with_capacity(references.len());
+    for reference in references {
+        if !unique.contains(&reference) {
+            unique.push(reference);
+        }
+    }
+    unique
+}
+
+/// Find all the `#NNN` tokens of the given text
+fn scan_references(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let preceded_by_word = i > 0 && chars[i - 1].is_alphanumeric();
+        if chars[i] == '#' && !preceded_by_word {
+            let digits: String = chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            let end = i + 1 + digits.len();
+            let followed_by_word = matches!(chars.get(end), Some(c) if c.is_alphanumeric());
+            if !digits.is_empty() && !followed_by_word {
+                references.push(format!("#{}", digits));
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    references
+}
+
 fn parse_trailers(pairs: Pairs<Rule>) -> Vec<(String, String)> {
     let mut trailers = Vec::new();
     for pair in pairs {
@@ -134,12 +641,16 @@ fn parse_trailers(pairs: Pairs<Rule>) -> Vec<(String, String)> {
             .as_str()
             .trim()
             .to_owned();
+        // unfold the value, joining its lines with a single space
         let value = pairs
             .next()
             .expect("broken parser: MUST have value")
             .as_str()
-            .trim()
-            .to_owned();
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
         trailers.push((token, value));
     }
     trailers
@@ -157,26 +668,50 @@ impl CommitType {
             CommitType::Refactoring => "refactor",
             CommitType::Style => "style",
             CommitType::Test => "test",
+            CommitType::Revert => "revert",
+            CommitType::Merge => "merge",
             CommitType::Other(s) => s.as_str(),
         }
     }
+
+    /// Get the default title of the known types in the rendered outputs, after the
+    /// conventional-changelog ones
+    pub fn title(&self) -> Option<&'static str> {
+        Some(match self {
+            CommitType::ContinuousIntegration => "Continuous Integration",
+            CommitType::Build => "Build System",
+            CommitType::BugFix => "Bug Fixes",
+            CommitType::Documentation => "Documentation",
+            CommitType::Feature => "Features",
+            CommitType::Performance => "Performance Improvements",
+            CommitType::Refactoring => "Code Refactoring",
+            CommitType::Style => "Styles",
+            CommitType::Test => "Tests",
+            CommitType::Revert => "Reverts",
+            CommitType::Merge => "Merges",
+            CommitType::Other(_) => return None,
+        })
+    }
 }
 
 impl FromStr for CommitType {
     type Err = ();
 
+    /// Parse the type case-insensitively, accepting some common aliases
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
+        Ok(match s.to_lowercase().as_str() {
             "build" => Self::Build,
             "ci" => Self::ContinuousIntegration,
-            "docs" => Self::Documentation,
-            "feat" => Self::Feature,
-            "fix" => Self::BugFix,
+            "docs" | "documentation" => Self::Documentation,
+            "feat" | "feature" => Self::Feature,
+            "fix" | "bugfix" => Self::BugFix,
             "perf" => Self::Performance,
             "refactor" => Self::Refactoring,
             "style" => Self::Style,
             "test" => Self::Test,
-            s => Self::Other(s.to_owned()),
+            "revert" => Self::Revert,
+            "merge" => Self::Merge,
+            _ => Self::Other(s.to_owned()),
         })
     }
 }
@@ -185,15 +720,48 @@ impl FromStr for CommitType {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_unparsed_message() {
+        let message = unparsed_message("Fixed the thing\n\nIt was broken.\nReally.\n");
+        assert_eq!(CommitType::Other("unparsed".to_string()), message.ctype);
+        assert_eq!("Fixed the thing", message.summary);
+        assert_eq!(Some("It was broken.\nReally.".to_string()), message.body);
+
+        assert_eq!(None, unparsed_message("WIP").body);
+    }
+
+    #[test]
+    fn test_normalize_body() {
+        let body = "Some body  \ncontent\n\n\n\nmultiple\n\n  indented words to wrap\n";
+        assert_eq!(
+            "Some body\ncontent\n\nmultiple\n\n  indented words to wrap",
+            normalize_body(body, None)
+        );
+        assert_eq!(
+            "Some body\ncontent\n\nmultiple\n\n  indented\n  words to\n  wrap",
+            normalize_body(body, Some(10))
+        );
+        assert_eq!(
+            "a\nunbreakable\nword",
+            normalize_body("a unbreakable word", Some(5))
+        );
+    }
+
     #[test]
     fn test_parse_simple_message() {
         let expected = ConventionalMessage {
             ctype: CommitType::Feature,
-            scope: None,
+            scopes: vec![],
             is_breaking: false,
             summary: "new feature".to_string(),
             body: None,
             trailers: vec![],
+            references: vec![],
+            co_authors: vec![],
+            reverted_commit: None,
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
         };
 
         let input = format!("feat: {}", &expected.summary);
@@ -205,7 +773,7 @@ mod test {
     fn test_parse_message_with_trailers() {
         let expected = ConventionalMessage {
             ctype: CommitType::Feature,
-            scope: None,
+            scopes: vec![],
             is_breaking: false,
             summary: "new feature".to_string(),
             body: None,
@@ -213,6 +781,12 @@ mod test {
                 ("Team".to_string(), "X functional".to_string()),
                 ("foo".to_string(), "bar metal".to_string()),
             ],
+            references: vec![],
+            co_authors: vec![],
+            reverted_commit: None,
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
         };
 
         let input = format!(
@@ -231,16 +805,22 @@ mod test {
     fn test_parse_message_with_all_syntaxes() {
         let expected = ConventionalMessage {
             ctype: CommitType::BugFix,
-            scope: Some("scope".parse().unwrap()),
+            scopes: vec!["scope".parse().unwrap()],
             is_breaking: true,
             summary: "the summary".to_string(),
             body: Some("Some body content\n\n\nmultiple\nlines\nblock".to_string()),
             trailers: vec![("Key".to_string(), "Value".to_string())],
+            references: vec![],
+            co_authors: vec![],
+            reverted_commit: None,
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
         };
 
         let input = format!(
             "fix({})!: {}\n\n{}\n\n{}: {} \n",
-            expected.scope.as_ref().unwrap(),
+            expected.scopes[0],
             &expected.summary,
             expected.body.as_ref().unwrap(),
             &expected.trailers[0].0,
@@ -250,4 +830,420 @@ mod test {
         let message = input.parse().unwrap();
         assert_eq!(expected, message);
     }
+
+    #[test]
+    fn test_parse_commit_type_variants() {
+        let cases = [
+            ("Feat", CommitType::Feature),
+            ("FEATURE", CommitType::Feature),
+            ("FIX", CommitType::BugFix),
+            ("bugfix", CommitType::BugFix),
+            ("BugFix", CommitType::BugFix),
+            ("Documentation", CommitType::Documentation),
+            ("Docs", CommitType::Documentation),
+            ("Revert", CommitType::Revert),
+            ("Chore", CommitType::Other("Chore".to_string())),
+        ];
+        for (raw, expected) in &cases {
+            assert_eq!(*expected, raw.parse::<CommitType>().unwrap(), "{}", raw);
+        }
+
+        let message: ConventionalMessage = "FEAT(api): add endpoint".parse().unwrap();
+        assert_eq!(CommitType::Feature, message.ctype);
+        assert_eq!("add endpoint", message.summary);
+        let message: ConventionalMessage = "Fix: typo".parse().unwrap();
+        assert_eq!(CommitType::BugFix, message.ctype);
+
+        let message: ConventionalMessage = "Feature(ui): dark mode".parse().unwrap();
+        assert_eq!("feat", message.ctype.as_str());
+        assert_eq!("feat(ui): dark mode", message.headline());
+    }
+
+    #[test]
+    fn test_apply_type_aliases() {
+        let mut aliases = TypeAliases::new();
+        aliases.insert("Chore".to_string(), "build".to_string());
+        aliases.insert("hotfix".to_string(), "fix".to_string());
+        let cases = [
+            ("chore: bump", CommitType::Build),
+            ("HotFix: patch", CommitType::BugFix),
+            ("feature: known", CommitType::Feature),
+            ("wip: unknown", CommitType::Other("wip".to_string())),
+        ];
+        for (raw, expected) in &cases {
+            let mut message: ConventionalMessage = raw.parse().unwrap();
+            message.apply_type_aliases(&aliases);
+            assert_eq!(*expected, message.ctype, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_extract_issues() {
+        let mut message: ConventionalMessage =
+            "fix: JIRA-456 crash on GH-12\n\nSee #7, UTF-8, SHA-256, ISO-8601 and A-1.\n\n\
+Closes: #123\nRefs: JIRA-456, AB2-3"
+                .parse()
+                .unwrap();
+        message.extract_issues(&IssuePatterns::default());
+        assert_eq!(
+            vec!["JIRA-456", "GH-12", "#7", "#123", "AB2-3"],
+            message.issues
+        );
+
+        message.extract_issues(&IssuePatterns::new(&[r"#\d+"]).unwrap());
+        assert_eq!(vec!["#7", "#123"], message.issues);
+
+        assert!(IssuePatterns::new(&["(unclosed"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_multiple_scopes() {
+        let message: ConventionalMessage = "feat(api, db)!: span both".parse().unwrap();
+        let scopes: Vec<_> = message.scopes.iter().map(CommitScope::as_str).collect();
+        assert_eq!(vec!["api", "db"], scopes);
+        assert!(message.is_breaking);
+        assert_eq!("feat(api,db)!: span both", message.headline());
+
+        let message: ConventionalMessage = "fix(,ui,): trailing comma".parse().unwrap();
+        assert_eq!(vec![CommitScope::from("ui".to_string())], message.scopes);
+
+        let message: ConventionalMessage = "fix(): empty".parse().unwrap();
+        assert!(message.scopes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_breaking_change_footers() {
+        let mut message: ConventionalMessage = "feat: new api\n\n\
+BREAKING CHANGE: the v1 endpoints are removed\n\
+BREAKING-CHANGE: the config is renamed\n\
+Team: core"
+            .parse()
+            .unwrap();
+        assert!(message.is_breaking);
+        assert_eq!(None, message.body);
+        assert_eq!(
+            Some("the v1 endpoints are removed\nthe config is renamed".to_string()),
+            message.breaking_change
+        );
+        assert_eq!(3, message.trailers.len());
+        assert_eq!("BREAKING CHANGE", message.trailers[0].0);
+
+        message.drop_breaking_change_trailers();
+        assert_eq!(
+            vec![("Team".to_string(), "core".to_string())],
+            message.trailers
+        );
+
+        // only the breaking change token may contain a space
+        let message: ConventionalMessage = "fix: bug\n\nBREAKING NEWS: none\nSEE ALSO: #2"
+            .parse()
+            .unwrap();
+        assert!(!message.is_breaking);
+        assert!(message.trailers.is_empty());
+        assert_eq!(None, message.breaking_change);
+        let message: ConventionalMessage =
+            "fix: bug\n\nbreaking change: lowercase".parse().unwrap();
+        assert!(!message.is_breaking);
+    }
+
+    #[test]
+    fn test_parse_folded_trailers() {
+        let message: ConventionalMessage = "feat!: new api\n\nSome body.\n\n\
+BREAKING-CHANGE: the v1 endpoints are removed,\n  migrate to v2 first\n\
+Team: core"
+            .parse()
+            .unwrap();
+        assert_eq!(Some("Some body.".to_string()), message.body);
+        assert_eq!(
+            vec![
+                (
+                    "BREAKING-CHANGE".to_string(),
+                    "the v1 endpoints are removed, migrate to v2 first".to_string()
+                ),
+                ("Team".to_string(), "core".to_string()),
+            ],
+            message.trailers
+        );
+
+        let message: ConventionalMessage = "fix: bug\n\nNote: first line\n\tsecond line\n \tthird"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            vec![(
+                "Note".to_string(),
+                "first line second line third".to_string()
+            )],
+            message.trailers
+        );
+    }
+
+    #[test]
+    fn test_trailer_values() {
+        let message: ConventionalMessage = "feat: new feature\n\nTeam: core\nTEAM: infra"
+            .parse()
+            .unwrap();
+        let values: Vec<_> = message.trailer_values("team").collect();
+        assert_eq!(vec!["core", "infra"], values);
+        assert_eq!(0, message.trailer_values("squad").count());
+    }
+
+    #[test]
+    fn test_parse_message_with_references() {
+        let expected = ConventionalMessage {
+            ctype: CommitType::BugFix,
+            scopes: vec![],
+            is_breaking: false,
+            summary: "bug".to_string(),
+            body: Some("Closes #42, #43".to_string()),
+            trailers: vec![],
+            references: vec!["#42".to_string(), "#43".to_string()],
+            co_authors: vec![],
+            reverted_commit: None,
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
+        };
+
+        let message = "fix: bug\n\nCloses #42, #43".parse().unwrap();
+        assert_eq!(expected, message);
+    }
+
+    #[test]
+    fn test_parse_references_from_hash_trailers() {
+        let message: ConventionalMessage = "fix: bug\n\nSome body, see #7\n\nFixes #12\nTeam #core"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            vec![
+                ("Fixes".to_string(), "12".to_string()),
+                ("Team".to_string(), "core".to_string()),
+            ],
+            message.trailers
+        );
+        assert_eq!(
+            vec!["#7".to_string(), "#12".to_string()],
+            message.references
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        for (deviant, expected) in &[
+            ("feat:add x", "feat: add x"),
+            ("feat(api)!:add x\n\nbody", "feat(api)!: add x\n\nbody"),
+            ("fix: crash.", "fix: crash"),
+            ("fix : crash", "fix: crash"),
+            ("fix\t:  crash . \n\nRefs: #1", "fix: crash\n\nRefs: #1"),
+            ("fix(a:b) :crash.", "fix(a:b): crash"),
+            (":bug: fix :crash.", ":bug: fix: crash"),
+            ("🐛fix:crash", "🐛fix: crash"),
+            ("Revert \"feat:add x.\"", "Revert \"feat: add x.\""),
+        ] {
+            let expected = expected.parse::<ConventionalMessage>().unwrap();
+            // strict parsing keeps the exact spec
+            assert_ne!(
+                Some(&expected),
+                deviant.parse().ok().as_ref(),
+                "{}",
+                deviant
+            );
+            assert_eq!(
+                expected,
+                ConventionalMessage::parse_lenient(deviant).unwrap(),
+                "{}",
+                deviant
+            );
+        }
+        assert_eq!(
+            "wait...",
+            ConventionalMessage::parse_lenient("fix: wait...")
+                .unwrap()
+                .summary
+        );
+        assert!(ConventionalMessage::parse_lenient("fix:").is_err());
+        assert!(ConventionalMessage::parse_lenient("WIP").is_err());
+        assert!(ConventionalMessage::parse_lenient("Update the README: typo").is_err());
+    }
+
+    #[test]
+    fn test_parse_with_comments() {
+        let raw = "# Please enter the commit message\nfix: crash\n\nSee #123 and\n#124 too\n\
+# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n";
+        assert!(raw.parse::<ConventionalMessage>().is_err());
+        let message = ConventionalMessage::parse_with(raw, &ParseOptions::default()).unwrap();
+        assert_eq!("crash", message.summary);
+        assert_eq!(Some("See #123 and".to_string()), message.body);
+        assert_eq!(vec!["#123".to_string()], message.references);
+
+        let raw = "fix: crash\n\n#123 stays\n; comment\n; ------------------------ >8\ndiff";
+        let options = ParseOptions {
+            comment_char: ';',
+            ..ParseOptions::default()
+        };
+        let message = ConventionalMessage::parse_with(raw, &options).unwrap();
+        assert_eq!(Some("#123 stays".to_string()), message.body);
+
+        // without git's template, a line starting with a reference isn't a comment
+        let raw = "fix: crash\n\nSee #123 and\n#124 too";
+        let message = ConventionalMessage::parse_with(raw, &ParseOptions::default()).unwrap();
+        assert_eq!(Some("See #123 and\n#124 too".to_string()), message.body);
+        assert_eq!(
+            vec!["#123".to_string(), "#124".to_string()],
+            message.references
+        );
+
+        let options = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let message = ConventionalMessage::parse_with("fix:crash.\n# comment\n", &options);
+        assert_eq!(
+            "fix: crash".parse::<ConventionalMessage>().unwrap(),
+            message.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_revert_message() {
+        let expected = ConventionalMessage {
+            ctype: CommitType::Revert,
+            scopes: vec![],
+            is_breaking: false,
+            summary: "feat(ui): add thing".to_string(),
+            body: Some("This reverts commit 1a2b3c4d5e6f.".to_string()),
+            trailers: vec![],
+            references: vec![],
+            co_authors: vec![],
+            reverted_commit: Some("1a2b3c4d5e6f".to_string()),
+            issues: vec![],
+            gitmoji: None,
+            breaking_change: None,
+        };
+
+        let message = "Revert \"feat(ui): add thing\"\n\nThis reverts commit 1a2b3c4d5e6f.\n"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, message);
+    }
+
+    #[test]
+    fn test_apply_reverts() {
+        let messages: Vec<ConventionalMessage> = vec![
+            "Revert \"feat(ui)!: add thing\"\n\nThis reverts commit 1a2b3c.",
+            "fix: keep me",
+            "feat(ui)!: add thing",
+            "revert: docs: not in range",
+        ]
+        .into_iter()
+        .map(|raw| raw.parse().unwrap())
+        .collect();
+
+        let messages = apply_reverts(messages);
+
+        let summaries: Vec<_> = messages.iter().map(|m| m.headline()).collect();
+        assert_eq!(
+            vec!["fix: keep me", "revert: docs: not in range"],
+            summaries
+        );
+    }
+
+    #[test]
+    fn test_parse_gitmoji() {
+        for (raw_message, gitmoji) in &[
+            ("✨ feat(api): add endpoint", "✨"),
+            ("♻️refactor(api): add endpoint", "♻️"),
+            (":sparkles: feat(api): add endpoint", ":sparkles:"),
+            ("🧑‍💻 feat(api): add endpoint", "🧑‍💻"),
+            ("⬆️ feat(api): add endpoint", "⬆️"),
+        ] {
+            let message: ConventionalMessage = raw_message.parse().unwrap();
+            assert_eq!(Some(gitmoji.to_string()), message.gitmoji);
+            assert_eq!("api", message.scopes[0].as_str());
+            assert_eq!("add endpoint", message.summary);
+        }
+        let message: ConventionalMessage = "✨ feat: add endpoint".parse().unwrap();
+        assert_eq!(CommitType::Feature, message.ctype);
+        assert_eq!(
+            None,
+            "feat: add endpoint"
+                .parse::<ConventionalMessage>()
+                .unwrap()
+                .gitmoji
+        );
+        assert!(":sparkles: add endpoint"
+            .parse::<ConventionalMessage>()
+            .is_err());
+        // letters and punctuation aren't emojis
+        for raw_message in &[
+            "é feat: add endpoint",
+            "漢字 feat: add endpoint",
+            "… feat: add endpoint",
+        ] {
+            assert!(
+                raw_message.parse::<ConventionalMessage>().is_err(),
+                "{}",
+                raw_message
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_co_authors() {
+        let message: ConventionalMessage =
+            "feat: pair work\n\nCo-authored-by: Jane Doe <jane@example.com>\nTeam: core\n\
+             co-authored-by: John"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            vec![("Team".to_string(), "core".to_string())],
+            message.trailers
+        );
+        assert_eq!(
+            vec![
+                Author {
+                    name: "Jane Doe".to_string(),
+                    email: Some("jane@example.com".to_string()),
+                },
+                Author {
+                    name: "John".to_string(),
+                    email: None,
+                },
+            ],
+            message.co_authors
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_author() {
+        let author: Author = "Jane Doe <jane@example.com".parse().unwrap();
+        assert_eq!(
+            Author {
+                name: "Jane Doe <jane@example.com".to_string(),
+                email: None,
+            },
+            author
+        );
+    }
+
+    #[test]
+    fn test_parse_pull_request_merge_message() {
+        let message = parse_merge_message(
+            "Merge pull request #123 from user/feature-x\n\nAdd the X feature\n",
+        )
+        .unwrap();
+        assert_eq!(CommitType::Merge, message.ctype);
+        assert_eq!("Add the X feature", message.summary);
+        assert_eq!(vec!["#123".to_string()], message.references);
+    }
+
+    #[test]
+    fn test_parse_branch_merge_message() {
+        let message = parse_merge_message("Merge branch 'feature/y' into master").unwrap();
+        assert_eq!(CommitType::Merge, message.ctype);
+        assert_eq!("feature/y", message.summary);
+        assert!(message.references.is_empty());
+
+        assert_eq!(None, parse_merge_message("feat: not a merge"));
+    }
 }
@@ -0,0 +1,341 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::{
+    changelog::{ChangeLogEntry, CommitField, KeyComparator},
+    error::{Error, Result},
+    message::CommitType,
+};
+
+/// Order of the groups' keys in a changelog
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum GroupOrder {
+    /// Keep the keys in the order they were first met
+    Insertion,
+    /// Natural, case and accent insensitive, order of the keys
+    Alpha,
+    /// Bytewise order of the keys
+    AlphaStrict,
+    /// Commit types by priority, or by the configured order, natural order of the other keys
+    Canonical,
+}
+
+impl FromStr for GroupOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "insertion" => Ok(Self::Insertion),
+            "alpha" => Ok(Self::Alpha),
+            "alpha-strict" => Ok(Self::AlphaStrict),
+            "canonical" => Ok(Self::Canonical),
+            _ => Err(Error::InvalidGroupOrder(s.to_owned())),
+        }
+    }
+}
+
+impl GroupOrder {
+    /// Get the comparator of the keys, `None` to keep the insertion order
+    pub fn comparator(&self) -> Option<KeyComparator<String>> {
+        match self {
+            Self::Insertion => None,
+            Self::Alpha | Self::Canonical => Some(Box::new(|a, b| natural_cmp(a, b))),
+            Self::AlphaStrict => Some(Box::new(|a, b| a.cmp(b))),
+        }
+    }
+
+    /// Get the comparator of the keys of the given group-by field. Canonically, the commit
+    /// types follow `type_order` if given, their priority otherwise.
+    pub fn field_comparator(
+        &self,
+        field: &CommitField,
+        type_order: &[String],
+    ) -> Option<KeyComparator<String>> {
+        match (self, field) {
+            (Self::Canonical, CommitField::CommitType) if type_order.is_empty() => {
+                Some(Box::new(|a, b| cmp_commit_types(a, b)))
+            }
+            (Self::Canonical, CommitField::CommitType) => {
+                let type_order = type_order.to_vec();
+                Some(Box::new(move |a, b| cmp_listed_types(&type_order, a, b)))
+            }
+            _ => self.comparator(),
+        }
+    }
+}
+
+/// Commit types in the order of their interest for the changelog's readers
+pub(crate) const COMMIT_TYPES_PRIORITY: &[CommitType] = &[
+    CommitType::Feature,
+    CommitType::BugFix,
+    CommitType::Performance,
+    CommitType::Refactoring,
+    CommitType::Revert,
+    CommitType::Documentation,
+    CommitType::Style,
+    CommitType::Test,
+    CommitType::Build,
+    CommitType::ContinuousIntegration,
+    CommitType::Merge,
+];
+
+/// Compare two commit type keys by priority, the unknown types coming last in natural order
+fn cmp_commit_types(a: &str, b: &str) -> Ordering {
+    let priority = |key: &str| {
+        let ctype = key.parse::<CommitType>().expect("unfailable");
+        COMMIT_TYPES_PRIORITY
+            .iter()
+            .position(|known| *known == ctype)
+            .unwrap_or(COMMIT_TYPES_PRIORITY.len())
+    };
+    priority(a)
+        .cmp(&priority(b))
+        .then_with(|| natural_cmp(a, b))
+}
+
+/// Compare two commit type keys by their position in the list, compared case-insensitively,
+/// the unlisted types coming last in natural order
+fn cmp_listed_types(type_order: &[String], a: &str, b: &str) -> Ordering {
+    let position = |key: &str| {
+        type_order
+            .iter()
+            .position(|listed| listed.eq_ignore_ascii_case(key))
+            .unwrap_or(type_order.len())
+    };
+    position(a)
+        .cmp(&position(b))
+        .then_with(|| natural_cmp(a, b))
+}
+
+/// Order of the entries within each group of a changelog
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EntryOrder {
+    /// Most recent commit first
+    Date,
+    /// Canonical type key (`as_str`), bytewise
+    Type,
+    /// Natural order of the summaries
+    Summary,
+    /// Natural order of the first scopes, unscoped entries first
+    Scope,
+}
+
+impl FromStr for EntryOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "date" => Ok(Self::Date),
+            "type" => Ok(Self::Type),
+            "summary" => Ok(Self::Summary),
+            "scope" => Ok(Self::Scope),
+            _ => Err(Error::InvalidEntryOrder(s.to_owned())),
+        }
+    }
+}
+
+impl EntryOrder {
+    /// Get the comparator of the entries
+    pub fn comparator(&self) -> fn(&ChangeLogEntry, &ChangeLogEntry) -> Ordering {
+        match self {
+            Self::Date => |a, b| b.date().cmp(a.date()),
+            Self::Type => |a, b| a.as_ref().ctype.as_str().cmp(b.as_ref().ctype.as_str()),
+            Self::Summary => |a, b| natural_cmp(&a.as_ref().summary, &b.as_ref().summary),
+            Self::Scope => |a, b| {
+                let first_scope = |entry: &ChangeLogEntry| entry.as_ref().scopes.first().cloned();
+                match (first_scope(a), first_scope(b)) {
+                    (Some(a), Some(b)) => natural_cmp(a.as_str(), b.as_str()),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Chunk<'a> {
+    Digits(&'a str),
+    Text(&'a str),
+}
+
+/// Split the string into runs of ASCII digits and runs of other characters
+fn chunks(s: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut in_digits = None;
+    for (position, c) in s.char_indices() {
+        let is_digit = c.is_ascii_digit();
+        match in_digits {
+            Some(previous) if previous != is_digit => {
+                chunks.push(to_chunk(&s[start..position], previous));
+                start = position;
+            }
+            _ => {}
+        }
+        in_digits = Some(is_digit);
+    }
+    if let Some(is_digit) = in_digits {
+        chunks.push(to_chunk(&s[start..], is_digit));
+    }
+    chunks
+}
+
+fn to_chunk(s: &str, is_digit: bool) -> Chunk<'_> {
+    if is_digit {
+        Chunk::Digits(s)
+    } else {
+        Chunk::Text(s)
+    }
+}
+
+/// Compare two runs of digits by their numeric value, whatever their length
+fn cmp_numbers(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Compare two texts ignoring the case and the accents of the latin letters
+fn cmp_folded(a: &str, b: &str) -> Ordering {
+    a.chars().flat_map(fold).cmp(b.chars().flat_map(fold))
+}
+
+/// Lower the case of the character then strip its accent
+fn fold(c: char) -> impl Iterator<Item = char> {
+    c.to_lowercase().flat_map(|c| {
+        let folded: &[char] = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => &['a'],
+            'æ' => &['a', 'e'],
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => &['c'],
+            'ď' | 'đ' => &['d'],
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => &['e'],
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => &['g'],
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => &['i'],
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => &['l'],
+            'ñ' | 'ń' | 'ņ' | 'ň' => &['n'],
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => &['o'],
+            'œ' => &['o', 'e'],
+            'ŕ' | 'ŗ' | 'ř' => &['r'],
+            'ś' | 'ŝ' | 'ş' | 'š' => &['s'],
+            'ß' => &['s', 's'],
+            'ţ' | 'ť' | 'ŧ' => &['t'],
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => &['u'],
+            'ý' | 'ÿ' => &['y'],
+            'ź' | 'ż' | 'ž' => &['z'],
+            _ => return vec![c],
+        };
+        folded.to_vec()
+    })
+}
+
+/// Compare two strings in natural order: runs of digits are compared by their numeric value
+/// and the other runs regardless of case and accents. Strings equal in this regard are
+/// ordered bytewise, so the order is total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a_chunks, b_chunks) = (chunks(a), chunks(b));
+    for pair in a_chunks.iter().zip(&b_chunks) {
+        let ordering = match pair {
+            (Chunk::Digits(a), Chunk::Digits(b)) => cmp_numbers(a, b),
+            (Chunk::Text(a), Chunk::Text(b)) => cmp_folded(a, b),
+            (Chunk::Digits(_), Chunk::Text(_)) => Ordering::Less,
+            (Chunk::Text(_), Chunk::Digits(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_chunks.len().cmp(&b_chunks.len()).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let cases = [
+            ("release/9", "release/10", Ordering::Less),
+            ("release/11", "release/10", Ordering::Greater),
+            ("v1.9.2", "v1.10.0", Ordering::Less),
+            ("file2", "file10", Ordering::Less),
+            ("007", "7", Ordering::Less),
+            ("a01", "a1", Ordering::Less),
+            ("a01", "a2", Ordering::Less),
+            (
+                "99999999999999999999999",
+                "100000000000000000000000",
+                Ordering::Less,
+            ),
+            ("éclair", "zebra", Ordering::Less),
+            ("éclair", "eclairs", Ordering::Less),
+            ("École", "ecole", Ordering::Greater),
+            ("straße", "strasse", Ordering::Greater),
+            ("Œuvre", "ouvrage", Ordering::Less),
+            ("apple", "Banana", Ordering::Less),
+            ("Apple", "apple", Ordering::Less),
+            ("B", "a", Ordering::Greater),
+            ("1", "a", Ordering::Less),
+            ("", "a", Ordering::Less),
+            ("api", "api", Ordering::Equal),
+        ];
+        for (a, b, expected) in &cases {
+            assert_eq!(*expected, natural_cmp(a, b), "{} <=> {}", a, b);
+            assert_eq!(expected.reverse(), natural_cmp(b, a), "{} <=> {}", b, a);
+        }
+    }
+
+    #[test]
+    fn test_sort_branches() {
+        let mut branches = vec!["release/10", "release/9", "Release/8", "release/11"];
+        branches.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            vec!["Release/8", "release/9", "release/10", "release/11"],
+            branches
+        );
+    }
+
+    #[test]
+    fn test_entry_orders() {
+        use crate::changelog::commit_metadata;
+
+        let entries: Vec<_> = [
+            ("2021-09-01T12:00:00+02:00", "fix(ui): item 10"),
+            ("2021-09-03T12:00:00+02:00", "feat: item 9"),
+            ("2021-09-02T12:00:00+02:00", "docs(api): Item 2"),
+        ]
+        .iter()
+        .map(|(date, raw_message)| {
+            ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                commit_metadata(date),
+                raw_message.parse().unwrap(),
+            )
+        })
+        .collect();
+        let sorted = |order: EntryOrder| {
+            let mut entries = entries.clone();
+            entries.sort_by(order.comparator());
+            entries
+                .iter()
+                .map(|entry| entry.as_ref().headline())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["feat: item 9", "docs(api): Item 2", "fix(ui): item 10"],
+            sorted(EntryOrder::Date)
+        );
+        assert_eq!(
+            vec!["docs(api): Item 2", "feat: item 9", "fix(ui): item 10"],
+            sorted(EntryOrder::Type)
+        );
+        assert_eq!(
+            vec!["docs(api): Item 2", "feat: item 9", "fix(ui): item 10"],
+            sorted(EntryOrder::Summary)
+        );
+        assert_eq!(
+            vec!["feat: item 9", "docs(api): Item 2", "fix(ui): item 10"],
+            sorted(EntryOrder::Scope)
+        );
+    }
+}
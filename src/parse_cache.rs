@@ -0,0 +1,269 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use blake3::hash;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::RunContext,
+    error::Result,
+    message::{ConventionalMessage, ParseOptions, Rule},
+    utils::get_cache_folder,
+};
+
+/// Name of the parse caches' folder, in the user's cache folder of résumé
+pub const PARSE_CACHE_FOLDER_NAME: &str = "messages";
+
+/// Get the path of the repository's parse cache, named after its git folder
+pub fn get_parse_cache_file(repository: &Repository) -> PathBuf {
+    let git_folder = repository
+        .path()
+        .canonicalize()
+        .unwrap_or_else(|_| repository.path().to_path_buf());
+    let name = hash(git_folder.to_string_lossy().as_bytes()).to_string();
+    get_cache_folder()
+        .join(PARSE_CACHE_FOLDER_NAME)
+        .join(name + ".json")
+}
+
+/// Get the version of the parser, to discard the messages parsed by another one or with other
+/// options
+fn parser_version(options: &ParseOptions) -> String {
+    let grammar = include_str!("conventional_message.pest");
+    format!(
+        "{}+{}+{}{}",
+        env!("CARGO_PKG_VERSION"),
+        blake3::hash(grammar.as_bytes()).to_hex(),
+        options.comment_char,
+        if options.lenient { "+lenient" } else { "" }
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: String,
+    /// Parsed messages by commit id, `None` for the non-conventional ones
+    messages: HashMap<String, Option<ConventionalMessage>>,
+}
+
+/// Parsed messages of a repository's commits, kept across runs to skip parsing the
+/// unchanged history again
+pub struct ParseCache {
+    path: PathBuf,
+    messages: RefCell<HashMap<String, Option<ConventionalMessage>>>,
+    /// Messages were parsed since the cache was loaded
+    changed: Cell<bool>,
+    options: ParseOptions,
+}
+
+impl ParseCache {
+    /// Load the cache of the repository, empty if missing, unreadable or written by another
+    /// version of the parser or with other options
+    pub fn load(repository: &Repository, options: ParseOptions) -> Self {
+        Self::load_file(get_parse_cache_file(repository), options)
+    }
+
+    /// Load the cache from the given file, like `load`
+    pub fn load_file(path: PathBuf, options: ParseOptions) -> Self {
+        let messages = match read_cache_file(&path) {
+            Ok(Some(cache)) if cache.version == parser_version(&options) => cache.messages,
+            Ok(_) => HashMap::new(),
+            Err(error) => {
+                log::warn!(
+                    "ignore unreadable parse cache {}: {}",
+                    path.display(),
+                    error.root_cause()
+                );
+                HashMap::new()
+            }
+        };
+        Self {
+            path,
+            messages: RefCell::new(messages),
+            changed: Cell::new(false),
+            options,
+        }
+    }
+
+    /// Parse the commit's message, unless it's already cached. Cached non-conventional
+    /// messages are parsed again to report their error.
+    pub fn parse(
+        &self,
+        id: Oid,
+        raw_message: &str,
+    ) -> std::result::Result<ConventionalMessage, pest::error::Error<Rule>> {
+        let key = id.to_string();
+        if let Some(Some(message)) = self.messages.borrow().get(&key) {
+            return Ok(message.clone());
+        }
+        let parsed = ConventionalMessage::parse_with(raw_message, &self.options);
+        let previous = self
+            .messages
+            .borrow_mut()
+            .insert(key, parsed.as_ref().ok().cloned());
+        if previous.is_none() {
+            self.changed.set(true);
+        }
+        parsed
+    }
+
+    /// Write the cache if messages were parsed, dropping the messages of the commits no
+    /// reference reaches anymore, like the rewritten ones
+    pub fn save(&self, repository: &Repository, context: &RunContext) -> Result<()> {
+        if !self.changed.get() {
+            return Ok(());
+        }
+        let reachable = reachable_commits(repository)?;
+        let mut messages = self.messages.borrow_mut();
+        messages.retain(|id, _| matches!(Oid::from_str(id), Ok(id) if reachable.contains(&id)));
+        if let Some(folder) = self.path.parent() {
+            context.create_dir_all(folder)?;
+        }
+        let cache = CacheFile {
+            version: parser_version(&self.options),
+            messages: std::mem::take(&mut *messages),
+        };
+        let result = context.write(&self.path, serde_json::to_vec(&cache)?);
+        *messages = cache.messages;
+        self.changed.set(false);
+        result
+    }
+
+    /// Number of cached messages, conventional or not
+    pub fn len(&self) -> usize {
+        self.messages.borrow().len()
+    }
+
+    /// The cache has no message
+    pub fn is_empty(&self) -> bool {
+        self.messages.borrow().is_empty()
+    }
+}
+
+/// List the commits reachable from the references of the repository
+fn reachable_commits(repository: &Repository) -> Result<HashSet<Oid>> {
+    let mut walker = repository.revwalk()?;
+    walker.push_glob("*")?;
+    Ok(walker.collect::<std::result::Result<_, _>>()?)
+}
+
+fn read_cache_file(path: &Path) -> Result<Option<CacheFile>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let reader = BufReader::new(File::open(path)?);
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::init_repository;
+
+    /// Open the repository with its parse cache file, and the ids of its commits, oldest first
+    fn open_repository(path: &Path) -> (Repository, PathBuf, Vec<Oid>) {
+        let repository = Repository::open(path).unwrap();
+        let mut walker = repository.revwalk().unwrap();
+        walker.push_glob("*").unwrap();
+        walker.set_sorting(git2::Sort::REVERSE).unwrap();
+        let ids = walker.map(|id| id.unwrap()).collect();
+        (repository, path.join("resume-messages.json"), ids)
+    }
+
+    #[test]
+    fn test_reuse_parsed_messages() {
+        let path = init_repository("parse-cache", &["feat: login", "WIP"]);
+        let (repository, file, ids) = open_repository(&path);
+        let cache = ParseCache::load_file(file.clone(), ParseOptions::default());
+        assert!(cache.is_empty());
+        assert!(cache.parse(ids[0], "feat: login").is_ok());
+        assert!(cache.parse(ids[1], "WIP").is_err());
+        cache.save(&repository, &RunContext::default()).unwrap();
+
+        let cache = ParseCache::load_file(file, ParseOptions::default());
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(2, cache.len());
+        // cached messages aren't parsed again
+        assert_eq!("login", cache.parse(ids[0], "WIP").unwrap().summary);
+        assert!(cache.parse(ids[1], "WIP").is_err());
+    }
+
+    #[test]
+    fn test_drop_unreachable_commits() {
+        let path = init_repository("parse-cache-unreachable", &["fix: crash"]);
+        let (repository, file, ids) = open_repository(&path);
+        // a commit which exists, but no reference reaches
+        let signature = git2::Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let tree = repository.find_commit(ids[0]).unwrap().tree().unwrap();
+        let unreachable = repository
+            .commit(None, &signature, &signature, "fix: gone", &tree, &[])
+            .unwrap();
+        let cache = ParseCache::load_file(file.clone(), ParseOptions::default());
+        cache.parse(ids[0], "fix: crash").unwrap();
+        cache.parse(unreachable, "fix: gone").unwrap();
+        cache.save(&repository, &RunContext::default()).unwrap();
+
+        let cache = ParseCache::load_file(file, ParseOptions::default());
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(1, cache.len());
+        assert_eq!(
+            "gone",
+            cache.parse(unreachable, "fix: gone").unwrap().summary
+        );
+    }
+
+    #[test]
+    fn test_discard_other_mode() {
+        let path = init_repository("parse-cache-mode", &["feat:login"]);
+        let (repository, file, ids) = open_repository(&path);
+        let cache = ParseCache::load_file(file.clone(), ParseOptions::default());
+        assert!(cache.parse(ids[0], "feat:login").is_err());
+        cache.save(&repository, &RunContext::default()).unwrap();
+
+        let cache = ParseCache::load_file(
+            file,
+            ParseOptions {
+                lenient: true,
+                ..ParseOptions::default()
+            },
+        );
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!("login", cache.parse(ids[0], "feat:login").unwrap().summary);
+    }
+
+    #[test]
+    fn test_discard_other_parser_versions() {
+        let path = init_repository("parse-cache-version", &["feat: login"]);
+        let (_, file, ids) = open_repository(&path);
+        let mut messages = HashMap::new();
+        messages.insert(ids[0].to_string(), None);
+        let cache = CacheFile {
+            version: "0.0.0".to_string(),
+            messages,
+        };
+        std::fs::write(&file, serde_json::to_vec(&cache).unwrap()).unwrap();
+
+        let cache = ParseCache::load_file(file, ParseOptions::default());
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(cache.is_empty());
+        assert!(cache.parse(ids[0], "feat: login").is_ok());
+    }
+
+    #[test]
+    fn test_parse_cache_file() {
+        let path = init_repository("parse-cache-file", &["feat: login"]);
+        let file = get_parse_cache_file(&Repository::open(&path).unwrap());
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(
+            Some(get_cache_folder().join(PARSE_CACHE_FOLDER_NAME).as_path()),
+            file.parent()
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::{
+    changelog::ChangeLogEntry,
+    error::{Error, Result},
+};
+
+/// Length of the periods used to slice a changelog
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PeriodKind {
+    Week,
+    Month,
+}
+
+impl FromStr for PeriodKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(Error::InvalidPeriod(s.to_owned())),
+        }
+    }
+}
+
+impl PeriodKind {
+    /// Get the period containing the given date
+    pub fn period_of(&self, date: NaiveDate) -> Period {
+        let start = match self {
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Self::Month => NaiveDate::from_ymd(date.year(), date.month(), 1),
+        };
+        let next_start = match self {
+            Self::Week => start + Duration::weeks(1),
+            Self::Month if start.month() == 12 => NaiveDate::from_ymd(start.year() + 1, 1, 1),
+            Self::Month => NaiveDate::from_ymd(start.year(), start.month() + 1, 1),
+        };
+        Period {
+            start,
+            end: next_start.pred(),
+        }
+    }
+}
+
+/// Range of days, bounds included
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// Partition the entries into consecutive periods, from the oldest entry's period to the
+/// newest entry's one. Periods without entries are kept.
+pub fn slice_entries(
+    kind: PeriodKind,
+    mut entries: Vec<ChangeLogEntry>,
+) -> Vec<(Period, Vec<ChangeLogEntry>)> {
+    entries.sort_by_key(|entry| entry.date().naive_local().date());
+    let first = match entries.first() {
+        Some(entry) => kind.period_of(entry.date().naive_local().date()),
+        None => return Vec::new(),
+    };
+
+    let mut slices = vec![(first, Vec::new())];
+    for entry in entries {
+        let date = entry.date().naive_local().date();
+        while slices.last().unwrap().0.end < date {
+            let next_start = slices.last().unwrap().0.end.succ();
+            slices.push((kind.period_of(next_start), Vec::new()));
+        }
+        slices.last_mut().unwrap().1.push(entry);
+    }
+    slices
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::commit_metadata;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn entry(datetime: &str) -> ChangeLogEntry {
+        ChangeLogEntry::new(
+            "origin".to_string().into(),
+            "master".to_string().into(),
+            commit_metadata(datetime),
+            "feat: something".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_week_period() {
+        let expected = Period {
+            start: date("2021-08-30"),
+            end: date("2021-09-05"),
+        };
+        assert_eq!(expected, PeriodKind::Week.period_of(date("2021-08-30")));
+        assert_eq!(expected, PeriodKind::Week.period_of(date("2021-09-01")));
+        assert_eq!(expected, PeriodKind::Week.period_of(date("2021-09-05")));
+    }
+
+    #[test]
+    fn test_month_period() {
+        let expected = Period {
+            start: date("2021-12-01"),
+            end: date("2021-12-31"),
+        };
+        assert_eq!(expected, PeriodKind::Month.period_of(date("2021-12-01")));
+        assert_eq!(expected, PeriodKind::Month.period_of(date("2021-12-31")));
+        assert_eq!(
+            date("2024-02-29"),
+            PeriodKind::Month.period_of(date("2024-02-10")).end
+        );
+    }
+
+    #[test]
+    fn test_slice_entries_keeps_empty_periods() {
+        let entries = vec![
+            entry("2021-09-20T09:00:00+02:00"),
+            entry("2021-09-05T23:30:00+02:00"),
+            entry("2021-09-06T00:10:00+02:00"),
+        ];
+
+        let slices = slice_entries(PeriodKind::Week, entries);
+
+        let summary: Vec<_> = slices
+            .iter()
+            .map(|(period, entries)| (period.start, entries.len()))
+            .collect();
+        assert_eq!(
+            vec![
+                (date("2021-08-30"), 1),
+                (date("2021-09-06"), 1),
+                (date("2021-09-13"), 0),
+                (date("2021-09-20"), 1),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn test_slice_no_entries() {
+        assert!(slice_entries(PeriodKind::Month, Vec::new()).is_empty());
+    }
+}
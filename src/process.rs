@@ -0,0 +1,375 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use git2::Oid;
+use rayon::{prelude::*, ThreadPoolBuilder};
+
+use crate::{
+    config::{Configuration, Project as ProjectConfiguration},
+    context::RunContext,
+    credentials::AuthMethod,
+    error::{BranchStep, Error, Result},
+    filter::TeamFilter,
+    links::infer_link_template,
+    message::IssuePatterns,
+    progress::Progress,
+    project::{Project, Sentinels, TraversalOptions},
+    run_report::RunReport,
+    snapshots::{BranchName, RepositoryOrigin, RepositorySnapshot, Snapshot, SnapshotBuilder},
+    timings::{timed, ProjectTimings},
+};
+
+/// Outcome of the processing of the configured projects
+pub struct ProjectsRun {
+    /// Entries of all the projects, with the configured descriptions and labels
+    pub report: RunReport,
+    /// Heads of the traversed branches, to start the next run from
+    pub snapshot: Snapshot,
+    /// Timings and access method of each project, in the configuration's order
+    pub summaries: Vec<ProjectSummary>,
+    /// Errors of the failed projects, when keeping going
+    pub failures: Vec<Error>,
+}
+
+/// How a project's processing went
+pub struct ProjectSummary {
+    /// Name of the project, from the configuration
+    pub name: String,
+    /// Durations of the processing's steps
+    pub timings: ProjectTimings,
+    /// How origin was accessed
+    pub auth_method: AuthMethod,
+}
+
+/// List all the branches of a local repository, in name order
+pub fn list_repository_branches(repository: &str) -> Result<Vec<BranchName>> {
+    Project::from_standalone_repository(repository, &[])?.all_branches()
+}
+
+/// Traverse the given branches of a local repository, each one down to the previous ones
+pub fn process_repository(
+    repository: &str,
+    branches_name: &[BranchName],
+    team_filter: TeamFilter,
+    options: &TraversalOptions,
+) -> Result<RunReport> {
+    process_repository_with_context(
+        repository,
+        branches_name,
+        team_filter,
+        options,
+        &RunContext::default(),
+    )
+}
+
+/// Traverse the branches like `process_repository`, within the given run's capabilities
+pub fn process_repository_with_context(
+    repository: &str,
+    branches_name: &[BranchName],
+    team_filter: TeamFilter,
+    options: &TraversalOptions,
+    context: &RunContext,
+) -> Result<RunReport> {
+    let mut project = Project::from_standalone_repository(repository, branches_name)?;
+    project.team_filter = team_filter;
+    project.options = options.clone();
+    if options.parse_cache {
+        project.load_parse_cache();
+    }
+    project.link_template = project
+        .get_origin()
+        .ok()
+        .and_then(|origin| infer_link_template(&origin));
+    project.branches_name = project.expand_branches(branches_name)?;
+    let mut sentinels = Sentinels::new();
+    let mut run_report = RunReport::default();
+    for branch_name in &project.branches_name {
+        let traversal = project
+            .build_walker(branch_name.as_str(), &sentinels)
+            .and_then(|walker| {
+                project.extract_messages(&"".to_string().into(), branch_name, walker)
+            })
+            .map_err(|error| {
+                error.in_branches(BranchStep::Traverse, std::slice::from_ref(branch_name))
+            })?;
+        sentinels.extend(&traversal.sentinels);
+        run_report.add_traversal(&project.name, branch_name, traversal);
+    }
+    if !context.read_only {
+        project.save_parse_cache(context)?;
+    }
+    Ok(run_report)
+}
+
+/// Clone or open the cached clones of the configured projects, fetch their branches then
+/// traverse them from the heads of the given snapshot, in parallel on the configured number
+/// of `jobs`, one per CPU by default.
+///
+/// `progress` builds the receiver of each project's progress, given the project and its
+/// number of steps.
+///
+/// The first failure fails the run, unless `keep_going` is set: the failures are then
+/// collected, and the failed projects keep their heads of the given snapshot.
+pub fn process_projects<F, P>(
+    config: &Configuration,
+    snapshot: Option<&Snapshot>,
+    options: &TraversalOptions,
+    context: &RunContext,
+    keep_going: bool,
+    progress: F,
+) -> Result<ProjectsRun>
+where
+    F: Fn(&ProjectConfiguration, u64) -> P,
+    P: Progress + Send,
+{
+    let options = &TraversalOptions {
+        type_aliases: config.type_aliases.clone(),
+        issue_patterns: IssuePatterns::new(&config.issue_patterns)?,
+        exclude_author: [&options.exclude_author[..], &config.exclude_authors].concat(),
+        ..options.clone()
+    };
+
+    // all the progresses are built upfront, the projects waiting for a thread being pending
+    let progresses: Vec<_> = config
+        .projects
+        .iter()
+        .map(|cfg_project| {
+            let branches_name = cfg_project.get_branches_name(config.default_branch.as_ref());
+            let branches_count = branches_name.as_ref().map_or(1, Vec::len);
+            progress(cfg_project, 1 + (branches_count as u64) * 2)
+        })
+        .collect();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(config.jobs.map_or(0, NonZeroUsize::get))
+        .build()?;
+    let results: Vec<_> = pool.install(|| {
+        config
+            .projects
+            .par_iter()
+            .zip(progresses)
+            .map(|(cfg_project, progress)| {
+                let branches_name = cfg_project.get_branches_name(config.default_branch.as_ref());
+                process_project(
+                    cfg_project,
+                    config,
+                    branches_name,
+                    &progress,
+                    snapshot,
+                    options,
+                    context,
+                )
+                .map_err(|error| {
+                    progress.abandon_with_message(format!("failed: {}", error.root_cause()));
+                    error.in_project(&cfg_project.name, &cfg_project.origin)
+                })
+            })
+            .collect()
+    });
+
+    let mut builder = SnapshotBuilder::new();
+    let mut run_report = RunReport {
+        key_descriptions: config.key_descriptions.clone(),
+        type_titles: config.type_titles.clone(),
+        type_order: config.type_order.clone(),
+        sections: config.sections.clone(),
+        group_by_overrides: config.get_group_by_overrides(),
+        project_names: config.get_project_names(),
+        ..RunReport::default()
+    };
+    let mut summaries = Vec::new();
+    let mut failures = Vec::new();
+
+    for (cfg_project, result) in config.projects.iter().zip(results) {
+        let outcome = match result {
+            Ok(outcome) => outcome,
+            Err(error) if keep_going => {
+                if let Some(previous) =
+                    snapshot.and_then(|snapshot| snapshot.get(&cfg_project.origin))
+                {
+                    builder.add_repository_snapshot(cfg_project.origin.clone(), previous.clone());
+                }
+                failures.push(error);
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        builder.add_repository_snapshot(outcome.origin, outcome.snapshot);
+        run_report.merge(outcome.report);
+        summaries.push(ProjectSummary {
+            name: cfg_project.name.clone(),
+            timings: outcome.timings,
+            auth_method: outcome.auth_method,
+        });
+    }
+
+    Ok(ProjectsRun {
+        report: run_report,
+        snapshot: builder.build(),
+        summaries,
+        failures,
+    })
+}
+
+/// Result of a project's processing
+struct ProjectOutcome {
+    report: RunReport,
+    origin: RepositoryOrigin,
+    snapshot: RepositorySnapshot,
+    timings: ProjectTimings,
+    /// How origin was accessed
+    auth_method: AuthMethod,
+}
+
+fn process_project(
+    cfg_project: &ProjectConfiguration,
+    config: &Configuration,
+    branches_name: Option<Vec<BranchName>>,
+    progress: &impl Progress,
+    snapshot: Option<&Snapshot>,
+    options: &TraversalOptions,
+    context: &RunContext,
+) -> Result<ProjectOutcome> {
+    let mut timings = ProjectTimings::default();
+    let fetch_policy = config.get_fetch_policy(cfg_project);
+    // local projects are traversed as they are, without fetching
+    let local = cfg_project.path.is_some();
+
+    let mut project = timed(&mut timings.clone, || {
+        let branches_name = branches_name.as_deref().unwrap_or_default();
+        if let Some(path) = &cfg_project.path {
+            progress.set_message(format!("open local repository: {}", path.display()));
+            let mut project =
+                Project::from_standalone_repository(&path.to_string_lossy(), branches_name)?;
+            project.name = cfg_project.name.clone();
+            return Ok(project);
+        }
+        progress.set_message(format!(
+            "try to open cached repository: {}",
+            cfg_project.origin
+        ));
+        if let Ok(project) = Project::from_cache(
+            &cfg_project.name,
+            &cfg_project.origin,
+            branches_name,
+            cfg_project.get_token(),
+        ) {
+            Ok(project)
+        } else {
+            progress.set_message(format!("clone repository: {}", cfg_project.origin));
+            Project::from_remote(
+                &cfg_project.name,
+                &cfg_project.origin,
+                branches_name,
+                cfg_project.get_token(),
+                fetch_policy,
+                context,
+            )
+        }
+    })?;
+    if branches_name.is_none() {
+        let branch_name = if context.read_only || local {
+            project.cached_default_branch()?
+        } else {
+            project.detect_default_branch()?
+        };
+        log::info!(
+            "{}: detected default branch {}",
+            cfg_project.name,
+            branch_name
+        );
+        project.branches_name = vec![branch_name];
+    }
+    project.team_filter = TeamFilter {
+        teams: cfg_project.team.clone(),
+        trailer_key: config.team_trailer_key.clone(),
+        ignore_case: config.team_ignore_case,
+    };
+    project.options = options.clone();
+    project.fetch_policy = fetch_policy;
+    project.link_template = config.get_link_template(cfg_project);
+    if options.parse_cache {
+        project.load_parse_cache();
+    }
+    project
+        .options
+        .exclude_author
+        .extend(cfg_project.exclude_authors.iter().cloned());
+    if let Some(snapshot) = snapshot {
+        project.snapshot = snapshot.get(&cfg_project.origin).cloned();
+    }
+    progress.inc(1);
+
+    // in read-only mode, assume the cached clone is already fetched
+    if !context.read_only && !local {
+        progress.set_message(format!(
+            "fetch {} branch(es): {}",
+            project.branches_name.len(),
+            cfg_project.origin
+        ));
+        timed(&mut timings.fetch, || {
+            project.fetch_branches(&project.branches_name, context)
+        })
+        .map_err(|error| error.in_branches(BranchStep::Fetch, &project.branches_name))?;
+    }
+    let patterns = std::mem::take(&mut project.branches_name);
+    project.branches_name = project
+        .expand_branches(&patterns)
+        .map_err(|error| error.in_branches(BranchStep::Read, &patterns))?;
+    // the fetch pruned the branches deleted from origin, don't read their stale heads
+    if !context.read_only && !local {
+        project.drop_pruned_branches();
+    }
+    let repo_snapshot = project
+        .read_branches(&project.branches_name)
+        .map_err(|error| error.in_branches(BranchStep::Read, &project.branches_name))?;
+    for (branch_name, head) in &repo_snapshot {
+        progress.set_message(format!("fetched branch {}: {}", branch_name, head));
+        progress.inc(1);
+    }
+
+    let report = report_branches(progress, &project, &cfg_project.origin, &mut timings)?;
+    if !context.read_only {
+        project.save_parse_cache(context)?;
+    }
+
+    progress.finish_with_message(timings.summary());
+    Ok(ProjectOutcome {
+        report,
+        origin: cfg_project.origin.clone(),
+        snapshot: repo_snapshot,
+        timings,
+        auth_method: project.auth_method(),
+    })
+}
+
+fn report_branches(
+    progress: &impl Progress,
+    project: &Project,
+    origin: &RepositoryOrigin,
+    timings: &mut ProjectTimings,
+) -> Result<RunReport> {
+    let mut sentinels = Sentinels::new();
+    let mut run_report = RunReport::default();
+    for branch_name in &project.branches_name {
+        progress.set_message(format!("traverse branch {}", branch_name));
+        if let Some(Some(head)) = project
+            .snapshot
+            .as_ref()
+            .map(|snapshot| snapshot.get(branch_name))
+        {
+            sentinels.insert(Oid::from_str(head.as_str())?);
+        }
+        let mut walk = Duration::default();
+        let traversal = timed(&mut walk, || -> Result<_> {
+            let walker = project.build_walker(branch_name.as_str(), &sentinels)?;
+            project.extract_messages(origin, branch_name, walker)
+        })
+        .map_err(|error| {
+            error.in_branches(BranchStep::Traverse, std::slice::from_ref(branch_name))
+        })?;
+        timings.walks.push((branch_name.clone(), walk));
+        sentinels.extend(&traversal.sentinels);
+        run_report.add_traversal(&project.name, branch_name, traversal);
+        progress.inc(1);
+    }
+    Ok(run_report)
+}
@@ -0,0 +1,32 @@
+/// Receiver of the progress of a project's processing, so that callers pick how to display it
+pub trait Progress {
+    /// Describe the current step
+    fn set_message(&self, message: String);
+
+    /// Advance of `delta` steps; a processing takes one step to open the repository then two
+    /// steps per branch, to fetch and to traverse it
+    fn inc(&self, _delta: u64) {}
+
+    /// Describe the end of the processing
+    fn finish_with_message(&self, message: String);
+
+    /// Describe the failure of the processing
+    fn abandon_with_message(&self, message: String);
+}
+
+/// Report the progress as log lines prefixed with the project's name
+pub struct LogProgress(pub String);
+
+impl Progress for LogProgress {
+    fn set_message(&self, message: String) {
+        log::info!("{}: {}", self.0, message);
+    }
+
+    fn finish_with_message(&self, message: String) {
+        log::info!("{}: {}", self.0, message);
+    }
+
+    fn abandon_with_message(&self, message: String) {
+        log::error!("{}: {}", self.0, message);
+    }
+}
@@ -1,13 +1,36 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
 
+use chrono::{DateTime, FixedOffset, TimeZone};
+use clap::Clap;
+use console::Term;
 use git2::{
-    build::RepoBuilder, Branch, BranchType, FetchOptions, Oid, RemoteCallbacks, Repository, Revwalk,
+    build::RepoBuilder, Branch, BranchType, Commit, Cred, CredentialType, Direction, FetchOptions,
+    FetchPrune, Oid, Remote, RemoteCallbacks, Repository, Revwalk, Signature, Time,
 };
-use git2_credentials::{ui4dialoguer::CredentialUI4Dialoguer, CredentialHandler};
+use git2_credentials::{ui4dialoguer::CredentialUI4Dialoguer, CredentialHandler, CredentialUI};
+use pest::error::LineColLocation;
 
 use crate::{
-    error::Result,
-    message::ConventionalMessage,
+    changelog::{ChangeLogEntry, CommitMetadata, TypeAliases},
+    context::RunContext,
+    credentials::{AuthMethod, CredentialEscalation, CredentialStep, NonInteractiveUI},
+    error::{Error, Result, UnparsableCommit},
+    filter::{BranchPattern, PersonFilter, TeamFilter, TypeFilter},
+    links::commit_url,
+    message::{
+        apply_reverts, parse_merge_message, unparsed_message, Author, ConventionalMessage,
+        IssuePatterns, ParseOptions, Rule, DEFAULT_COMMENT_CHAR,
+    },
+    parse_cache::ParseCache,
     snapshots::{BranchName, CommitHash, RepositoryOrigin, RepositorySnapshot},
     utils::get_repo_cache_folder,
 };
@@ -15,91 +38,365 @@ use crate::{
 /// Set of commits to not travers
 pub type Sentinels = HashSet<Oid>;
 
+/// Outcome of a branch's traversal
+pub struct BranchTraversal {
+    /// Entries of the walked commits, in traversal order
+    pub entries: Vec<ChangeLogEntry>,
+    /// Merge commits met, to bound the traversal of the next branches
+    pub sentinels: Sentinels,
+    /// Number of walked commits, whether they made an entry or not
+    pub scanned: usize,
+    /// The traversal stopped at `--max-commits`
+    pub truncated: bool,
+}
+
+/// Delay before the first retry of a failed fetch, doubled before each next one
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How to fetch the branches from origin
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchPolicy {
+    /// Abandon a fetch lasting longer than this
+    pub timeout: Option<Duration>,
+    /// Number of retries of a failed or abandoned fetch
+    pub retries: u32,
+    /// Number of commits to clone and fetch from each branch's head, the whole history if
+    /// missing
+    pub depth: Option<u32>,
+}
+
+impl FetchPolicy {
+    /// Warn that the depth can't be honored by the clone or fetch `operation`: the linked
+    /// libgit2 can't clone nor fetch shallowly, the whole history is transferred instead
+    fn check_depth(&self, project_name: &str, operation: &str) {
+        if let Some(depth) = self.depth {
+            log::warn!(
+                "{}: shallow operations aren't supported by libgit2, {} the whole history \
+                 instead of the last {} commit(s)",
+                project_name,
+                operation,
+                depth
+            );
+        }
+    }
+}
+
+/// Options driving the extraction of the changelog entries from the commits, also the
+/// command line options of the subcommands traversing repositories
+#[derive(Clap, Debug, Clone, Default)]
+pub struct TraversalOptions {
+    /// Drop the reverted commits along with their revert commits
+    #[clap(long)]
+    pub apply_reverts: bool,
+    /// Follow the first parents only and report merge commits (e.g. pull requests)
+    #[clap(long)]
+    pub merges: bool,
+    /// Report the merge commits with a conventional message too (e.g. pull requests merged with
+    /// an edited message), following all the parents unlike `--merges`
+    #[clap(long)]
+    pub include_merges: bool,
+    /// Keep only the commits committed by someone matching one of these patterns, matched like
+    /// the `--author` ones: a pattern with `*` or `?` must match a whole name or email
+    #[clap(long, multiple_occurrences(true))]
+    pub committer: Vec<String>,
+    /// Drop the commits committed by someone matching one of these patterns
+    #[clap(long, multiple_occurrences(true))]
+    pub exclude_committer: Vec<String>,
+    /// Keep only the commits authored by someone matching one of these patterns, like an
+    /// email or a name. Combined with `--team`, the commits must pass both filters.
+    #[clap(long, multiple_occurrences(true))]
+    pub author: Vec<String>,
+    /// Drop the commits authored by someone matching one of these patterns, like `*[bot]`
+    #[clap(long, multiple_occurrences(true))]
+    pub exclude_author: Vec<String>,
+    /// Keep only the commits of these types, once their aliases are resolved
+    #[clap(long, multiple_occurrences(true))]
+    pub include_type: Vec<String>,
+    /// Drop the commits of these types, like `chore`
+    #[clap(long, multiple_occurrences(true))]
+    pub exclude_type: Vec<String>,
+    /// Stop the traversal at the given tag, excluded
+    #[clap(long)]
+    pub since_tag: Option<String>,
+    /// Start the traversal at the given tag instead of the branch's head
+    #[clap(long)]
+    pub until_tag: Option<String>,
+    /// Report the non-conventional commits in an `unparsed` type instead of dropping them
+    #[clap(long)]
+    pub include_unparsed: bool,
+    /// Fail, listing them, if some commits aren't conventional ones
+    #[clap(long)]
+    pub strict: bool,
+    /// Accept the near-miss conventional messages, like `feat:add thing` or `fix : crash.`,
+    /// as their well-formed equivalent
+    #[clap(long)]
+    pub lenient: bool,
+    /// Leave the `BREAKING CHANGE` footers out of the entries' trailers, their description
+    /// being the entries' `breaking_change`
+    #[clap(long)]
+    pub drop_breaking_trailers: bool,
+    /// Character starting the comment lines stripped from the messages, overriding the
+    /// repositories' `core.commentChar` [default: #]
+    #[clap(long)]
+    pub comment_char: Option<char>,
+    /// Stop the traversal of each branch after this number of commits
+    #[clap(long)]
+    pub max_commits: Option<usize>,
+    /// Keep the parsed messages in the cache folder, to skip parsing them again on the next
+    /// runs
+    #[clap(long)]
+    pub parse_cache: bool,
+    /// Canonical commit types by alias
+    #[clap(skip)]
+    pub type_aliases: TypeAliases,
+    /// Patterns of the issue references
+    #[clap(skip)]
+    pub issue_patterns: IssuePatterns,
+}
+
 /// Project groups a repository and info to traverse its history.
 pub struct Project {
+    /// Name of the project, in the entries
     pub name: String,
     repository: Repository,
+    /// Branches to traverse, in order
     pub branches_name: Vec<BranchName>,
-    pub team: Option<String>,
+    /// Teams whose commits are kept
+    pub team_filter: TeamFilter,
+    /// How to extract the entries from the commits
+    pub options: TraversalOptions,
+    /// How to fetch the branches from origin
+    pub fetch_policy: FetchPolicy,
+    /// Heads of the previous run, where to stop the traversals
     pub snapshot: Option<RepositorySnapshot>,
+    /// Parsed messages of the previous runs, with `--parse-cache`
+    parse_cache: Option<ParseCache>,
+    /// Template of the links to the commits, with `{hash}` and `{short_hash}` placeholders
+    pub link_template: Option<String>,
+    /// Local branches for standalone repositories, remote-tracking ones for cached clones
+    branch_type: BranchType,
+    credentials: Arc<Mutex<CredentialEscalation>>,
 }
 
 impl Project {
-    /// Build a Project from a repository from the file system
+    /// Build a Project from a repository from the file system, a working tree or a bare one.
+    /// It's named after its folder, without the `.git` suffix of the bare ones.
     pub fn from_standalone_repository(path: &str, branches_name: &[BranchName]) -> Result<Self> {
         let path = PathBuf::from(path).canonicalize()?;
-        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
-        let repository = Repository::open(path)?;
-        Ok(Self {
+        let name = standalone_name(&path);
+        let repository = Repository::open(&path)
+            .or_else(|error| Repository::open_bare(&path).map_err(|_| error))?;
+        Ok(Self::new(
             name,
             repository,
-            branches_name: branches_name.to_vec(),
-            team: None,
-            snapshot: None,
-        })
+            branches_name,
+            BranchType::Local,
+            CredentialEscalation::new(""),
+        ))
     }
 
-    /// Build a Project from a cached clone
+    /// Build a Project from a cached clone, fetched with the given token if it's an HTTP(S) one
     pub fn from_cache(
         name: &str,
         origin: &RepositoryOrigin,
         branches_name: &[BranchName],
+        token: Option<String>,
     ) -> Result<Self> {
         let path = get_repo_cache_folder(origin);
         let repo = Repository::open(path)?;
-        Ok(Self {
-            name: name.to_string(),
-            repository: repo,
-            branches_name: branches_name.to_vec(),
-            team: None,
-            snapshot: None,
-        })
+        Ok(Self::new(
+            name.to_string(),
+            repo,
+            branches_name,
+            BranchType::Remote,
+            CredentialEscalation::new(origin.as_str()).with_token(token),
+        ))
     }
 
-    /// Clone the repository from the given origin then build a Project
+    /// Clone the repository from the given origin, with the given token if it's an HTTP(S)
+    /// one, then build a Project fetching it with the given policy
     pub fn from_remote(
         name: &str,
         origin: &RepositoryOrigin,
         branches_name: &[BranchName],
+        token: Option<String>,
+        fetch_policy: FetchPolicy,
+        context: &RunContext,
     ) -> Result<Self> {
         let path = get_repo_cache_folder(origin);
+        context.check_write(&path)?;
+        fetch_policy.check_depth(name, "cloning");
 
+        let escalation = CredentialEscalation::new(origin.as_str()).with_token(token);
+        let credentials = Arc::new(Mutex::new(escalation.clone()));
         let repo = RepoBuilder::new()
-            .fetch_options(Self::default_fetch_options())
+            .fetch_options(Self::default_fetch_options(Self::default_callbacks(
+                &credentials,
+            )))
             .bare(true)
             .clone(origin.as_str(), path.as_ref())?;
 
-        Ok(Self {
-            name: name.to_string(),
-            repository: repo,
+        let mut project = Self::new(
+            name.to_string(),
+            repo,
+            branches_name,
+            BranchType::Remote,
+            escalation,
+        );
+        project.credentials = credentials;
+        project.fetch_policy = fetch_policy;
+        Ok(project)
+    }
+
+    fn new(
+        name: String,
+        repository: Repository,
+        branches_name: &[BranchName],
+        branch_type: BranchType,
+        credentials: CredentialEscalation,
+    ) -> Self {
+        Self {
+            name,
+            repository,
             branches_name: branches_name.to_vec(),
-            team: None,
+            team_filter: TeamFilter::default(),
+            options: TraversalOptions::default(),
+            fetch_policy: FetchPolicy::default(),
             snapshot: None,
-        })
+            parse_cache: None,
+            link_template: None,
+            branch_type,
+            credentials: Arc::new(Mutex::new(credentials)),
+        }
     }
 
-    /// Build default `FetchOptions`, with credentials' callback, etc
-    fn default_fetch_options() -> FetchOptions<'static> {
+    /// Build default `RemoteCallbacks`, with credentials' callback escalating from anonymous
+    /// access to the token, then to actual credentials. These are only prompted for when
+    /// stderr is a terminal.
+    fn default_callbacks(
+        credentials: &Arc<Mutex<CredentialEscalation>>,
+    ) -> RemoteCallbacks<'static> {
         let mut callbacks = RemoteCallbacks::new();
-        let git_config = git2::Config::open_default().unwrap();
-        let mut ch =
-            CredentialHandler::new_with_ui(git_config, Box::new(CredentialUI4Dialoguer {}));
+        // without git's configuration, there are no credential helpers to escalate to
+        let mut ch = match git2::Config::open_default() {
+            Ok(git_config) => {
+                let ui: Box<dyn CredentialUI> = if Term::stderr().is_term() {
+                    Box::new(CredentialUI4Dialoguer {})
+                } else {
+                    Box::new(NonInteractiveUI)
+                };
+                Some(CredentialHandler::new_with_ui(git_config, ui))
+            }
+            Err(error) => {
+                log::warn!(
+                    "can't read git's configuration, no credential helper: {}",
+                    error
+                );
+                None
+            }
+        };
+        let credentials = Arc::clone(credentials);
         callbacks.credentials(move |url, username_from_url, allowed_types| {
-            ch.try_next_credential(url, username_from_url, allowed_types)
+            let mut escalation = credentials.lock().unwrap();
+            match escalation.next_step(allowed_types) {
+                CredentialStep::Anonymous if allowed_types.contains(CredentialType::DEFAULT) => {
+                    Cred::default()
+                }
+                CredentialStep::Anonymous => Cred::userpass_plaintext("", ""),
+                CredentialStep::Token => Cred::userpass_plaintext(
+                    username_from_url.unwrap_or("x-access-token"),
+                    escalation.token().unwrap_or_default(),
+                ),
+                CredentialStep::Escalate => match ch.as_mut() {
+                    Some(ch) => ch.try_next_credential(url, username_from_url, allowed_types),
+                    None => Err(git2::Error::from_str(
+                        "no credential helper, git's configuration can't be read",
+                    )),
+                },
+            }
         });
+        callbacks
+    }
 
+    /// Build default `FetchOptions` from the given callbacks, pruning the remote-tracking
+    /// branches deleted from origin, etc
+    fn default_fetch_options(callbacks: RemoteCallbacks) -> FetchOptions {
         let mut fetch_option = FetchOptions::new();
         fetch_option.remote_callbacks(callbacks);
+        fetch_option.prune(FetchPrune::On);
         fetch_option
     }
 
-    /// Get the `Branch` object from the given branch name
-    fn get_branch(&self, branch_name: &str) -> Result<Branch> {
-        Ok(self
-            .repository
-            .find_branch(branch_name, BranchType::Local)?)
+    /// Get the method used by the last successful access to origin
+    pub fn auth_method(&self) -> AuthMethod {
+        self.credentials.lock().unwrap().method()
+    }
+
+    /// List the branches of origin, in name order, without cloning nor fetching it
+    pub fn list_origin_branches(
+        origin: &RepositoryOrigin,
+        token: Option<String>,
+    ) -> Result<Vec<BranchName>> {
+        let escalation = CredentialEscalation::new(origin.as_str()).with_token(token);
+        let credentials = Arc::new(Mutex::new(escalation));
+        let mut remote = Remote::create_detached(origin.as_str())?;
+        remote.connect_auth(
+            Direction::Fetch,
+            Some(Self::default_callbacks(&credentials)),
+            None,
+        )?;
+        let mut branches_name: Vec<BranchName> = remote
+            .list()?
+            .iter()
+            .filter_map(|head| head.name().strip_prefix("refs/heads/"))
+            .map(|name| name.to_string().into())
+            .collect();
+        remote.disconnect()?;
+        branches_name.sort();
+        Ok(branches_name)
+    }
+
+    /// Ask origin which branch its HEAD points to
+    pub fn detect_default_branch(&self) -> Result<BranchName> {
+        let mut remote = self.repository.find_remote("origin")?;
+        remote.connect_auth(
+            Direction::Fetch,
+            Some(Self::default_callbacks(&self.credentials)),
+            None,
+        )?;
+        let head = remote.default_branch();
+        remote.disconnect()?;
+        head?
+            .as_str()
+            .and_then(|head| head.strip_prefix("refs/heads/"))
+            .map(|name| name.to_string().into())
+            .ok_or_else(|| Error::UnknownDefaultBranch(self.name.clone()))
+    }
+
+    /// Get the branch the `HEAD` of the cached clone points to, which is origin's
+    /// default branch at clone time
+    pub fn cached_default_branch(&self) -> Result<BranchName> {
+        self.repository
+            .find_reference("HEAD")?
+            .symbolic_target()
+            .and_then(|head| head.strip_prefix("refs/heads/"))
+            .map(|name| name.to_string().into())
+            .ok_or_else(|| Error::UnknownDefaultBranch(self.name.clone()))
     }
 
+    /// Get the `Branch` object from the given branch name, resolved as a remote-tracking
+    /// branch of origin for cached clones
+    fn get_branch(&self, branch_name: &str) -> Result<Branch<'_>> {
+        let branch = match self.branch_type {
+            BranchType::Local => self.repository.find_branch(branch_name, BranchType::Local),
+            BranchType::Remote => self
+                .repository
+                .find_branch(&format!("origin/{}", branch_name), BranchType::Remote),
+        };
+        Ok(branch?)
+    }
+
+    /// Get the URL of the repository's `origin` remote
     pub fn get_origin(&self) -> Result<RepositoryOrigin> {
         Ok(RepositoryOrigin::from(
             self.repository
@@ -108,70 +405,1127 @@ impl Project {
         ))
     }
 
-    /// Get the `Branch` object from the given branch name. Create the branche if needed.
-    fn get_or_create_branch(&self, branch_name: &BranchName) -> Result<Branch> {
-        match self
-            .repository
-            .find_branch(branch_name.as_str(), BranchType::Local)
-        {
-            Ok(branch) => Ok(branch),
-            Err(_) => Ok(self.repository.branch(
-                branch_name.as_str(),
-                &self.repository.head()?.peel_to_commit()?,
-                false,
-            )?),
+    /// Fetch the branches from origin in a single request, into their remote-tracking
+    /// branches. Branch patterns fetch the matching branches, or all of them when the
+    /// pattern isn't a valid refspec, like regular expressions. Failed fetches are retried
+    /// following the fetch policy, unless a timed out fetch can't be stopped.
+    pub fn fetch_branches(&self, branches_name: &[BranchName], context: &RunContext) -> Result<()> {
+        context.check_write(self.repository.path())?;
+        let refspec = |name: &str| format!("+refs/heads/{0}:refs/remotes/origin/{0}", name);
+        let refspecs = branches_name
+            .iter()
+            .map(|branch_name| {
+                Ok(match BranchPattern::parse(branch_name.as_str())? {
+                    None => refspec(branch_name.as_str()),
+                    // refspecs only accept a single `*` wildcard
+                    Some(BranchPattern::Glob(glob))
+                        if !glob.contains('?') && glob.matches('*').count() == 1 =>
+                    {
+                        refspec(&glob)
+                    }
+                    Some(_) => refspec("*"),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.fetch_policy.check_depth(&self.name, "fetching");
+        let mut delay = RETRY_DELAY;
+        for _ in 0..self.fetch_policy.retries {
+            match self.fetch_refspecs(&refspecs) {
+                Err(error @ Error::FetchStalled(_)) => return Err(error),
+                Err(error) => {
+                    log::warn!(
+                        "{}: fetch failed, retry in {:?}: {}",
+                        self.name,
+                        delay,
+                        error
+                    );
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+                ok => return ok,
+            }
         }
+        self.fetch_refspecs(&refspecs)
     }
 
-    /// Fetch the branch from origin and return the pointed commit ID
-    pub fn fetch_branch(&self, branch_name: &BranchName) -> Result<CommitHash> {
-        let mut remote = self.repository.find_remote("origin")?;
-        let branch = self.get_or_create_branch(branch_name)?;
-        remote.fetch(
-            &[&format!("refs/heads/{0}:refs/heads/{0}", branch_name)],
-            Some(&mut Self::default_fetch_options()),
-            None,
-        )?;
-        Ok(branch.get().target().unwrap().into())
+    /// Fetch the refspecs from origin, abandoning the fetch after the policy's timeout
+    fn fetch_refspecs(&self, refspecs: &[String]) -> Result<()> {
+        let timeout = match self.fetch_policy.timeout {
+            Some(timeout) => timeout,
+            None => {
+                let mut remote = self.repository.find_remote("origin")?;
+                let callbacks = Self::default_callbacks(&self.credentials);
+                let mut options = Self::default_fetch_options(callbacks);
+                return Ok(remote.fetch(refspecs, Some(&mut options), None)?);
+            }
+        };
+
+        // fetch from a thread of its own, cancelled on timeout as soon as it makes progress:
+        // libgit2 has no connection timeout
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let path = self.repository.path().to_path_buf();
+        let credentials = Arc::clone(&self.credentials);
+        let refspecs = refspecs.to_vec();
+        let cancel = Arc::clone(&cancelled);
+        let fetch = thread::spawn(move || {
+            let fetch = || -> std::result::Result<(), git2::Error> {
+                let repository = Repository::open(&path)?;
+                let mut remote = repository.find_remote("origin")?;
+                let mut callbacks = Self::default_callbacks(&credentials);
+                callbacks.transfer_progress(move |_| !cancel.load(Ordering::Relaxed));
+                let mut options = Self::default_fetch_options(callbacks);
+                remote.fetch(&refspecs, Some(&mut options), None)
+            };
+            let _ = tx.send(fetch());
+        });
+        match rx.recv_timeout(timeout) {
+            Ok(result) => Ok(result?),
+            Err(_) => {
+                cancelled.store(true, Ordering::Relaxed);
+                // give the fetch as long again to stop, a retry must not run alongside it
+                match rx.recv_timeout(timeout) {
+                    Ok(_) => {
+                        let _ = fetch.join();
+                        Err(Error::FetchTimeout(timeout))
+                    }
+                    Err(_) => Err(Error::FetchStalled(timeout)),
+                }
+            }
+        }
+    }
+
+    /// List the branches, local ones for standalone repositories and remote-tracking ones for
+    /// cached clones, in name order
+    pub fn list_branches(&self) -> Result<Vec<BranchName>> {
+        let mut branches_name = Vec::new();
+        for branch in self.repository.branches(Some(self.branch_type))? {
+            let (branch, _) = branch?;
+            let name = match (branch.name()?, self.branch_type) {
+                (Some(name), BranchType::Remote) => name.strip_prefix("origin/"),
+                (name, _) => name,
+            };
+            match name {
+                Some(name) if name != "HEAD" => branches_name.push(name.to_string().into()),
+                _ => {}
+            }
+        }
+        branches_name.sort();
+        Ok(branches_name)
+    }
+
+    /// List all the branches, failing if there are none, like in a repository whose only
+    /// commits are reachable from a detached `HEAD`
+    pub fn all_branches(&self) -> Result<Vec<BranchName>> {
+        let branches_name = self.list_branches()?;
+        if branches_name.is_empty() {
+            return Err(Error::NoBranch(self.name.clone()));
+        }
+        Ok(branches_name)
+    }
+
+    /// Replace the branch patterns, with `*` or `?` wildcards or `regex:` prefixed, by the
+    /// branches matching them, in name order. The patterns matching no branch are dropped
+    /// with a warning.
+    pub fn expand_branches(&self, branches_name: &[BranchName]) -> Result<Vec<BranchName>> {
+        let mut expanded = Vec::new();
+        let mut existing = None;
+        for branch_name in branches_name {
+            let pattern = match BranchPattern::parse(branch_name.as_str())? {
+                Some(pattern) => pattern,
+                None => {
+                    if !expanded.contains(branch_name) {
+                        expanded.push(branch_name.clone());
+                    }
+                    continue;
+                }
+            };
+            if existing.is_none() {
+                existing = Some(self.list_branches()?);
+            }
+            let matching: Vec<_> = existing
+                .iter()
+                .flatten()
+                .filter(|name| pattern.matches(name.as_str()))
+                .cloned()
+                .collect();
+            if matching.is_empty() {
+                log::warn!("{}: no branch matches {}", self.name, branch_name);
+            }
+            for branch_name in matching {
+                if !expanded.contains(&branch_name) {
+                    expanded.push(branch_name);
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Remove the branches deleted from origin, pruned from the cached clone by the last fetch,
+    /// with a warning naming each of them
+    pub fn drop_pruned_branches(&mut self) {
+        let name = &self.name;
+        let repository = &self.repository;
+        self.branches_name.retain(|branch_name| {
+            let reference = format!("refs/remotes/origin/{}", branch_name);
+            let exists = repository.find_reference(&reference).is_ok();
+            if !exists {
+                log::warn!(
+                    "{}: branch {} doesn't exist on origin anymore, skip it",
+                    name,
+                    branch_name
+                );
+            }
+            exists
+        });
+    }
+
+    /// Get the commit IDs the branches point to, without fetching them
+    pub fn read_branches(&self, branches_name: &[BranchName]) -> Result<RepositorySnapshot> {
+        let mut snapshot = RepositorySnapshot::new();
+        for branch_name in branches_name {
+            let branch = self.get_branch(branch_name.as_str())?;
+            let head: CommitHash = branch.get().target().unwrap().into();
+            snapshot.insert(branch_name.clone(), head);
+        }
+        Ok(snapshot)
     }
 
     /// Build a commits walker. Its path is bound by the `sentinels` set of commits.
-    pub fn build_walker(&self, branch_name: &str, sentinels: &Sentinels) -> Result<Revwalk> {
-        let branch = self.get_branch(branch_name)?;
+    pub fn build_walker(&self, branch_name: &str, sentinels: &Sentinels) -> Result<Revwalk<'_>> {
+        log::info!("{}: build walker for branch {}", self.name, branch_name);
         let mut walker = self.repository.revwalk()?;
-        walker.push(branch.get().target().expect("Branch must point somewhere"))?;
+        if self.options.merges {
+            walker.simplify_first_parent()?;
+        }
+        match &self.options.until_tag {
+            Some(tag) => walker.push(self.resolve_tag(tag)?)?,
+            None => {
+                let branch = self.get_branch(branch_name)?;
+                walker.push(branch.get().target().expect("Branch must point somewhere"))?
+            }
+        }
+        if let Some(tag) = &self.options.since_tag {
+            walker.hide(self.resolve_tag(tag)?)?;
+        }
         for oid in sentinels {
-            walker.hide(*oid).unwrap();
+            walker.hide(*oid).map_err(|_| {
+                let origin = self
+                    .get_origin()
+                    .unwrap_or_else(|_| self.name.clone().into());
+                let branch_name = branch_name.to_owned().into();
+                // the snapshot's head may be older than the shallow history
+                if self.repository.is_shallow() {
+                    Error::ShallowSentinel(origin, branch_name, (*oid).into())
+                } else {
+                    Error::UnreachableSentinel(origin, branch_name, (*oid).into())
+                }
+            })?;
         }
         Ok(walker)
     }
 
-    pub fn extract_messages(&self, walker: Revwalk) -> (Vec<ConventionalMessage>, Sentinels) {
+    /// Get the commits whose parents are missing from a shallow repository, none for a
+    /// complete one
+    fn shallow_boundaries(&self) -> Result<Sentinels> {
+        let path = self.repository.path().join("shallow");
+        if !self.repository.is_shallow() || !path.exists() {
+            return Ok(Sentinels::new());
+        }
+        let mut boundaries = Sentinels::new();
+        for line in std::fs::read_to_string(path)?.lines() {
+            boundaries.insert(Oid::from_str(line.trim())?);
+        }
+        Ok(boundaries)
+    }
+
+    /// Get the commit pointed by the given tag, lightweight or annotated
+    pub fn resolve_tag(&self, tag: &str) -> Result<Oid> {
+        self.repository
+            .revparse_single(&format!("refs/tags/{}", tag))
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|_| Error::TagNotFound(tag.to_owned()))
+    }
+
+    /// Get the options of the messages' parsing. The comment character is the given one, else
+    /// the repository's `core.commentChar`.
+    fn parse_options(&self) -> ParseOptions {
+        let configured = || {
+            let comment_char = self
+                .repository
+                .config()
+                .and_then(|config| config.get_string("core.commentChar"))
+                .ok()?;
+            let mut chars = comment_char.chars();
+            match (chars.next(), chars.next()) {
+                (Some(comment_char), None) => Some(comment_char),
+                _ => None,
+            }
+        };
+        ParseOptions {
+            lenient: self.options.lenient,
+            comment_char: self
+                .options
+                .comment_char
+                .or_else(configured)
+                .unwrap_or(DEFAULT_COMMENT_CHAR),
+        }
+    }
+
+    /// Load the messages parsed by the previous runs, to skip parsing them again
+    pub fn load_parse_cache(&mut self) {
+        let cache = ParseCache::load(&self.repository, self.parse_options());
+        log::info!("{}: {} cached parsed message(s)", self.name, cache.len());
+        self.parse_cache = Some(cache);
+    }
+
+    /// Save the parsed messages for the next runs, if the parse cache is loaded
+    pub fn save_parse_cache(&self, context: &RunContext) -> Result<()> {
+        match &self.parse_cache {
+            Some(cache) => cache.save(&self.repository, context),
+            None => Ok(()),
+        }
+    }
+
+    /// Extract the changelog entries of the walked commits, along with the merge commits met.
+    /// Merge commits make entries only with `merges` or `include_merges`.
+    pub fn extract_messages(
+        &self,
+        origin: &RepositoryOrigin,
+        branch_name: &BranchName,
+        walker: Revwalk,
+    ) -> Result<BranchTraversal> {
         let mut messages = Vec::new();
         let mut new_sentinels = Sentinels::new();
+        let mut unparsable = Vec::new();
+        let mut scanned = 0;
+        let mut truncated = false;
+        let boundaries = self.shallow_boundaries()?;
+        let parse_options = self.parse_options();
 
         for object in walker {
-            let commit = self.repository.find_commit(object.unwrap()).unwrap();
-            if commit.parent_count() > 1 {
+            if Some(scanned) == self.options.max_commits {
+                log::warn!(
+                    "{}: traversal of branch {} truncated after {} commits",
+                    self.name,
+                    branch_name,
+                    scanned
+                );
+                truncated = true;
+                break;
+            }
+            let commit = self.repository.find_commit(object?)?;
+            scanned += 1;
+            let is_merge = commit.parent_count() > 1;
+            if is_merge {
                 new_sentinels.insert(commit.id());
             }
-            if let Some(raw_message) = commit.message() {
-                if let Ok(message) = raw_message.parse::<ConventionalMessage>() {
-                    if let Some(team) = self.team.as_ref() {
-                        if message
-                            .trailers
-                            .iter()
-                            .any(|(key, value)| key == "team" && value == team)
-                        {
-                            messages.push(message)
-                        }
+            let reported = !is_merge || self.options.merges || self.options.include_merges;
+            if let Some(raw_message) = commit.message().filter(|_| reported) {
+                let merge_message = if self.options.merges && commit.parent_count() > 1 {
+                    parse_merge_message(raw_message)
+                } else {
+                    None
+                };
+                let parsed = match &self.parse_cache {
+                    Some(cache) => cache.parse(commit.id(), raw_message),
+                    None => ConventionalMessage::parse_with(raw_message, &parse_options),
+                };
+                if let Err(error) = &parsed {
+                    // merge commits are generated by git, strict mode doesn't apply to them
+                    if self.options.strict && commit.parent_count() <= 1 {
+                        unparsable.push(to_unparsable(&commit, raw_message, error));
+                    }
+                }
+                let message = merge_message.or_else(|| parsed.ok()).or_else(|| {
+                    if self.options.include_unparsed {
+                        Some(unparsed_message(raw_message))
                     } else {
-                        messages.push(message);
+                        None
+                    }
+                });
+                if let Some(mut message) = message {
+                    message.apply_type_aliases(&self.options.type_aliases);
+                    message.extract_issues(&self.options.issue_patterns);
+                    if self.options.drop_breaking_trailers {
+                        message.drop_breaking_change_trailers();
+                    }
+                    let type_filter = TypeFilter {
+                        include: &self.options.include_type,
+                        exclude: &self.options.exclude_type,
+                    };
+                    if self.team_filter.matches(&message) && type_filter.matches(&message.ctype) {
+                        let metadata = to_metadata(&commit);
+                        if self.accepts(&metadata) {
+                            let url = self.link_template.as_ref().and_then(|template| {
+                                Some(commit_url(template, metadata.hash.as_ref()?))
+                            });
+                            messages.push(
+                                ChangeLogEntry::new(
+                                    origin.to_owned(),
+                                    branch_name.to_owned(),
+                                    metadata,
+                                    message,
+                                )
+                                .with_url(url),
+                            );
+                        }
                     }
                 }
             }
+            // the history goes on beyond the shallow clone, don't pass the boundary silently
+            if boundaries.contains(&commit.id()) {
+                log::warn!(
+                    "{}: traversal of branch {} truncated at the shallow history's commit {}",
+                    self.name,
+                    branch_name,
+                    commit.id()
+                );
+                truncated = true;
+                break;
+            }
+        }
+
+        if !unparsable.is_empty() {
+            return Err(Error::UnparsableCommits(unparsable));
+        }
+        if self.options.apply_reverts {
+            messages = apply_reverts(messages);
+        }
+
+        Ok(BranchTraversal {
+            entries: messages,
+            sentinels: new_sentinels,
+            scanned,
+            truncated,
+        })
+    }
+
+    /// Check the commit's metadata against the people filters
+    fn accepts(&self, metadata: &CommitMetadata) -> bool {
+        let committer_filter = PersonFilter {
+            include: &self.options.committer,
+            exclude: &self.options.exclude_committer,
+        };
+        let author_filter = PersonFilter {
+            include: &self.options.author,
+            exclude: &self.options.exclude_author,
+        };
+        committer_filter.matches(&metadata.committer) && author_filter.matches(&metadata.author)
+    }
+}
+
+fn to_metadata(commit: &Commit) -> CommitMetadata {
+    CommitMetadata {
+        hash: Some(commit.id().into()),
+        date: to_datetime(commit.time()),
+        author: to_author(&commit.author()),
+        committer: to_author(&commit.committer()),
+    }
+}
+
+fn to_unparsable(
+    commit: &Commit,
+    raw_message: &str,
+    error: &pest::error::Error<Rule>,
+) -> UnparsableCommit {
+    let position = match error.line_col {
+        LineColLocation::Pos(position) => position,
+        LineColLocation::Span(start, _) => start,
+    };
+    UnparsableCommit {
+        hash: commit.id().to_string(),
+        headline: raw_message.lines().next().unwrap_or("").to_owned(),
+        position,
+    }
+}
+
+fn to_author(signature: &Signature) -> Author {
+    Author {
+        name: signature.name().unwrap_or("").to_owned(),
+        email: signature.email().map(str::to_owned),
+    }
+}
+
+/// Convert a git time to a date time in its own timezone, in UTC if its offset is out of range
+fn to_datetime(time: Time) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| FixedOffset::east(0))
+        .timestamp(time.seconds(), 0)
+}
+
+/// Name a repository after its canonical path's folder, without the `.git` suffix of the bare
+/// ones. The `.git` folder of a working tree is named after the working tree.
+fn standalone_name(path: &Path) -> String {
+    let path = match path.file_name() {
+        Some(name) if name == ".git" => path.parent().unwrap_or(path),
+        _ => path,
+    };
+    let name = path
+        .file_name()
+        .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+    match name.strip_suffix(".git") {
+        Some(stripped) if !stripped.is_empty() => stripped.to_owned(),
+        _ => name.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        changelog::CommitField,
+        test_utils::{init_repository, init_repository_with_signatures},
+    };
+
+    #[test]
+    fn test_expand_branches() {
+        let path = init_repository("expanded", &["feat: first"]);
+        let repository = Repository::open(&path).unwrap();
+        let head = repository.find_branch("master", BranchType::Local).unwrap();
+        let head = head.get().peel_to_commit().unwrap();
+        for name in &["release/2.0", "release/1.0", "feature/x"] {
+            repository.branch(name, &head, false).unwrap();
+        }
+        let project = Project::from_standalone_repository(path.to_str().unwrap(), &[]).unwrap();
+
+        let patterns: Vec<BranchName> = ["master", "release/*", "hotfix/*", "release/1.0"]
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect();
+        let expanded = project.expand_branches(&patterns).unwrap();
+        let regex_patterns: Vec<BranchName> = [r"regex:release/\d\.0", "regex:feat.*", "master"]
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect();
+        let regex_expanded = project.expand_branches(&regex_patterns).unwrap();
+
+        std::fs::remove_dir_all(&path).unwrap();
+        let names: Vec<_> = expanded.iter().map(BranchName::as_str).collect();
+        assert_eq!(vec!["master", "release/1.0", "release/2.0"], names);
+        let names: Vec<_> = regex_expanded.iter().map(BranchName::as_str).collect();
+        assert_eq!(
+            vec!["release/1.0", "release/2.0", "feature/x", "master"],
+            names
+        );
+    }
+
+    #[test]
+    fn test_all_branches() {
+        let path = init_repository("all", &["feat: first"]);
+        let repository = Repository::open(&path).unwrap();
+        let head = repository.find_branch("master", BranchType::Local).unwrap();
+        let head = head.get().peel_to_commit().unwrap();
+        for name in &["release/1.0", "feature/x"] {
+            repository.branch(name, &head, false).unwrap();
+        }
+        let project = Project::from_standalone_repository(path.to_str().unwrap(), &[]).unwrap();
+        let branches_name = project.all_branches().unwrap();
+        repository.set_head_detached(head.id()).unwrap();
+        for name in &["master", "release/1.0", "feature/x"] {
+            let mut branch = repository.find_branch(name, BranchType::Local).unwrap();
+            branch.delete().unwrap();
+        }
+        let detached = project.all_branches();
+
+        std::fs::remove_dir_all(&path).unwrap();
+        let names: Vec<_> = branches_name.iter().map(BranchName::as_str).collect();
+        assert_eq!(vec!["feature/x", "master", "release/1.0"], names);
+        assert!(matches!(detached, Err(Error::NoBranch(_))));
+    }
+
+    #[test]
+    fn test_sentinel_bounds_walk() {
+        let path = init_repository("bounded", &["feat: first", "fix: second", "feat: third"]);
+        let project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let oids: Vec<_> = walker.map(|oid| oid.unwrap()).collect();
+        let sentinels = vec![oids[1]].into_iter().collect();
+        let walker = project.build_walker("master", &sentinels).unwrap();
+        let entries = project
+            .extract_messages(
+                &"origin".to_string().into(),
+                &"master".parse().unwrap(),
+                walker,
+            )
+            .unwrap()
+            .entries;
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!("third", entries[0].as_ref().summary);
+    }
+
+    #[test]
+    fn test_to_datetime() {
+        let date = to_datetime(Time::new(1_600_000_000, 120));
+        assert_eq!("2020-09-13T14:26:40+02:00", date.to_rfc3339());
+        let date = to_datetime(Time::new(1_600_000_000, 24 * 60));
+        assert_eq!("2020-09-13T12:26:40+00:00", date.to_rfc3339());
+    }
+
+    #[test]
+    fn test_conventional_merge_commit() {
+        let path = init_repository("merged", &["chore: init"]);
+        let repository = Repository::open(&path).unwrap();
+        let signature = Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let base = repository
+            .find_branch("master", BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        let tree = base.tree().unwrap();
+        let feature = repository
+            .commit(None, &signature, &signature, "WIP", &tree, &[&base])
+            .unwrap();
+        let feature = repository.find_commit(feature).unwrap();
+        let merge = repository
+            .commit(
+                Some("refs/heads/master"),
+                &signature,
+                &signature,
+                "feat(api): add endpoint (#12)",
+                &tree,
+                &[&base, &feature],
+            )
+            .unwrap();
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let traverse = |project: &Project| {
+            let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+            project
+                .extract_messages(
+                    &"origin".to_string().into(),
+                    &"master".parse().unwrap(),
+                    walker,
+                )
+                .unwrap()
+        };
+        let summaries = |traversal: &BranchTraversal| -> Vec<String> {
+            traversal
+                .entries
+                .iter()
+                .map(|entry| entry.as_ref().summary.clone())
+                .collect()
+        };
+
+        let traversal = traverse(&project);
+        assert_eq!(vec!["init"], summaries(&traversal));
+        assert!(traversal.sentinels.contains(&merge));
+
+        project.options.include_merges = true;
+        let traversal = traverse(&project);
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(vec!["add endpoint (#12)", "init"], summaries(&traversal));
+        assert!(traversal.sentinels.contains(&merge));
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let path = init_repository("strict", &["feat: first", "WIP", "Fixed it", "fix: last"]);
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let extract = |project: &Project| {
+            let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+            project.extract_messages(
+                &"origin".to_string().into(),
+                &"master".parse().unwrap(),
+                walker,
+            )
+        };
+
+        let traversal = extract(&project).unwrap();
+        assert_eq!(2, traversal.entries.len());
+        assert_eq!(4, traversal.scanned);
+
+        project.options.strict = true;
+        let result = extract(&project);
+        std::fs::remove_dir_all(&path).unwrap();
+        match result {
+            Err(Error::UnparsableCommits(commits)) => {
+                let headlines: Vec<_> = commits.iter().map(|c| c.headline.as_str()).collect();
+                assert_eq!(vec!["Fixed it", "WIP"], headlines);
+                assert_eq!((1, 4), commits[1].position);
+            }
+            _ => panic!("expected unparsable commits"),
+        }
+    }
+
+    #[test]
+    fn test_max_commits() {
+        let path = init_repository(
+            "max-commits",
+            &["feat: first", "fix: second", "WIP", "feat: fourth"],
+        );
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let extract = |project: &Project| {
+            let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+            project
+                .extract_messages(
+                    &"origin".to_string().into(),
+                    &"master".parse().unwrap(),
+                    walker,
+                )
+                .unwrap()
+        };
+
+        // the unparsable commit counts as visited
+        project.options.max_commits = Some(2);
+        let traversal = extract(&project);
+        assert!(traversal.truncated);
+        assert_eq!(2, traversal.scanned);
+        assert_eq!(1, traversal.entries.len());
+        assert_eq!("fourth", traversal.entries[0].as_ref().summary);
+
+        project.options.max_commits = Some(4);
+        let traversal = extract(&project);
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(!traversal.truncated);
+        assert_eq!(3, traversal.entries.len());
+    }
+
+    #[test]
+    fn test_committer_filters() {
+        let jane = Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let bot = Signature::now("Release Bot", "release-bot@example.com").unwrap();
+        let path = init_repository_with_signatures(
+            "committer",
+            &[
+                ("feat: direct", jane.clone(), jane.clone()),
+                ("fix: cherry-picked", jane.clone(), bot),
+            ],
+        );
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let extract = |project: &Project| {
+            let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+            project
+                .extract_messages(
+                    &"origin".to_string().into(),
+                    &"master".parse().unwrap(),
+                    walker,
+                )
+                .unwrap()
+                .entries
+        };
+
+        project.options.committer = vec!["release-bot".to_string()];
+        let committed_by_bot = extract(&project);
+        project.options.committer = vec![];
+        project.options.exclude_committer = vec!["release-bot".to_string()];
+        let not_committed_by_bot = extract(&project);
+
+        project.options.exclude_committer = vec![];
+        project.options.exclude_author = vec!["jane*".to_string()];
+        let not_authored_by_jane = extract(&project);
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(not_authored_by_jane.is_empty());
+        assert_eq!(1, committed_by_bot.len());
+        assert_eq!("cherry-picked", committed_by_bot[0].as_ref().summary);
+        assert_eq!("Jane Doe", committed_by_bot[0].get(&CommitField::Author));
+        assert_eq!(
+            "Release Bot",
+            committed_by_bot[0].get(&CommitField::Committer)
+        );
+        assert_eq!(1, not_committed_by_bot.len());
+        assert_eq!("direct", not_committed_by_bot[0].as_ref().summary);
+    }
+
+    #[test]
+    fn test_tags_bound_walk() {
+        let path = init_repository("tags", &["feat: first", "fix: second", "feat: third"]);
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let oids: Vec<_> = walker.map(|oid| oid.unwrap()).collect();
+        let signature = Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let first = project.repository.find_object(oids[2], None).unwrap();
+        let second = project.repository.find_object(oids[1], None).unwrap();
+        project
+            .repository
+            .tag_lightweight("v1.0.0", &first, false)
+            .unwrap();
+        project
+            .repository
+            .tag("v1.1.0", &second, &signature, "release 1.1.0", false)
+            .unwrap();
+
+        project.options.since_tag = Some("v1.0.0".to_string());
+        project.options.until_tag = Some("v1.1.0".to_string());
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let entries = project
+            .extract_messages(
+                &"origin".to_string().into(),
+                &"master".parse().unwrap(),
+                walker,
+            )
+            .unwrap()
+            .entries;
+
+        project.options.until_tag = Some("v2.0.0".to_string());
+        let missing = project.build_walker("master", &Sentinels::new()).err();
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!("second", entries[0].as_ref().summary);
+        assert!(matches!(missing, Some(Error::TagNotFound(tag)) if tag == "v2.0.0"));
+    }
+
+    #[test]
+    fn test_missing_sentinel() {
+        let path = init_repository("missing", &["feat: first"]);
+        let project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let missing = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let sentinels = vec![missing].into_iter().collect();
+
+        let result = project.build_walker("master", &sentinels);
+
+        std::fs::remove_dir_all(&path).unwrap();
+        match result {
+            Err(Error::UnreachableSentinel(_, branch, hash)) => {
+                assert_eq!("master", branch.as_str());
+                assert_eq!(CommitHash::from(missing), hash);
+            }
+            _ => panic!("missing sentinel must be reported"),
+        }
+    }
+
+    #[test]
+    fn test_shallow_repository() {
+        let path = init_repository("shallow", &["feat: first", "fix: second", "feat: third"]);
+        let repository = Repository::open(&path).unwrap();
+        let mut walker = repository.revwalk().unwrap();
+        walker.push_ref("refs/heads/master").unwrap();
+        let oids: Vec<_> = walker.map(|oid| oid.unwrap()).collect();
+        std::fs::write(path.join(".git/shallow"), format!("{}\n", oids[1])).unwrap();
+        let project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let traversal = project
+            .extract_messages(
+                &"origin".to_string().into(),
+                &"master".parse().unwrap(),
+                walker,
+            )
+            .unwrap();
+        let missing = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let sentinels = vec![missing].into_iter().collect();
+        let result = project.build_walker("master", &sentinels);
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(2, traversal.entries.len());
+        assert!(traversal.truncated);
+        assert!(
+            matches!(result, Err(Error::ShallowSentinel(_, _, hash)) if hash == missing.into())
+        );
+    }
+
+    #[test]
+    fn test_vanished_commit() {
+        let path = init_repository("vanished", &["feat: first", "fix: second", "feat: third"]);
+        let repository = Repository::open(&path).unwrap();
+        let mut walker = repository.revwalk().unwrap();
+        walker.push_ref("refs/heads/master").unwrap();
+        let root = walker.last().unwrap().unwrap().to_string();
+        let object = path.join(".git/objects").join(&root[..2]).join(&root[2..]);
+        std::fs::remove_file(object).unwrap();
+        let project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let result = project.extract_messages(
+            &"origin".to_string().into(),
+            &"master".parse().unwrap(),
+            walker,
+        );
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(matches!(result, Err(Error::Git(_))));
+    }
+
+    #[test]
+    fn test_fetch_into_remote_tracking_branches() {
+        let origin = init_repository("fetched", &["feat: first", "fix: second"]);
+        let path =
+            std::env::temp_dir().join(format!("resume-fetched-clone-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        // the HEAD of a fresh bare repository is unborn
+        let repository = Repository::init_bare(&path).unwrap();
+        repository
+            .remote("origin", origin.to_str().unwrap())
+            .unwrap();
+        let branches_name = ["master".parse().unwrap()];
+        let mut project = Project::new(
+            "fetched".to_string(),
+            repository,
+            &branches_name,
+            BranchType::Remote,
+            CredentialEscalation::new(""),
+        );
+        // unsupported by libgit2: the whole history is fetched anyway
+        project.fetch_policy.depth = Some(1);
+
+        let read_only = RunContext { read_only: true };
+        let result = project.fetch_branches(&branches_name, &read_only);
+        project
+            .fetch_branches(&branches_name, &RunContext::default())
+            .unwrap();
+        let snapshot = project.read_branches(&branches_name).unwrap();
+        let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+        let oids: Vec<_> = walker.map(|oid| oid.unwrap()).collect();
+        let has_local_branch = project
+            .repository
+            .find_branch("master", BranchType::Local)
+            .is_ok();
+
+        std::fs::remove_dir_all(&origin).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(
+            project.repository.path(),
+            crate::context::violated_path(result)
+        );
+        let head: CommitHash = oids[0].into();
+        assert_eq!(Some(&head), snapshot.get(&branches_name[0]));
+        assert_eq!(2, oids.len());
+        assert!(!has_local_branch);
+    }
+
+    #[test]
+    fn test_prune_deleted_branches() {
+        let origin = init_repository("pruned-origin", &["feat: first"]);
+        let origin_repository = Repository::open(&origin).unwrap();
+        let head = origin_repository.head().unwrap().peel_to_commit().unwrap();
+        origin_repository.branch("feature", &head, false).unwrap();
+        let path = std::env::temp_dir().join(format!("resume-pruned-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let repository = Repository::init_bare(&path).unwrap();
+        repository
+            .remote("origin", origin.to_str().unwrap())
+            .unwrap();
+        let branches_name: Vec<BranchName> =
+            vec!["master".parse().unwrap(), "feature".parse().unwrap()];
+        let mut project = Project::new(
+            "pruned".to_string(),
+            repository,
+            &branches_name,
+            BranchType::Remote,
+            CredentialEscalation::new(""),
+        );
+
+        project
+            .fetch_branches(&branches_name, &RunContext::default())
+            .unwrap();
+        let fetched = project
+            .read_branches(&branches_name)
+            .map(|snapshot| snapshot.len());
+        origin_repository
+            .find_branch("feature", BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        project
+            .fetch_branches(&branches_name, &RunContext::default())
+            .unwrap();
+        project.drop_pruned_branches();
+        let snapshot = project.read_branches(&project.branches_name).unwrap();
+
+        std::fs::remove_dir_all(&origin).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(2, fetched.unwrap());
+        assert_eq!(vec![branches_name[0].clone()], project.branches_name);
+        assert_eq!(1, snapshot.len());
+        assert!(snapshot.contains_key(&branches_name[0]));
+    }
+
+    #[test]
+    fn test_fetch_timeout() {
+        // the server accepts the connection but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}/repository.git", listener.local_addr().unwrap());
+        let path = std::env::temp_dir().join(format!("resume-timeout-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let repository = Repository::init_bare(&path).unwrap();
+        repository.remote("origin", &origin).unwrap();
+        let mut project = Project::new(
+            "timeout".to_string(),
+            repository,
+            &[],
+            BranchType::Remote,
+            CredentialEscalation::new(&origin),
+        );
+        project.fetch_policy = FetchPolicy {
+            timeout: Some(Duration::from_millis(200)),
+            retries: 1,
+            depth: None,
+        };
+
+        let result = project.fetch_branches(&["master".parse().unwrap()], &RunContext::default());
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(matches!(result, Err(Error::FetchStalled(_))));
+    }
+
+    #[test]
+    fn test_detect_default_branch() {
+        let origin = init_repository("detected", &["feat: first"]);
+        let origin_repository = Repository::open(&origin).unwrap();
+        let head = origin_repository.head().unwrap().peel_to_commit().unwrap();
+        origin_repository.branch("main", &head, false).unwrap();
+        origin_repository.set_head("refs/heads/main").unwrap();
+        let path = std::env::temp_dir().join(format!("resume-detect-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let repository = Repository::init_bare(&path).unwrap();
+        repository
+            .remote("origin", origin.to_str().unwrap())
+            .unwrap();
+        let project = Project::new(
+            "detected".to_string(),
+            repository,
+            &[],
+            BranchType::Remote,
+            CredentialEscalation::new(""),
+        );
+
+        let branch_name = project.detect_default_branch();
+
+        std::fs::remove_dir_all(&origin).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!("main", branch_name.unwrap().as_str());
+    }
+
+    #[test]
+    fn test_read_only_clone() {
+        let origin: RepositoryOrigin = "file:///nonexistent/repository".to_string().into();
+        let context = RunContext { read_only: true };
+        let result = Project::from_remote(
+            "clone",
+            &origin,
+            &[],
+            None,
+            FetchPolicy::default(),
+            &context,
+        )
+        .map(|_| ());
+        assert_eq!(
+            get_repo_cache_folder(&origin),
+            crate::context::violated_path(result)
+        );
+    }
+
+    #[test]
+    fn test_standalone_repository_name() {
+        let path = init_repository("standalone", &["feat: first"]);
+        let expected = format!("resume-standalone-{}", std::process::id());
+        for path in &[
+            format!("{}/.", path.display()),
+            format!("{}/", path.display()),
+            format!("{}/.git/..", path.display()),
+            format!("{}/.git", path.display()),
+        ] {
+            let project = Project::from_standalone_repository(path, &[]).unwrap();
+            assert_eq!(expected, project.name, "{}", path);
         }
 
-        (messages, new_sentinels)
+        let bare_path =
+            std::env::temp_dir().join(format!("resume-mirror-{}.git", std::process::id()));
+        let _ = std::fs::remove_dir_all(&bare_path);
+        let bare = Repository::init_bare(&bare_path).unwrap();
+        bare.remote("origin", path.to_str().unwrap())
+            .unwrap()
+            .fetch(&["+refs/heads/*:refs/heads/*"], None, None)
+            .unwrap();
+        let project =
+            Project::from_standalone_repository(bare_path.to_str().unwrap(), &[]).unwrap();
+        let branches = project.list_branches().unwrap();
+
+        std::fs::remove_dir_all(&path).unwrap();
+        std::fs::remove_dir_all(&bare_path).unwrap();
+        assert_eq!(
+            format!("resume-mirror-{}", std::process::id()),
+            project.name
+        );
+        assert_eq!(vec![BranchName::from("master".to_string())], branches);
+        assert_eq!("/", standalone_name(Path::new("/")));
+    }
+
+    #[test]
+    fn test_author_filter() {
+        let jane = Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let john = Signature::now("John Roe", "john@example.com").unwrap();
+        let path = init_repository_with_signatures(
+            "author",
+            &[
+                ("feat: login\n\nTeam: web", jane.clone(), jane.clone()),
+                ("fix: crash\n\nTeam: web", john.clone(), jane.clone()),
+                ("docs: readme\n\nTeam: api", jane.clone(), john),
+            ],
+        );
+        let mut project = Project::from_standalone_repository(
+            path.to_str().unwrap(),
+            &["master".parse().unwrap()],
+        )
+        .unwrap();
+        let summaries = |project: &Project| -> Vec<String> {
+            let walker = project.build_walker("master", &Sentinels::new()).unwrap();
+            project
+                .extract_messages(
+                    &"origin".to_string().into(),
+                    &"master".parse().unwrap(),
+                    walker,
+                )
+                .unwrap()
+                .entries
+                .iter()
+                .map(|entry| entry.as_ref().summary.clone())
+                .collect()
+        };
+
+        project.options.author = vec!["jane@example.com".to_string()];
+        let by_jane = summaries(&project);
+        project.options.author = vec!["jane@example.com".to_string(), "John Roe".to_string()];
+        let by_jane_or_john = summaries(&project);
+        project.options.author = vec!["Jane".to_string()];
+        project.team_filter.teams = vec!["web".to_string()];
+        let by_jane_in_web = summaries(&project);
+
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(vec!["readme", "login"], by_jane);
+        assert_eq!(vec!["readme", "crash", "login"], by_jane_or_john);
+        assert_eq!(vec!["login"], by_jane_in_web);
     }
 }
@@ -1,10 +1,32 @@
-use std::str::FromStr;
+pub mod slack;
 
-use crate::error::{Error, Result};
+use std::{io::Write, path::Path, str::FromStr};
+
+use serde::Serialize;
+use tera::Context;
+
+use crate::{
+    changelog::{ChangeLog, ChangeLogStats},
+    error::{Error, Result},
+    period::Period,
+};
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum OutputType {
     Yaml,
+    Template,
+    /// Indented and colored output for terminals
+    Pretty,
+    /// Counts of the entries, in YAML
+    Stats,
+    /// Markdown release block following [Keep a Changelog](https://keepachangelog.com)
+    KeepAChangelog,
+    /// Nested sections and lists, to embed in a web page
+    Html,
+    /// One row per entry, to analyze in a spreadsheet
+    Csv,
+    /// Like `Csv`, tab-separated
+    Tsv,
 }
 
 impl FromStr for OutputType {
@@ -13,7 +35,171 @@ impl FromStr for OutputType {
     fn from_str(s: &str) -> Result<Self> {
         match s {
             "yaml" => Ok(OutputType::Yaml),
+            "template" => Ok(OutputType::Template),
+            "pretty" => Ok(OutputType::Pretty),
+            "stats" => Ok(OutputType::Stats),
+            "keep-a-changelog" => Ok(OutputType::KeepAChangelog),
+            "html" => Ok(OutputType::Html),
+            "csv" => Ok(OutputType::Csv),
+            "tsv" => Ok(OutputType::Tsv),
             _ => Err(Error::OutputType(s.to_string())),
         }
     }
 }
+
+/// Information about how a report's changelog was built
+#[derive(Debug, Default, Serialize)]
+pub struct ReportMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<Period>,
+    /// Group-by fields removed because all the entries share the same key
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flattened: Vec<String>,
+    /// The run wasn't allowed to fetch or to write anything
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Number of walked commits, rendered by the stats output only
+    #[serde(skip)]
+    pub scanned: Option<usize>,
+    /// Branches whose traversal stopped at `--max-commits`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<String>,
+}
+
+impl ReportMeta {
+    pub fn is_empty(&self) -> bool {
+        self.period.is_none()
+            && self.flattened.is_empty()
+            && !self.read_only
+            && self.truncated.is_empty()
+    }
+}
+
+/// Counts of a report's changelog, along with its meta block
+#[derive(Serialize)]
+struct StatsReport<'a> {
+    #[serde(skip_serializing_if = "is_empty_meta")]
+    meta: &'a ReportMeta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scanned: Option<usize>,
+    #[serde(flatten)]
+    stats: ChangeLogStats,
+}
+
+fn is_empty_meta(meta: &&ReportMeta) -> bool {
+    meta.is_empty()
+}
+
+/// Changelog along with its meta block
+#[derive(Serialize)]
+pub struct Report<'a> {
+    pub meta: ReportMeta,
+    pub changes: &'a ChangeLog,
+    /// Render the YAML as the `meta` block and the `changes`, instead of the changelog only
+    #[serde(skip)]
+    pub with_meta: bool,
+    /// Render the entries' bodies in the Keep a Changelog output
+    #[serde(skip)]
+    pub with_bodies: bool,
+}
+
+impl<'a> Report<'a> {
+    /// Render the report as YAML: the changelog only, or the `meta` block and the `changes`
+    /// with `with_meta`, whatever the meta information.
+    pub fn to_yaml(&self) -> Result<String> {
+        let mut output = Vec::new();
+        self.write_yaml(&mut output)?;
+        Ok(String::from_utf8(output).expect("unfailable"))
+    }
+
+    /// Write the YAML of [`Report::to_yaml`] to `writer` as it's serialized
+    pub fn write_yaml<W: Write>(&self, writer: W) -> Result<()> {
+        if self.with_meta {
+            Ok(serde_yaml::to_writer(writer, self)?)
+        } else {
+            self.changes.write_yaml(writer)
+        }
+    }
+
+    /// Render the counts of the changelog as YAML, with the number of scanned commits
+    pub fn to_stats(&self) -> Result<String> {
+        let report = StatsReport {
+            meta: &self.meta,
+            scanned: self.meta.scanned,
+            stats: self.changes.stats(),
+        };
+        Ok(serde_yaml::to_string(&report)?)
+    }
+
+    /// Render the report for terminals, colored if `colors` is set
+    pub fn to_pretty(&self, colors: bool) -> String {
+        let changes = self.changes.to_pretty(colors);
+        match &self.meta.period {
+            Some(period) => format!("{} → {}\n\n{}", period.start, period.end, changes),
+            None => changes,
+        }
+    }
+
+    /// Render the report as a Keep a Changelog release, titled with its period if sliced
+    pub fn to_keep_a_changelog(&self) -> String {
+        let title = match &self.meta.period {
+            Some(period) => format!("[{} → {}]", period.start, period.end),
+            None => "[Unreleased]".to_owned(),
+        };
+        self.changes.to_keep_a_changelog(&title, self.with_bodies)
+    }
+
+    /// Render the report as HTML, headed by its period if sliced
+    pub fn to_html(&self) -> String {
+        let changes = self.changes.to_html();
+        match &self.meta.period {
+            Some(period) => format!("<h1>{} → {}</h1>\n{}", period.start, period.end, changes),
+            None => changes,
+        }
+    }
+
+    /// Render the report's entries as CSV, or TSV with a tab `delimiter`, without meta block
+    pub fn to_csv(&self, delimiter: u8) -> Result<String> {
+        self.changes.to_csv(delimiter)
+    }
+
+    pub fn write_csv<W: Write>(&self, delimiter: u8, writer: W) -> Result<()> {
+        self.changes.write_csv(delimiter, writer)
+    }
+
+    /// Render the report with the given Tera template, which receives the `meta` block too
+    pub fn to_template(&self, path: &Path) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("meta", &self.meta);
+        self.changes.render_template(path, context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::CommitField;
+
+    #[test]
+    fn test_yaml_shape() {
+        let change_log = ChangeLog::new(vec![CommitField::CommitType]);
+        let bare = change_log.to_yaml().unwrap();
+        let mut report = Report {
+            meta: ReportMeta {
+                read_only: true,
+                ..ReportMeta::default()
+            },
+            changes: &change_log,
+            with_meta: false,
+            with_bodies: false,
+        };
+        assert_eq!(bare, report.to_yaml().unwrap());
+
+        report.with_meta = true;
+        let wrapped = report.to_yaml().unwrap();
+        report.meta = ReportMeta::default();
+        let wrapped_empty = report.to_yaml().unwrap();
+        assert_eq!("---\nmeta:\n  read_only: true\nchanges: {}\n", wrapped);
+        assert_eq!("---\nmeta: {}\nchanges: {}\n", wrapped_empty);
+    }
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashSet, time::Duration};
+
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+
+use crate::{
+    changelog::ChangeLogEntry,
+    error::Result,
+    message::{CommitScope, ConventionalMessage},
+    ordering::COMMIT_TYPES_PRIORITY,
+    report::Report,
+};
+
+/// Maximum length of a section's text accepted by Slack
+const MAX_SECTION_LENGTH: usize = 3000;
+
+/// Abandon the webhook call after this delay
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Build the Slack message of the report: its title, then a section per commit type listing
+/// each commit once, whatever the grouping, the breaking changes first
+pub fn payload(report: &Report) -> Value {
+    let title = match &report.meta.period {
+        Some(period) => format!("Changes {} → {}", period.start, period.end),
+        None => "Changes".to_owned(),
+    };
+    let change_log = report.changes;
+
+    let mut sections: IndexMap<&str, Vec<String>> = COMMIT_TYPES_PRIORITY
+        .iter()
+        .map(|ctype| (ctype.as_str(), Vec::new()))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut entries: Vec<_> = change_log
+        .entries()
+        .filter(|entry| match entry.hash() {
+            Some(hash) => seen.insert(hash),
+            None => true,
+        })
+        .collect();
+    entries.sort_by_key(|entry| !entry.as_ref().is_breaking);
+    for entry in entries {
+        let message: &ConventionalMessage = entry.as_ref();
+        sections
+            .entry(message.ctype.as_str())
+            .or_default()
+            .push(render_entry(entry, message));
+    }
+
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": {"type": "plain_text", "text": title},
+    })];
+    for (ctype, lines) in sections.iter().filter(|(_, lines)| !lines.is_empty()) {
+        let mut text = format!("*{}*", escape(change_log.type_title(ctype)));
+        for (index, line) in lines.iter().enumerate() {
+            let more = format!("\n… and {} more", lines.len() - index);
+            if text.len() + 1 + line.len() + more.len() > MAX_SECTION_LENGTH {
+                text.push_str(&more);
+                break;
+            }
+            text.push('\n');
+            text.push_str(line);
+        }
+        blocks.push(json!({
+            "type": "section",
+            "text": {"type": "mrkdwn", "text": text},
+        }));
+    }
+    json!({ "text": title, "blocks": blocks })
+}
+
+/// Render the entry as a bullet, like `• :warning: *BREAKING* api: add endpoint`, its
+/// summary linked to the commit if possible
+fn render_entry(entry: &ChangeLogEntry, message: &ConventionalMessage) -> String {
+    let mut line = "• ".to_owned();
+    if message.is_breaking {
+        line.push_str(":warning: *BREAKING* ");
+    }
+    if !message.scopes.is_empty() {
+        let scopes: Vec<_> = message.scopes.iter().map(CommitScope::as_str).collect();
+        line.push_str(&format!("_{}:_ ", escape(&scopes.join(","))));
+    }
+    match entry.url() {
+        Some(url) => line.push_str(&format!("<{}|{}>", url, escape(&message.summary))),
+        None => line.push_str(&escape(&message.summary)),
+    }
+    line
+}
+
+/// Escape the control characters of Slack's `mrkdwn` text
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Post the message to the Slack incoming webhook, failing on error responses
+pub fn post(webhook: &str, payload: &Value) -> Result<()> {
+    reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()?
+        .post(webhook)
+        .json(payload)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::{commit_metadata, ChangeLog, CommitField};
+    use crate::report::ReportMeta;
+
+    #[test]
+    fn test_payload() {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope]);
+        for (hash, raw_message) in &[
+            ("1a2b", "fix(ui): escape <input> & output"),
+            ("3c4d", "feat(api,db): export"),
+            ("5e6f", "feat!: drop v1"),
+            ("7a8b", "chore: bump"),
+        ] {
+            let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+            metadata.hash = Some(hash.to_string().into());
+            let url = format!("https://example.com/commit/{}", hash);
+            change_log
+                .insert(
+                    ChangeLogEntry::new(
+                        "origin".to_string().into(),
+                        "master".to_string().into(),
+                        metadata,
+                        raw_message.parse().unwrap(),
+                    )
+                    .with_url(Some(url).filter(|_| *hash != "7a8b")),
+                )
+                .unwrap();
+        }
+        let report = Report {
+            meta: ReportMeta::default(),
+            changes: &change_log,
+            with_meta: false,
+            with_bodies: false,
+        };
+
+        let payload = payload(&report);
+        assert_eq!("Changes", payload["text"]);
+        let texts: Vec<_> = payload["blocks"].as_array().unwrap()[1..]
+            .iter()
+            .map(|block| block["text"]["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                "*Features*\n\
+                 • :warning: *BREAKING* <https://example.com/commit/5e6f|drop v1>\n\
+                 • _api,db:_ <https://example.com/commit/3c4d|export>",
+                "*Bug Fixes*\n\
+                 • _ui:_ <https://example.com/commit/1a2b|escape &lt;input&gt; &amp; output>",
+                "*chore*\n• bump",
+            ],
+            texts
+        );
+    }
+
+    #[test]
+    fn test_post_failure() {
+        let error = post("http://127.0.0.1:1/hooks", &json!({"text": "Changes"})).unwrap_err();
+        assert!(matches!(error, crate::Error::Http(_)));
+        assert_eq!("HTTP request failed", error.to_string());
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(!source.contains("/hooks"), "{}", source);
+    }
+}
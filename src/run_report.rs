@@ -0,0 +1,318 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    changelog::{
+        ChangeLog, ChangeLogEntry, CommitField, GroupByOverrides, KeyDescriptions, ProjectNames,
+        TypeTitles,
+    },
+    context::RunContext,
+    error::Result,
+    keep_a_changelog::SectionMapping,
+    project::BranchTraversal,
+    snapshots::BranchName,
+};
+
+/// Entries extracted by a run, saved to render them again without touching the repositories
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RunReport {
+    pub entries: Vec<ChangeLogEntry>,
+    #[serde(default)]
+    pub key_descriptions: KeyDescriptions,
+    #[serde(default, alias = "type_labels")]
+    pub type_titles: TypeTitles,
+    /// Order of the commit type groups, their priority if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_order: Vec<String>,
+    #[serde(default)]
+    pub sections: SectionMapping,
+    /// Group-by fields of the projects overriding them, by origin
+    #[serde(default, skip_serializing_if = "GroupByOverrides::is_empty")]
+    pub group_by_overrides: GroupByOverrides,
+    /// Names of the projects, by origin
+    #[serde(default, skip_serializing_if = "ProjectNames::is_empty")]
+    pub project_names: ProjectNames,
+    /// Number of walked commits, whether they made an entry or not
+    #[serde(default)]
+    pub scanned: Option<usize>,
+    /// Branches whose traversal stopped at `--max-commits`, as `project (branch)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<String>,
+}
+
+impl RunReport {
+    /// Add the entries and the counts of a branch's traversal
+    pub fn add_traversal(
+        &mut self,
+        project: &str,
+        branch_name: &BranchName,
+        traversal: BranchTraversal,
+    ) {
+        self.entries.extend(traversal.entries);
+        self.scanned = Some(self.scanned.unwrap_or(0) + traversal.scanned);
+        if traversal.truncated {
+            self.truncated
+                .push(format!("{} ({})", project, branch_name));
+        }
+    }
+
+    /// Build an empty changelog grouped by the given fields, with the report's key
+    /// descriptions, type labels, sections, group-by overrides and project names
+    pub fn new_change_log(&self, group_by: Vec<CommitField>) -> ChangeLog {
+        ChangeLog::new(group_by)
+            .with_key_descriptions(self.key_descriptions.clone())
+            .with_type_titles(self.type_titles.clone())
+            .with_type_order(self.type_order.clone())
+            .with_sections(self.sections.clone())
+            .with_group_by_overrides(self.group_by_overrides.clone())
+            .with_project_names(self.project_names.clone())
+    }
+
+    /// Build the changelog of the report's entries grouped by the given fields
+    pub fn into_change_log(mut self, group_by: Vec<CommitField>) -> Result<ChangeLog> {
+        let entries = std::mem::take(&mut self.entries);
+        let mut change_log = self.new_change_log(group_by);
+        for entry in entries {
+            change_log.insert(entry)?;
+        }
+        Ok(change_log)
+    }
+
+    /// Add the entries and the counts of another report, keeping these descriptions and labels
+    pub fn merge(&mut self, other: RunReport) {
+        self.entries.extend(other.entries);
+        if let Some(scanned) = other.scanned {
+            self.scanned = Some(self.scanned.unwrap_or(0) + scanned);
+        }
+        self.truncated.extend(other.truncated);
+    }
+
+    /// Keep only the first entry of each commit, or of each commit of each branch when
+    /// `per_branch` is set. Entries without hash are all kept.
+    pub fn dedup(&mut self, per_branch: bool) {
+        let mut seen = HashSet::new();
+        self.entries.retain(|entry| match entry.hash() {
+            Some(hash) => {
+                let branch = if per_branch {
+                    entry.get(&CommitField::Branch).to_owned()
+                } else {
+                    String::new()
+                };
+                seen.insert((hash.clone(), branch))
+            }
+            None => true,
+        });
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        log::info!("load run report from file: {:?}", path.as_ref());
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, path: P, context: &RunContext) -> Result<()> {
+        log::info!("save run report file: {:?}", path.as_ref());
+        context.check_write(path.as_ref())?;
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(serde_json::to_writer(writer, &self)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::commit_metadata;
+    use crate::snapshots::CommitHash;
+
+    fn build_entries() -> Vec<ChangeLogEntry> {
+        [
+            "feat(api)!: new endpoint\n\nCo-authored-by: John Roe <john@example.com>",
+            "fix: billing\n\nTeam: core",
+            "chore: bump",
+        ]
+        .iter()
+        .map(|raw_message| {
+            ChangeLogEntry::new(
+                "origin".to_string().into(),
+                "master".to_string().into(),
+                commit_metadata("2021-09-01T12:00:00+02:00"),
+                raw_message.parse().unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    /// Entries with their commit hashes and links, like the ones of a traversal
+    fn build_traversed_entries() -> Vec<ChangeLogEntry> {
+        ["feat(api)!: new endpoint", "fix: billing\n\nTeam: core"]
+            .iter()
+            .enumerate()
+            .map(|(index, raw_message)| {
+                let hash = format!("{:040x}", index + 1);
+                let mut commit = commit_metadata("2021-09-01T12:00:00+02:00");
+                commit.hash = Some(CommitHash::from(hash.clone()));
+                ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    commit,
+                    raw_message.parse().unwrap(),
+                )
+                .with_url(Some(format!("https://example.com/commit/{}", hash)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "resume-test-run-report-{}.json",
+            std::process::id()
+        ));
+        let mut type_titles = TypeTitles::new();
+        type_titles.insert("feat".to_string(), "Features".to_string());
+        let report = RunReport {
+            entries: build_traversed_entries(),
+            key_descriptions: KeyDescriptions::new(),
+            type_titles,
+            type_order: Vec::new(),
+            sections: SectionMapping::new(),
+            group_by_overrides: GroupByOverrides::new(),
+            project_names: ProjectNames::new(),
+            scanned: Some(3),
+            truncated: vec!["sample (master)".to_string()],
+        };
+        report.to_file(&path, &RunContext::default()).unwrap();
+        let loaded = RunReport::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(report.entries, loaded.entries);
+        assert_eq!(Some(3), loaded.scanned);
+        assert_eq!(report.truncated, loaded.truncated);
+        assert_eq!(report.type_titles, loaded.type_titles);
+    }
+
+    #[test]
+    fn test_into_change_log() {
+        let mut type_titles = TypeTitles::new();
+        type_titles.insert("feat".to_string(), "Features".to_string());
+        let report = RunReport {
+            entries: build_entries(),
+            type_titles,
+            ..RunReport::default()
+        };
+
+        let change_log = report
+            .into_change_log(vec![CommitField::CommitType])
+            .unwrap();
+
+        assert_eq!(3, change_log.stats().counts.total);
+        assert!(change_log.to_pretty(false).starts_with("Features\n"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut report = RunReport::default();
+        report.add_traversal(
+            "sample",
+            &"master".to_string().into(),
+            BranchTraversal {
+                entries: build_entries(),
+                sentinels: Default::default(),
+                scanned: 10,
+                truncated: true,
+            },
+        );
+        let mut other = RunReport::default();
+        other.add_traversal(
+            "other",
+            &"develop".to_string().into(),
+            BranchTraversal {
+                entries: build_entries(),
+                sentinels: Default::default(),
+                scanned: 4,
+                truncated: false,
+            },
+        );
+        report.merge(other);
+
+        assert_eq!(6, report.entries.len());
+        assert_eq!(Some(14), report.scanned);
+        assert_eq!(vec!["sample (master)".to_string()], report.truncated);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let entry = |hash: Option<&str>, branch: &str| {
+            let mut metadata = commit_metadata("2021-09-01T12:00:00+02:00");
+            metadata.hash = hash.map(|hash| hash.to_string().into());
+            ChangeLogEntry::new(
+                "origin".to_string().into(),
+                branch.to_string().into(),
+                metadata,
+                "fix: shared".parse().unwrap(),
+            )
+        };
+        let report = RunReport {
+            entries: vec![
+                entry(Some("a1"), "release/1"),
+                entry(Some("a1"), "release/2"),
+                entry(Some("b2"), "release/2"),
+                entry(Some("a1"), "release/2"),
+                entry(None, "release/1"),
+                entry(None, "release/1"),
+            ],
+            ..RunReport::default()
+        };
+        let branches = |report: &RunReport| -> Vec<String> {
+            report
+                .entries
+                .iter()
+                .map(|entry| {
+                    let hash = entry.hash().map_or("", CommitHash::as_str);
+                    format!("{}@{}", hash, entry.get(&CommitField::Branch))
+                })
+                .collect()
+        };
+
+        let mut deduped = report.clone();
+        deduped.dedup(false);
+        assert_eq!(
+            vec!["a1@release/1", "b2@release/2", "@release/1", "@release/1"],
+            branches(&deduped)
+        );
+
+        let mut deduped = report;
+        deduped.dedup(true);
+        assert_eq!(
+            vec![
+                "a1@release/1",
+                "a1@release/2",
+                "b2@release/2",
+                "@release/1",
+                "@release/1"
+            ],
+            branches(&deduped)
+        );
+    }
+
+    #[test]
+    fn test_read_only_forbids_saving() {
+        let path = std::env::temp_dir().join(format!(
+            "resume-test-read-only-run-report-{}.json",
+            std::process::id()
+        ));
+        let report = RunReport {
+            entries: build_entries(),
+            ..RunReport::default()
+        };
+        let result = report.to_file(&path, &RunContext { read_only: true });
+        assert_eq!(path, crate::context::violated_path(result));
+        assert!(!path.exists());
+    }
+}
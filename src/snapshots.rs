@@ -8,12 +8,16 @@ use std::{
 };
 
 use blake3::{Hash, Hasher};
+use chrono::{DateTime, Duration, Utc};
 use git2::Oid;
 use serde::{Deserialize, Serialize};
 
-use crate::error::{Error, Result};
+use crate::{
+    context::RunContext,
+    error::{Error, Result},
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct CommitHash(String);
 
 impl CommitHash {
@@ -21,17 +25,52 @@ impl CommitHash {
         self.0.as_str()
     }
 
+    /// Abbreviated hash, as displayed by `git log --oneline`
+    pub fn short(&self) -> &str {
+        &self.0[..self.0.len().min(7)]
+    }
+
+    /// Check that the hash is a full SHA-1 or SHA-256 hexadecimal hash
+    pub fn is_valid(&self) -> bool {
+        (self.0.len() == 40 || self.0.len() == 64) && self.0.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Validate the hash of the given branch's head
+    pub fn validate(&self, origin: &RepositoryOrigin, branch: &BranchName) -> Result<()> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(Error::InvalidCommitHash(
+                origin.clone(),
+                branch.clone(),
+                self.clone(),
+            ))
+        }
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
 }
 
+impl From<String> for CommitHash {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
 impl From<Oid> for CommitHash {
     fn from(oid: Oid) -> Self {
         Self(oid.to_string())
     }
 }
 
+impl fmt::Display for CommitHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Ord, PartialOrd)]
 pub struct BranchName(String);
 
@@ -65,7 +104,7 @@ impl fmt::Display for BranchName {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Ord, PartialOrd)]
 pub struct RepositoryOrigin(String);
 
 impl RepositoryOrigin {
@@ -107,6 +146,12 @@ impl From<String> for SnapshotHash {
     }
 }
 
+impl fmt::Display for SnapshotHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_str())
+    }
+}
+
 impl FromStr for SnapshotHash {
     type Err = ();
 
@@ -115,7 +160,8 @@ impl FromStr for SnapshotHash {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Snapshots of the previous runs, from the oldest to the most recent one
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SnapshotHistory {
     snapshots: Vec<Snapshot>,
 }
@@ -126,12 +172,20 @@ impl SnapshotHash {
     }
 }
 
+/// Heads of the traversed branches of each repository, where the next run stops
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Snapshot {
     hash: SnapshotHash,
+    /// Missing from the snapshots saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    /// Name given with `--label`, to refer to the snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
     repositories: BTreeMap<RepositoryOrigin, RepositorySnapshot>,
 }
 
+#[derive(Default)]
 pub struct SnapshotBuilder {
     repositories: BTreeMap<RepositoryOrigin, RepositorySnapshot>,
 }
@@ -150,14 +204,14 @@ impl SnapshotHistory {
         match File::open(path) {
             Ok(file) => {
                 let reader = BufReader::new(file);
-                Ok(serde_yaml::from_reader(reader)?)
+                let history: Self = serde_yaml::from_reader(reader)?;
+                history.validate()?;
+                Ok(history)
             }
             Err(error) => {
                 if error.kind() == std::io::ErrorKind::NotFound {
                     log::info!("snapshot file doesn't exist");
-                    Ok(Self {
-                        snapshots: Vec::new(),
-                    })
+                    Ok(Self::new())
                 } else {
                     Err(Error::from(error))
                 }
@@ -165,37 +219,157 @@ impl SnapshotHistory {
         }
     }
 
-    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    /// Check that all the snapshots' commit hashes are well-formed
+    pub fn validate(&self) -> Result<()> {
+        for snapshot in &self.snapshots {
+            for (origin, branches) in &snapshot.repositories {
+                for (branch_name, head) in branches {
+                    head.validate(origin, branch_name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, path: P, context: &RunContext) -> Result<()> {
         log::info!("save snapshot file: {:?}", path.as_ref());
+        context.check_write(path.as_ref())?;
         let file = File::create(path)?;
         let writer = BufWriter::new(file);
         Ok(serde_yaml::to_writer(writer, &self)?)
     }
 
+    /// Iterate over the snapshots with their index, from the most recent one
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &Snapshot)> {
+        self.snapshots.iter().rev().enumerate()
+    }
+
     pub fn last(&self) -> Option<&Snapshot> {
         self.snapshots.last()
     }
 
     pub fn get_by_hash(&self, hash: &SnapshotHash) -> Option<&Snapshot> {
-        for snapshot in self.snapshots.iter().rev() {
-            if &snapshot.hash == hash {
-                return Some(snapshot);
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| &snapshot.hash == hash)
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Option<&Snapshot> {
+        self.snapshots.get(self.position_of_index(index)?)
+    }
+
+    /// Get the snapshot a reference points to: an index counted from the most recent snapshot,
+    /// `last` or `last~N`, the N-th before last, a hash or a label
+    pub fn resolve(&self, reference: &str) -> Result<&Snapshot> {
+        Ok(&self.snapshots[self.resolve_position(reference)?])
+    }
+
+    /// Remove the snapshot a reference points to, see [`SnapshotHistory::resolve`]
+    pub fn remove(&mut self, reference: &str) -> Result<Snapshot> {
+        let position = self.resolve_position(reference)?;
+        Ok(self.snapshots.remove(position))
+    }
+
+    /// Get the position in the history of the snapshot a reference points to
+    fn resolve_position(&self, reference: &str) -> Result<usize> {
+        let invalid = || Error::InvalidSnapshotRef(reference.to_owned());
+        let index = match reference.strip_prefix("last") {
+            Some("") => Some(0),
+            Some(relative) if relative.starts_with('~') => {
+                Some(relative[1..].parse().map_err(|_| invalid())?)
             }
+            _ => reference.parse().ok(),
+        };
+        let hash = reference.to_owned().into();
+        let position = match index {
+            Some(index) => self.position_of_index(index),
+            None => self
+                .snapshots
+                .iter()
+                .rposition(|snapshot| snapshot.hash == hash)
+                .or_else(|| {
+                    self.snapshots
+                        .iter()
+                        .rposition(|snapshot| snapshot.label.as_deref() == Some(reference))
+                }),
+        };
+        position.ok_or_else(|| Error::SnapshotDoesntExist(reference.to_owned()))
+    }
+
+    /// Get the most recent snapshot with the given label
+    pub fn get_by_label(&self, label: &str) -> Option<&Snapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.label.as_deref() == Some(label))
+    }
+
+    pub fn remove_by_hash(&mut self, hash: &SnapshotHash) -> Option<Snapshot> {
+        let position = self
+            .snapshots
+            .iter()
+            .rposition(|snapshot| &snapshot.hash == hash)?;
+        Some(self.snapshots.remove(position))
+    }
+
+    pub fn remove_by_index(&mut self, index: usize) -> Option<Snapshot> {
+        let position = self.position_of_index(index)?;
+        Some(self.snapshots.remove(position))
+    }
+
+    /// Keep only the `max` most recent snapshots
+    pub fn prune(&mut self, max: usize) {
+        let excess = self.snapshots.len().saturating_sub(max);
+        self.snapshots.drain(..excess);
+    }
+
+    /// Convert an index, counted from the most recent snapshot, to a position in the history
+    fn position_of_index(&self, index: usize) -> Option<usize> {
+        self.snapshots.len().checked_sub(index)?.checked_sub(1)
+    }
+
+    /// Check that the most recent snapshot may be amended: it must be labelless and created
+    /// less than `max_age` before `now`
+    pub fn check_amendable(&self, max_age: Duration, now: DateTime<Utc>) -> Result<()> {
+        let last = match self.last() {
+            Some(last) => last,
+            None => return Ok(()),
+        };
+        if let Some(label) = &last.label {
+            return Err(Error::NotAmendable(format!("it's labeled '{}'", label)));
+        }
+        match last.created_at {
+            Some(created_at) if now - created_at <= max_age => Ok(()),
+            Some(_) => Err(Error::NotAmendable(format!(
+                "it's older than {} hour(s)",
+                max_age.num_hours()
+            ))),
+            None => Err(Error::NotAmendable(
+                "its creation date is unknown".to_owned(),
+            )),
         }
-        None
     }
 
-    pub fn get_by_index(&self, index: usize) -> Option<&Snapshot> {
-        self.snapshots.get(self.snapshots.len() - index - 1)
+    /// Merge the given snapshot into the most recent one, instead of appending it.
+    /// Repositories of the given snapshot replace the existing ones, the others are kept.
+    pub fn amend(&mut self, snapshot: Snapshot) {
+        match self.snapshots.last_mut() {
+            Some(last) => last.merge(snapshot),
+            None => self.snapshots.push(snapshot),
+        }
     }
 
+    /// Append the snapshot, unless the most recent one has the same heads. The latter then
+    /// takes the snapshot's label, if any.
     pub fn push(&mut self, snapshot: Snapshot) {
-        if self
-            .last()
-            .map(|last| last.hash != snapshot.hash)
-            .unwrap_or(true)
-        {
-            self.snapshots.push(snapshot);
+        match self.snapshots.last_mut() {
+            Some(last) if last.hash == snapshot.hash => {
+                if snapshot.label.is_some() {
+                    last.label = snapshot.label;
+                }
+            }
+            _ => self.snapshots.push(snapshot),
         }
     }
 }
@@ -204,6 +378,38 @@ impl Snapshot {
     pub fn get(&self, origin: &RepositoryOrigin) -> Option<&RepositorySnapshot> {
         self.repositories.get(origin)
     }
+
+    pub fn hash(&self) -> &SnapshotHash {
+        &self.hash
+    }
+
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn with_label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn repositories_count(&self) -> usize {
+        self.repositories.len()
+    }
+
+    /// Replace the repositories' snapshots by the other's ones and recompute the hash.
+    /// The timestamp becomes the other's one, and so does the label if it has one.
+    pub fn merge(&mut self, other: Snapshot) {
+        self.created_at = other.created_at;
+        if other.label.is_some() {
+            self.label = other.label;
+        }
+        self.repositories.extend(other.repositories);
+        self.hash = compute_hash(&self.repositories);
+    }
 }
 
 impl SnapshotBuilder {
@@ -222,18 +428,250 @@ impl SnapshotBuilder {
     }
 
     pub fn build(self) -> Snapshot {
-        let mut hasher = Hasher::new();
-        for (origin, branches) in &self.repositories {
-            hasher.update(origin.as_bytes());
-            for (branch_name, head) in branches {
-                hasher.update(branch_name.as_bytes());
-                hasher.update(head.as_bytes());
+        Snapshot {
+            hash: compute_hash(&self.repositories),
+            created_at: Some(Utc::now()),
+            label: None,
+            repositories: self.repositories,
+        }
+    }
+}
+
+fn compute_hash(repositories: &BTreeMap<RepositoryOrigin, RepositorySnapshot>) -> SnapshotHash {
+    let mut hasher = Hasher::new();
+    for (origin, branches) in repositories {
+        hasher.update(origin.as_bytes());
+        for (branch_name, head) in branches {
+            hasher.update(branch_name.as_bytes());
+            hasher.update(head.as_bytes());
+        }
+    }
+    SnapshotHash::from_hash(hasher.finalize())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_snapshot(repositories: &[(&str, &str, &str)]) -> Snapshot {
+        let mut builder = SnapshotBuilder::new();
+        let mut grouped: BTreeMap<RepositoryOrigin, RepositorySnapshot> = BTreeMap::new();
+        for (origin, branch, head) in repositories {
+            grouped
+                .entry(origin.to_string().into())
+                .or_default()
+                .insert(branch.to_string().into(), CommitHash(head.to_string()));
+        }
+        for (origin, repo_snapshot) in grouped {
+            builder.add_repository_snapshot(origin, repo_snapshot);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_amend_merges_into_last_snapshot() {
+        let mut history = SnapshotHistory::new();
+        history.push(build_snapshot(&[("a", "master", "1")]));
+        history.push(build_snapshot(&[
+            ("a", "master", "2"),
+            ("b", "master", "3"),
+        ]));
+
+        history.amend(build_snapshot(&[("b", "master", "4"), ("c", "dev", "5")]));
+
+        let expected = build_snapshot(&[
+            ("a", "master", "2"),
+            ("b", "master", "4"),
+            ("c", "dev", "5"),
+        ]);
+        let last = history.last().unwrap();
+        assert_eq!(2, history.snapshots.len());
+        assert_eq!(expected.hash, last.hash);
+        assert_eq!(expected.repositories, last.repositories);
+        assert!(last.created_at >= history.snapshots[0].created_at);
+    }
+
+    #[test]
+    fn test_amend_empty_history() {
+        let mut history = SnapshotHistory::new();
+        let snapshot = build_snapshot(&[("a", "master", "1")]);
+
+        history.amend(snapshot.clone());
+
+        assert_eq!(vec![snapshot], history.snapshots);
+    }
+
+    #[test]
+    fn test_validate_history() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut history = SnapshotHistory::new();
+        history.push(build_snapshot(&[("a", "master", hash)]));
+        assert!(history.validate().is_ok());
+
+        history.push(build_snapshot(&[
+            ("a", "master", hash),
+            ("b", "dev", "0123g"),
+        ]));
+        match history.validate() {
+            Err(Error::InvalidCommitHash(origin, branch, head)) => {
+                assert_eq!("b", origin.as_str());
+                assert_eq!("dev", branch.as_str());
+                assert_eq!("0123g", head.as_str());
             }
+            _ => panic!("malformed hash must be reported"),
         }
+    }
 
-        Snapshot {
-            hash: SnapshotHash::from_hash(hasher.finalize()),
-            repositories: self.repositories,
+    fn build_history(count: usize) -> SnapshotHistory {
+        let mut history = SnapshotHistory::new();
+        for i in 0..count {
+            history.push(build_snapshot(&[("a", "master", &i.to_string())]));
         }
+        history
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut history = build_history(3);
+        let last = history.last().cloned();
+
+        history.prune(5);
+        assert_eq!(3, history.snapshots.len());
+
+        history.prune(1);
+        assert_eq!(1, history.snapshots.len());
+        assert_eq!(last.as_ref(), history.last());
+
+        history.prune(0);
+        assert!(history.snapshots.is_empty());
+    }
+
+    #[test]
+    fn test_remove_by_index() {
+        let mut history = build_history(3);
+        let expected = history.get_by_index(1).cloned();
+
+        assert_eq!(None, history.remove_by_index(3));
+        assert_eq!(None, history.get_by_index(usize::MAX));
+        assert_eq!(expected, history.remove_by_index(1));
+        assert_eq!(2, history.snapshots.len());
+    }
+
+    #[test]
+    fn test_remove_by_hash() {
+        let mut history = build_history(3);
+        let expected = history.get_by_index(2).cloned().unwrap();
+
+        assert_eq!(
+            Some(expected.clone()),
+            history.remove_by_hash(&expected.hash)
+        );
+        assert_eq!(None, history.remove_by_hash(&expected.hash));
+        assert_eq!(2, history.snapshots.len());
+    }
+
+    #[test]
+    fn test_read_only_state() {
+        let path = std::env::temp_dir().join(format!("resume-ro-state-{}", std::process::id()));
+        let context = RunContext { read_only: true };
+        let result = build_history(1).to_file(&path, &context);
+        assert_eq!(path, crate::context::violated_path(result));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_created_at_is_optional() {
+        let yaml = "snapshots:\n  - hash: abc\n    repositories: {}\n";
+        let history: SnapshotHistory = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(None, history.last().unwrap().created_at());
+
+        let snapshot = build_snapshot(&[("a", "master", "1")]);
+        let yaml = serde_yaml::to_string(&snapshot).unwrap();
+        let deserialized: Snapshot = serde_yaml::from_str(&yaml).unwrap();
+        assert!(deserialized.created_at().is_some());
+        assert_eq!(snapshot, deserialized);
+    }
+
+    #[test]
+    fn test_label() {
+        let mut history = build_history(2);
+        let labeled = build_snapshot(&[("a", "master", "1")]).with_label(Some("v1".to_owned()));
+        let hash = labeled.hash.clone();
+
+        // same heads as the most recent snapshot: it's labeled instead of duplicated
+        history.push(labeled);
+        assert_eq!(2, history.snapshots.len());
+        assert_eq!(Some("v1"), history.last().unwrap().label());
+        history.push(build_snapshot(&[("a", "master", "1")]));
+        assert_eq!(Some("v1"), history.last().unwrap().label());
+
+        history.push(build_snapshot(&[("a", "master", "2")]));
+        assert_eq!(Some(&hash), history.get_by_label("v1").map(Snapshot::hash));
+        assert_eq!(None, history.get_by_label("v2"));
+
+        // a merged snapshot without label keeps the most recent one's
+        history.push(build_snapshot(&[("a", "master", "3")]).with_label(Some("v2".to_owned())));
+        history.amend(build_snapshot(&[("b", "master", "4")]));
+        assert_eq!(Some("v2"), history.last().unwrap().label());
+        history.amend(build_snapshot(&[("b", "master", "5")]).with_label(Some("v3".to_owned())));
+        assert_eq!(Some("v3"), history.last().unwrap().label());
+    }
+
+    #[test]
+    fn test_check_amendable() {
+        let max_age = Duration::hours(24);
+        let mut history = SnapshotHistory::new();
+        assert!(history.check_amendable(max_age, Utc::now()).is_ok());
+
+        history.push(build_snapshot(&[("a", "master", "1")]));
+        let created_at = *history.last().unwrap().created_at().unwrap();
+        assert!(history.check_amendable(max_age, created_at).is_ok());
+        let later = created_at + Duration::hours(25);
+        assert!(matches!(
+            history.check_amendable(max_age, later),
+            Err(Error::NotAmendable(_))
+        ));
+
+        history.push(build_snapshot(&[("a", "master", "2")]).with_label(Some("v1".to_owned())));
+        assert!(matches!(
+            history.check_amendable(max_age, Utc::now()),
+            Err(Error::NotAmendable(reason)) if reason.contains("v1")
+        ));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut history = build_history(3);
+        history.push(build_snapshot(&[("a", "master", "3")]).with_label(Some("v1".to_owned())));
+        let hash_of = |reference: &str| history.resolve(reference).map(|s| s.hash.clone());
+
+        let last = history.last().unwrap().hash.clone();
+        let first = history.snapshots[0].hash.clone();
+        assert_eq!(last, hash_of("last").unwrap());
+        assert_eq!(last, hash_of("0").unwrap());
+        assert_eq!(last, hash_of("v1").unwrap());
+        assert_eq!(history.snapshots[2].hash, hash_of("last~1").unwrap());
+        assert_eq!(first, hash_of("last~3").unwrap());
+        assert_eq!(first, hash_of("3").unwrap());
+        assert_eq!(first, hash_of(&first.to_string()).unwrap());
+
+        for invalid in &["last~", "last~x", "last~-1"] {
+            assert!(
+                matches!(hash_of(invalid), Err(Error::InvalidSnapshotRef(_))),
+                "{}",
+                invalid
+            );
+        }
+        for missing in &["last~4", "4", "v2", "lastly"] {
+            assert!(
+                matches!(hash_of(missing), Err(Error::SnapshotDoesntExist(_))),
+                "{}",
+                missing
+            );
+        }
+
+        let removed = history.remove("last~1").unwrap();
+        assert_eq!(3, history.snapshots.len());
+        assert!(history.get_by_hash(&removed.hash).is_none());
     }
 }
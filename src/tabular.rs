@@ -0,0 +1,136 @@
+use std::{collections::HashSet, io::Write};
+
+use serde::Serialize;
+
+use crate::{
+    changelog::{ChangeLogEntry, CommitField},
+    error::Result,
+    message::{CommitScope, ConventionalMessage},
+};
+
+/// Flat view of an entry, one column per field
+#[derive(Serialize)]
+struct Row<'a> {
+    origin: &'a str,
+    branch: &'a str,
+    commit_type: &'a str,
+    /// Scopes of the message, comma-separated
+    scope: String,
+    breaking: bool,
+    summary: &'a str,
+    author: &'a str,
+    date: String,
+    hash: &'a str,
+}
+
+/// Render the entries as a table with a header row, one row per entry whatever the grouping,
+/// its fields separated by `delimiter`. Fields containing the delimiter, quotes or newlines
+/// are quoted.
+pub fn render<'a>(
+    entries: impl Iterator<Item = &'a ChangeLogEntry>,
+    delimiter: u8,
+) -> Result<String> {
+    let mut output = Vec::new();
+    write(entries, delimiter, &mut output)?;
+    Ok(String::from_utf8(output).expect("unfailable"))
+}
+
+/// Write the table of [`render`] to `output` as the rows are built
+pub fn write<'a, W: Write>(
+    entries: impl Iterator<Item = &'a ChangeLogEntry>,
+    delimiter: u8,
+    output: W,
+) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(output);
+    // entries with several scopes are in several buckets, write them once
+    let mut seen = HashSet::new();
+    for entry in entries {
+        let origin = entry.get(&CommitField::Origin);
+        let branch = entry.get(&CommitField::Branch);
+        let hash = entry.hash().map_or("", |hash| hash.as_str());
+        if !hash.is_empty() && !seen.insert((origin, branch, hash)) {
+            continue;
+        }
+        let message: &ConventionalMessage = entry.as_ref();
+        let scopes: Vec<_> = message.scopes.iter().map(CommitScope::as_str).collect();
+        writer.serialize(Row {
+            origin,
+            branch,
+            commit_type: message.ctype.as_str(),
+            scope: scopes.join(","),
+            breaking: message.is_breaking,
+            summary: &message.summary,
+            author: entry.get(&CommitField::Author),
+            date: entry.date().to_rfc3339(),
+            hash,
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::{ChangeLog, CommitMetadata};
+    use crate::message::Author;
+
+    fn build_change_log() -> ChangeLog {
+        let mut change_log = ChangeLog::new(vec![CommitField::Scope]);
+        let author = Author {
+            name: "Jane Doe".to_string(),
+            email: Some("jane@example.com".to_string()),
+        };
+        for (hash, raw_message) in &[
+            ("1a2b3c4d", "feat(api,ui)!: add \"bulk\" export, with CSV"),
+            ("5e6f7a8b", "fix: crash\n\nOn startup."),
+        ] {
+            let metadata = CommitMetadata {
+                hash: Some(hash.to_string().into()),
+                date: "2021-09-01T12:00:00+02:00".parse().unwrap(),
+                author: author.clone(),
+                committer: author.clone(),
+            };
+            change_log
+                .insert(ChangeLogEntry::new(
+                    "origin".to_string().into(),
+                    "master".to_string().into(),
+                    metadata,
+                    raw_message.parse().unwrap(),
+                ))
+                .unwrap();
+        }
+        change_log
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let output = build_change_log().to_csv(b',').unwrap();
+        let expected = "\
+origin,branch,commit_type,scope,breaking,summary,author,date,hash
+origin,master,feat,\"api,ui\",true,\"add \"\"bulk\"\" export, with CSV\",Jane Doe,2021-09-01T12:00:00+02:00,1a2b3c4d
+origin,master,fix,,false,crash,Jane Doe,2021-09-01T12:00:00+02:00,5e6f7a8b
+";
+        assert_eq!(expected, output);
+
+        let mut reader = csv::Reader::from_reader(output.as_bytes());
+        let rows: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+        assert_eq!(2, rows.len());
+        assert_eq!("add \"bulk\" export, with CSV", &rows[0][5]);
+    }
+
+    #[test]
+    fn test_render_tsv() {
+        let output = build_change_log().to_csv(b'\t').unwrap();
+        assert_eq!(
+            "origin\tbranch\tcommit_type\tscope\tbreaking\tsummary\tauthor\tdate\thash",
+            output.lines().next().unwrap()
+        );
+        assert_eq!(
+            "origin\tmaster\tfix\t\tfalse\tcrash\tJane Doe\t2021-09-01T12:00:00+02:00\t5e6f7a8b",
+            output.lines().nth(2).unwrap()
+        );
+    }
+}
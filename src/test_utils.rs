@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use git2::{Repository, Signature};
+
+/// Create a repository with a `master` branch made of the given commits' messages
+pub fn init_repository(name: &str, messages: &[&str]) -> PathBuf {
+    let signature = Signature::now("Jane Doe", "jane@example.com").unwrap();
+    let commits: Vec<_> = messages
+        .iter()
+        .map(|message| (*message, signature.clone(), signature.clone()))
+        .collect();
+    init_repository_with_signatures(name, &commits)
+}
+
+/// Create a repository with a `master` branch made of the given commits'
+/// messages, authors and committers
+pub fn init_repository_with_signatures(
+    name: &str,
+    commits: &[(&str, Signature, Signature)],
+) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("resume-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    let repository = Repository::init(&path).unwrap();
+    let tree_id = repository.index().unwrap().write_tree().unwrap();
+    let tree = repository.find_tree(tree_id).unwrap();
+    let mut parent = None;
+    for (message, author, committer) in commits {
+        let parents: Vec<_> = parent.iter().collect();
+        let oid = repository
+            .commit(None, author, committer, message, &tree, &parents)
+            .unwrap();
+        parent = Some(repository.find_commit(oid).unwrap());
+    }
+    repository
+        .branch("master", parent.as_ref().unwrap(), true)
+        .unwrap();
+    path
+}
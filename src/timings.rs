@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use crate::snapshots::BranchName;
+
+/// Wall-clock durations of the steps of a project's processing
+#[derive(Debug, Default, Clone)]
+pub struct ProjectTimings {
+    /// Time spent to open the cached clone, or to clone the repository
+    pub clone: Duration,
+    /// Time spent to fetch the branches, all of them being fetched at once
+    pub fetch: Duration,
+    /// Time spent to walk each branch
+    pub walks: Vec<(BranchName, Duration)>,
+}
+
+impl ProjectTimings {
+    /// Total time spent to walk the branches
+    pub fn walk(&self) -> Duration {
+        self.walks.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.clone + self.fetch + self.walk()
+    }
+
+    /// Summary of the timings, like "done in 3m12s (fetch 2m58s, walk 14s)"
+    pub fn summary(&self) -> String {
+        format!(
+            "done in {} (fetch {}, walk {})",
+            format_duration(self.total()),
+            format_duration(self.fetch),
+            format_duration(self.walk())
+        )
+    }
+}
+
+/// Run `f` and add its wall-clock duration to `duration`
+pub fn timed<T>(duration: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *duration += start.elapsed();
+    result
+}
+
+/// Format a duration for humans: "350ms", "14s", "3m12s" or "1h02m03s"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => format!("{}ms", duration.as_millis()),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread::sleep;
+
+    use super::*;
+
+    #[test]
+    fn test_timed_aggregation() {
+        let mut timings = ProjectTimings::default();
+        timed(&mut timings.clone, || sleep(Duration::from_millis(5)));
+        timed(&mut timings.fetch, || sleep(Duration::from_millis(10)));
+        timed(&mut timings.fetch, || sleep(Duration::from_millis(10)));
+        for branch in &["master", "develop"] {
+            let mut walk = Duration::default();
+            let count = timed(&mut walk, || {
+                sleep(Duration::from_millis(15));
+                42
+            });
+            assert_eq!(42, count);
+            timings.walks.push((branch.to_string().into(), walk));
+        }
+
+        assert!(timings.fetch >= Duration::from_millis(20));
+        assert!(timings.walk() >= Duration::from_millis(30));
+        assert_eq!(
+            timings.clone + timings.fetch + timings.walk(),
+            timings.total()
+        );
+        assert!(timings.total() >= Duration::from_millis(55));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!("350ms", format_duration(Duration::from_millis(350)));
+        assert_eq!("14s", format_duration(Duration::from_millis(14_800)));
+        assert_eq!("3m12s", format_duration(Duration::from_secs(192)));
+        assert_eq!("1h02m03s", format_duration(Duration::from_secs(3723)));
+    }
+
+    #[test]
+    fn test_summary() {
+        let timings = ProjectTimings {
+            clone: Duration::from_secs(0),
+            fetch: Duration::from_secs(178),
+            walks: vec![
+                ("master".to_string().into(), Duration::from_secs(10)),
+                ("develop".to_string().into(), Duration::from_secs(4)),
+            ],
+        };
+        assert_eq!("done in 3m12s (fetch 2m58s, walk 14s)", timings.summary());
+    }
+}
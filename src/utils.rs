@@ -1,25 +1,51 @@
 use std::env;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::fs::{read_dir, remove_dir_all};
+use std::path::{Path, PathBuf};
 
 use blake3::hash;
 
+use crate::context::RunContext;
+use crate::error::Result;
+use crate::parse_cache::PARSE_CACHE_FOLDER_NAME;
 use crate::snapshots::RepositoryOrigin;
 
+#[cfg(not(any(unix, windows)))]
+compile_error!("no cache folder is known for this platform");
+
+/// Get the user's cache folder of résumé
+pub fn get_cache_folder() -> PathBuf {
+    platform_cache_folder(|name| env::var_os(name)).join("resume")
+}
+
+/// Get the user's cache folder, given how to read the environment variables
 #[cfg(target_os = "macos")]
-fn get_cache_folder() -> PathBuf {
-    PathBuf::from(format!(
-        "{}/Library/Caches/resume",
-        env::var("HOME").unwrap(),
-    ))
+fn platform_cache_folder(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    home_folder(&var).join("Library").join("Caches")
 }
 
-#[cfg(target_os = "linux")]
-pub fn get_cache_folder() -> PathBuf {
-    let mut path = PathBuf::from(
-        env::var("XDG_CACHE_HOME").unwrap_or(format!("{}/.cache", env::var("HOME").unwrap())),
-    );
-    path.push("resume");
-    path
+/// Get the user's cache folder, given how to read the environment variables
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_cache_folder(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    var("XDG_CACHE_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_folder(&var).join(".cache"))
+}
+
+/// Get the user's cache folder, given how to read the environment variables
+#[cfg(windows)]
+fn platform_cache_folder(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    var("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .expect("the LOCALAPPDATA environment variable must be set")
+}
+
+#[cfg(unix)]
+fn home_folder(var: &impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    var("HOME")
+        .map(PathBuf::from)
+        .expect("the HOME environment variable must be set")
 }
 
 /// Get the user's cache folder where store the given repository origin
@@ -28,3 +54,146 @@ pub fn get_repo_cache_folder(origin: &RepositoryOrigin) -> PathBuf {
     path.push(hash(origin.as_bytes()).to_string());
     path
 }
+
+/// Remove the cached clones of the repositories not in the given origins.
+/// Return the removed folders, or the folders to remove on dry run.
+pub fn prune_cache(
+    origins: &[RepositoryOrigin],
+    dry_run: bool,
+    context: &RunContext,
+) -> Result<Vec<PathBuf>> {
+    prune_cache_folder(&get_cache_folder(), origins, dry_run, context)
+}
+
+fn prune_cache_folder(
+    cache_folder: &Path,
+    origins: &[RepositoryOrigin],
+    dry_run: bool,
+    context: &RunContext,
+) -> Result<Vec<PathBuf>> {
+    if !cache_folder.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let expected: Vec<String> = origins
+        .iter()
+        .map(|origin| hash(origin.as_bytes()).to_string())
+        .collect();
+
+    let mut pruned = Vec::new();
+    for entry in read_dir(cache_folder)? {
+        let entry = entry?;
+        // never follow symbolic links out of the cache folder
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if expected.contains(&name) || name == PARSE_CACHE_FOLDER_NAME {
+            continue;
+        }
+
+        let path = entry.path();
+        if !dry_run {
+            log::info!("remove cached repository: {:?}", path);
+            context.check_write(&path)?;
+            remove_dir_all(&path)?;
+        }
+        pruned.push(path);
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn test_platform_cache_folder() {
+        let var = |name: &str| match name {
+            "HOME" => Some(OsString::from("/home/jane")),
+            "LOCALAPPDATA" => Some(OsString::from(r"C:\Users\jane\AppData\Local")),
+            _ => None,
+        };
+        let folder = platform_cache_folder(var);
+
+        #[cfg(target_os = "macos")]
+        assert_eq!(Path::new("/home/jane/Library/Caches"), folder);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            assert_eq!(Path::new("/home/jane/.cache"), folder);
+            let xdg = |name: &str| match name {
+                "XDG_CACHE_HOME" => Some(OsString::from("/tmp/cache")),
+                _ => var(name),
+            };
+            assert_eq!(Path::new("/tmp/cache"), platform_cache_folder(xdg));
+        }
+        #[cfg(windows)]
+        assert_eq!(Path::new(r"C:\Users\jane\AppData\Local"), folder);
+
+        let origin: RepositoryOrigin = "git@example.com:user/repository.git".to_string().into();
+        let repo_folder = get_repo_cache_folder(&origin);
+        assert_eq!(Some(get_cache_folder().as_path()), repo_folder.parent());
+        assert!(get_cache_folder().ends_with("resume"));
+    }
+
+    #[test]
+    fn test_prune_cache_folder() {
+        let cache_folder =
+            env::temp_dir().join(format!("resume-pruned-cache-{}", std::process::id()));
+        let kept: RepositoryOrigin = "git@example.com:kept.git".to_string().into();
+        let removed: RepositoryOrigin = "git@example.com:removed.git".to_string().into();
+        for origin in &[&kept, &removed] {
+            create_dir_all(cache_folder.join(hash(origin.as_bytes()).to_string())).unwrap();
+        }
+        write(cache_folder.join("file"), "not a repository").unwrap();
+        let removed_folder = cache_folder.join(hash(removed.as_bytes()).to_string());
+        let origins = vec![kept.clone()];
+
+        let dry_run =
+            prune_cache_folder(&cache_folder, &origins, true, &RunContext::default()).unwrap();
+        let still_there = removed_folder.exists();
+        let pruned =
+            prune_cache_folder(&cache_folder, &origins, false, &RunContext::default()).unwrap();
+        let kept_exists = cache_folder
+            .join(hash(kept.as_bytes()).to_string())
+            .exists();
+        let file_exists = cache_folder.join("file").exists();
+
+        remove_dir_all(&cache_folder).unwrap();
+        assert_eq!(vec![removed_folder.clone()], dry_run);
+        assert!(still_there);
+        assert_eq!(vec![removed_folder], pruned);
+        assert!(kept_exists);
+        assert!(file_exists);
+    }
+
+    #[test]
+    fn test_prune_missing_cache_folder() {
+        let cache_folder =
+            env::temp_dir().join(format!("resume-missing-cache-{}", std::process::id()));
+        assert!(
+            prune_cache_folder(&cache_folder, &[], false, &RunContext::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_only_prune_cache_folder() {
+        let cache_folder = env::temp_dir().join(format!("resume-ro-cache-{}", std::process::id()));
+        let removed = cache_folder.join("unconfigured");
+        create_dir_all(&removed).unwrap();
+        let context = RunContext { read_only: true };
+
+        let dry_run = prune_cache_folder(&cache_folder, &[], true, &context);
+        let result = prune_cache_folder(&cache_folder, &[], false, &context);
+        let still_there = removed.exists();
+
+        remove_dir_all(&cache_folder).unwrap();
+        assert_eq!(vec![removed.clone()], dry_run.unwrap());
+        assert_eq!(removed, crate::context::violated_path(result));
+        assert!(still_there);
+    }
+}